@@ -0,0 +1,75 @@
+//! Compares evaluation of the same formula from the string, from the parsed tree
+//! and from the compiled program, run with `cargo bench --bench compile`
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use eval::{Dialect, Environment, Evaluator, ParserConfig};
+
+const FORMULA: &str = "price * quantity * (1 + tax) - min(discount, price * quantity / 10)";
+
+/// Prices the formula is evaluated with, so the result isn't the same every iteration
+fn prices() -> impl Iterator<Item = f64> {
+    (0..100).map(f64::from).cycle()
+}
+
+fn evaluation(c: &mut Criterion) {
+    let config = ParserConfig::standard().with_dialect(Dialect::standard());
+    let mut env = Environment::new()
+        .with_variable("price", 0.0)
+        .with_variable("quantity", 3.0)
+        .with_variable("tax", 0.2)
+        .with_variable("discount", 5.0);
+    let mut group = c.benchmark_group("evaluation");
+
+    group.bench_function("string", |b| {
+        let mut prices = prices();
+
+        b.iter(|| {
+            env.set("price", prices.next().unwrap());
+            eval::eval_with_env_and_config(FORMULA, &env, &config).unwrap()
+        })
+    });
+
+    let evaluator = Evaluator::new();
+    let parsed = eval::parse_with_config(FORMULA, &config).unwrap();
+
+    group.bench_function("tree", |b| {
+        let mut prices = prices();
+
+        b.iter(|| {
+            env.set("price", prices.next().unwrap());
+            evaluator.eval_with(&parsed, &env).unwrap()
+        })
+    });
+
+    let compiled = eval::compile_with_config(FORMULA, &config).unwrap();
+
+    group.bench_function("compiled env", |b| {
+        let mut prices = prices();
+
+        b.iter(|| {
+            env.set("price", prices.next().unwrap());
+            compiled.eval_with(&env).unwrap()
+        })
+    });
+
+    let price = compiled.slot("price").unwrap();
+    let mut slots = compiled
+        .variables()
+        .iter()
+        .map(|name| env.get(name).unwrap())
+        .collect::<Vec<_>>();
+
+    group.bench_function("compiled", |b| {
+        let mut prices = prices();
+
+        b.iter(|| {
+            slots[price] = prices.next().unwrap();
+            compiled.eval(&slots).unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, evaluation);
+criterion_main!(benches);
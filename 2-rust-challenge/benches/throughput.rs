@@ -0,0 +1,67 @@
+//! Measures throughput of tokenizing, parsing and evaluating the batch of formulas,
+//! so the regressions of every stage are visible, run with `cargo bench --bench throughput`
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use eval::{
+    tokens::{RawTokenIterator, TokenIterator},
+    Dialect, Evaluator, ExprParser, ParserConfig,
+};
+
+const LINES: u32 = 2_000;
+
+fn stages(c: &mut Criterion) {
+    let config = ParserConfig::standard().with_dialect(Dialect::standard());
+    let dialect = config.dialect();
+    let lines = (0..LINES)
+        .map(|i| format!("({i} + 0.5) * 3 - max({i}, 12345678901234567890) / 7 ^ 2 % 11"))
+        .collect::<Vec<_>>();
+    let bytes = lines.iter().map(String::len).sum::<usize>();
+    let mut group = c.benchmark_group("batch");
+
+    group.throughput(Throughput::Bytes(bytes as u64));
+
+    group.bench_function("raw tokens", |b| {
+        b.iter(|| {
+            lines
+                .iter()
+                .map(|line| RawTokenIterator::with_dialect(line, dialect).count())
+                .sum::<usize>()
+        })
+    });
+
+    group.bench_function("tokens", |b| {
+        b.iter(|| {
+            lines
+                .iter()
+                .map(|line| TokenIterator::with_dialect(line, dialect).count())
+                .sum::<usize>()
+        })
+    });
+
+    let parser = ExprParser::new(config.clone());
+
+    group.bench_function("parse", |b| {
+        b.iter(|| {
+            lines
+                .iter()
+                .filter(|line| parser.parse_str(line).is_ok())
+                .count()
+        })
+    });
+
+    let evaluator = Evaluator::new();
+
+    group.bench_function("eval", |b| {
+        b.iter(|| {
+            lines
+                .iter()
+                .filter(|line| evaluator.eval(&parser.parse_str(line).unwrap()).is_ok())
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, stages);
+criterion_main!(benches);
@@ -0,0 +1,840 @@
+//! Module with the arbitrary-precision integers
+//! that back the exact evaluation
+
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Display},
+    hash::{Hash, Hasher},
+    ops::{Add, Deref, DerefMut, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+/// Largest power of 10 that fits into the limb
+const DECIMAL_BASE: u32 = 1_000_000_000;
+const DECIMAL_DIGITS: usize = 9;
+
+/// Signed integer of any size stored as the magnitude in base `2^32`
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    /// Limbs from the least significant one, without leading zeros
+    magnitude: Limbs,
+}
+
+/// Limbs of the magnitude, up to two of them are kept inline,
+/// so the integers of 64 bits, i.e. the most of the literals, don't allocate
+#[derive(Clone)]
+enum Limbs {
+    Inline { len: u8, limbs: [u32; 2] },
+    Heap(Vec<u32>),
+}
+
+impl Limbs {
+    fn push(&mut self, limb: u32) {
+        match self {
+            Self::Inline { len, limbs } if usize::from(*len) < limbs.len() => {
+                limbs[usize::from(*len)] = limb;
+                *len += 1;
+            }
+            Self::Inline { .. } => {
+                let mut heap = self.to_vec();
+
+                heap.push(limb);
+                *self = Self::Heap(heap);
+            }
+            Self::Heap(heap) => heap.push(limb),
+        }
+    }
+
+    /// Drops the leading zeros
+    fn trim(&mut self) {
+        match self {
+            Self::Inline { len, limbs } => {
+                while *len > 0 && limbs[usize::from(*len) - 1] == 0 {
+                    *len -= 1;
+                }
+            }
+            Self::Heap(heap) => trim(heap),
+        }
+    }
+}
+
+impl Default for Limbs {
+    fn default() -> Self {
+        Self::Inline {
+            len: 0,
+            limbs: [0; 2],
+        }
+    }
+}
+
+impl From<Vec<u32>> for Limbs {
+    fn from(value: Vec<u32>) -> Self {
+        match value.as_slice() {
+            [] => Self::default(),
+            [low] => Self::Inline {
+                len: 1,
+                limbs: [*low, 0],
+            },
+            [low, high] => Self::Inline {
+                len: 2,
+                limbs: [*low, *high],
+            },
+            _ => Self::Heap(value),
+        }
+    }
+}
+
+impl Deref for Limbs {
+    type Target = [u32];
+
+    fn deref(&self) -> &[u32] {
+        match self {
+            Self::Inline { len, limbs } => &limbs[..usize::from(*len)],
+            Self::Heap(heap) => heap,
+        }
+    }
+}
+
+impl DerefMut for Limbs {
+    fn deref_mut(&mut self) -> &mut [u32] {
+        match self {
+            Self::Inline { len, limbs } => &mut limbs[..usize::from(*len)],
+            Self::Heap(heap) => heap,
+        }
+    }
+}
+
+/// Limbs are compared by the value, whether they are inline or not
+impl PartialEq for Limbs {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for Limbs {}
+
+impl Hash for Limbs {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
+}
+
+impl Debug for Limbs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseBigIntError;
+
+impl Display for ParseBigIntError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid digit found in the integer")
+    }
+}
+
+impl std::error::Error for ParseBigIntError {}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn one() -> Self {
+        Self::from(1u32)
+    }
+
+    fn from_parts(negative: bool, magnitude: impl Into<Limbs>) -> Self {
+        let mut magnitude = magnitude.into();
+
+        magnitude.trim();
+
+        Self {
+            negative: negative && !magnitude.is_empty(),
+            magnitude,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_empty()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> Self {
+        Self::from_parts(false, self.magnitude.clone())
+    }
+
+    /// Number of significant bits of the magnitude
+    pub fn bits(&self) -> u64 {
+        match self.magnitude.last() {
+            Some(top) => self.magnitude.len() as u64 * 32 - u64::from(top.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    /// Divides with the quotient rounded toward zero,
+    /// so the remainder has the sign of the dividend
+    pub fn checked_div_rem(&self, divisor: &Self) -> Option<(Self, Self)> {
+        if divisor.is_zero() {
+            return None;
+        }
+
+        let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &divisor.magnitude);
+
+        Some((
+            Self::from_parts(self.negative != divisor.negative, quotient),
+            Self::from_parts(self.negative, remainder),
+        ))
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> Self {
+        let mut base = self.clone();
+        let mut result = Self::one();
+
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = &result * &base;
+            }
+
+            exponent >>= 1;
+
+            if exponent > 0 {
+                base = &base * &base;
+            }
+        }
+
+        result
+    }
+
+    /// Greatest common divisor, that is always non-negative
+    pub fn gcd(&self, other: &Self) -> Self {
+        let mut a = self.abs();
+        let mut b = other.abs();
+
+        while !b.is_zero() {
+            let (_, remainder) = a.checked_div_rem(&b).expect("divisor is not zero");
+
+            a = b;
+            b = remainder;
+        }
+
+        a
+    }
+
+    /// Square root of the magnitude rounded down
+    pub fn sqrt(&self) -> Self {
+        let magnitude = self.abs();
+
+        if magnitude.is_zero() {
+            return magnitude;
+        }
+
+        // starts above the root, so Newton's iterations decrease toward it
+        let mut root = Self::one().shl(self.bits().div_ceil(2));
+
+        loop {
+            let (quotient, _) = magnitude.checked_div_rem(&root).expect("root is not zero");
+            let (next, _) = (&root + &quotient)
+                .checked_div_rem(&Self::from(2))
+                .expect("divisor is not zero");
+
+            if next >= root {
+                return root;
+            }
+
+            root = next;
+        }
+    }
+
+    /// Multiplies by `2^bits`
+    pub fn shl(&self, bits: u64) -> Self {
+        if self.is_zero() {
+            return self.clone();
+        }
+
+        let limbs = (bits / 32) as usize;
+        let mut magnitude = vec![0; limbs];
+
+        magnitude.extend(shl_bits(&self.magnitude, (bits % 32) as u32));
+
+        Self::from_parts(self.negative, magnitude)
+    }
+
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.bits() > 128 {
+            return None;
+        }
+
+        let magnitude = self
+            .magnitude
+            .iter()
+            .rev()
+            .fold(0u128, |acc, &limb| (acc << 32) | u128::from(limb));
+
+        if self.negative {
+            // magnitude of `i128::MIN` doesn't fit into the positive range
+            (magnitude <= 1 << 127).then(|| (magnitude as i128).wrapping_neg())
+        } else {
+            i128::try_from(magnitude).ok()
+        }
+    }
+
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_i128().and_then(|value| i64::try_from(value).ok())
+    }
+
+    /// Floating point number that is closest to the integer up to the last bit of the mantissa
+    pub fn to_f64(&self) -> f64 {
+        let bits = self.bits();
+        // keeps 64 significant bits that are enough for the mantissa
+        let shift = bits.saturating_sub(64);
+        let (top, _) = div_rem_magnitude(&self.magnitude, &Self::one().shl(shift).magnitude);
+        let value = top
+            .iter()
+            .rev()
+            .fold(0f64, |acc, &limb| acc * 4_294_967_296.0 + f64::from(limb))
+            * 2f64.powi(shift.min(i32::MAX as u64) as i32);
+
+        if self.negative {
+            -value
+        } else {
+            value
+        }
+    }
+}
+
+/// Value of the ASCII digit of any radix up to 36, that is out of range for other bytes
+fn digit_value(digit: u8) -> u32 {
+    match digit {
+        b'0'..=b'9' => u32::from(digit - b'0'),
+        b'a'..=b'z' => u32::from(digit - b'a') + 10,
+        b'A'..=b'Z' => u32::from(digit - b'A') + 10,
+        _ => u32::MAX,
+    }
+}
+
+fn trim(magnitude: &mut Vec<u32>) {
+    while magnitude.last() == Some(&0) {
+        magnitude.pop();
+    }
+}
+
+fn compare_magnitude(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let (long, short) = if left.len() >= right.len() {
+        (left, right)
+    } else {
+        (right, left)
+    };
+    let mut result = Vec::with_capacity(long.len() + 1);
+    let mut carry = 0u64;
+
+    for (i, &limb) in long.iter().enumerate() {
+        let sum = u64::from(limb) + u64::from(short.get(i).copied().unwrap_or(0)) + carry;
+
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+
+    result
+}
+
+/// Subtracts the smaller magnitude from the larger one
+fn sub_magnitude(larger: &[u32], smaller: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(larger.len());
+    let mut borrow = 0i64;
+
+    for (i, &limb) in larger.iter().enumerate() {
+        let diff = i64::from(limb) - i64::from(smaller.get(i).copied().unwrap_or(0)) - borrow;
+
+        result.push(diff as u32);
+        borrow = i64::from(diff < 0);
+    }
+
+    trim(&mut result);
+    result
+}
+
+fn mul_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    if left.is_empty() || right.is_empty() {
+        return vec![];
+    }
+
+    let mut result = vec![0u32; left.len() + right.len()];
+
+    for (i, &a) in left.iter().enumerate() {
+        let mut carry = 0u64;
+
+        for (j, &b) in right.iter().enumerate() {
+            let product = u64::from(a) * u64::from(b) + u64::from(result[i + j]) + carry;
+
+            result[i + j] = product as u32;
+            carry = product >> 32;
+        }
+
+        result[i + right.len()] = carry as u32;
+    }
+
+    trim(&mut result);
+    result
+}
+
+/// Shifts left by less than 32 bits keeping the carried out limb
+fn shl_bits(magnitude: &[u32], bits: u32) -> Vec<u32> {
+    let mut result = Vec::with_capacity(magnitude.len() + 1);
+
+    if bits == 0 {
+        result.extend_from_slice(magnitude);
+        result.push(0);
+        return result;
+    }
+
+    let mut carry = 0;
+
+    for &limb in magnitude {
+        result.push((limb << bits) | carry);
+        carry = limb >> (32 - bits);
+    }
+
+    result.push(carry);
+    result
+}
+
+fn div_rem_small(magnitude: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; magnitude.len()];
+    let mut remainder = 0u64;
+
+    for (i, &limb) in magnitude.iter().enumerate().rev() {
+        let current = (remainder << 32) | u64::from(limb);
+
+        quotient[i] = (current / u64::from(divisor)) as u32;
+        remainder = current % u64::from(divisor);
+    }
+
+    trim(&mut quotient);
+    (quotient, remainder as u32)
+}
+
+/// Long division from Knuth's "The Art of Computer Programming", algorithm D
+fn div_rem_magnitude(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if compare_magnitude(dividend, divisor) == Ordering::Less {
+        return (vec![], dividend.to_vec());
+    }
+
+    if let [divisor] = divisor {
+        let (quotient, remainder) = div_rem_small(dividend, *divisor);
+        let mut remainder = vec![remainder];
+
+        trim(&mut remainder);
+        return (quotient, remainder);
+    }
+
+    const BASE: u64 = 1 << 32;
+
+    let n = divisor.len();
+    let m = dividend.len() - n;
+    // normalizes the divisor, so its top limb has the highest bit set
+    let shift = divisor[n - 1].leading_zeros();
+    let v = &shl_bits(divisor, shift)[..n];
+    let mut u = shl_bits(dividend, shift);
+    let mut quotient = vec![0u32; m + 1];
+
+    for j in (0..=m).rev() {
+        let top = (u64::from(u[j + n]) << 32) | u64::from(u[j + n - 1]);
+        let mut estimate = top / u64::from(v[n - 1]);
+        let mut rest = top % u64::from(v[n - 1]);
+
+        while estimate >= BASE
+            || estimate * u64::from(v[n - 2]) > ((rest << 32) | u64::from(u[j + n - 2]))
+        {
+            estimate -= 1;
+            rest += u64::from(v[n - 1]);
+
+            if rest >= BASE {
+                break;
+            }
+        }
+
+        let mut borrow = 0i64;
+        let mut carry = 0u64;
+
+        for i in 0..n {
+            let product = estimate * u64::from(v[i]) + carry;
+            let diff = i64::from(u[i + j]) - borrow - (product & 0xffff_ffff) as i64;
+
+            carry = product >> 32;
+            u[i + j] = diff as u32;
+            borrow = i64::from(diff < 0);
+        }
+
+        let diff = i64::from(u[j + n]) - borrow - carry as i64;
+
+        u[j + n] = diff as u32;
+
+        // estimate was one too large, so the divisor is added back
+        if diff < 0 {
+            estimate -= 1;
+
+            let mut carry = 0u64;
+
+            for i in 0..n {
+                let sum = u64::from(u[i + j]) + u64::from(v[i]) + carry;
+
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+
+        quotient[j] = estimate as u32;
+    }
+
+    let mut remainder = (0..n)
+        .map(|i| match (shift, u.get(i + 1)) {
+            (0, _) => u[i],
+            (shift, Some(&next)) if i + 1 < n => (u[i] >> shift) | (next << (32 - shift)),
+            (shift, _) => u[i] >> shift,
+        })
+        .collect::<Vec<_>>();
+
+    trim(&mut quotient);
+    trim(&mut remainder);
+    (quotient, remainder)
+}
+
+impl From<u64> for BigInt {
+    fn from(value: u64) -> Self {
+        Self::from_parts(
+            false,
+            Limbs::Inline {
+                len: 2,
+                limbs: [value as u32, (value >> 32) as u32],
+            },
+        )
+    }
+}
+
+impl From<u32> for BigInt {
+    fn from(value: u32) -> Self {
+        Self::from(u64::from(value))
+    }
+}
+
+impl From<u128> for BigInt {
+    fn from(value: u128) -> Self {
+        if let Ok(value) = u64::try_from(value) {
+            return Self::from(value);
+        }
+
+        Self::from_parts(
+            false,
+            (0..4)
+                .map(|limb| (value >> (limb * 32)) as u32)
+                .collect::<Vec<_>>(),
+        )
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(value: i128) -> Self {
+        let BigInt { magnitude, .. } = Self::from(value.unsigned_abs());
+
+        Self::from_parts(value < 0, magnitude)
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(value: i64) -> Self {
+        let BigInt { magnitude, .. } = Self::from(value.unsigned_abs());
+
+        Self::from_parts(value < 0, magnitude)
+    }
+}
+
+impl From<i32> for BigInt {
+    fn from(value: i32) -> Self {
+        Self::from(i64::from(value))
+    }
+}
+
+impl BigInt {
+    /// Parses digits of the radix from 2 to 36 with the optional sign, i.e. `-ff` in base 16
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseBigIntError> {
+        assert!((2..=36).contains(&radix), "radix should be from 2 to 36");
+
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+
+        if digits.is_empty() || !digits.bytes().all(|digit| digit_value(digit) < radix) {
+            return Err(ParseBigIntError);
+        }
+
+        // largest chunk of the digits whose value fits into the limb
+        let chunk_len = u32::MAX.ilog(radix) as usize;
+        let mut magnitude = Limbs::default();
+        // first chunk is shorter, so the rest have the exact number of digits
+        let first = digits.len() % chunk_len;
+        let chunks = std::iter::once(&digits.as_bytes()[..first])
+            .chain(digits.as_bytes()[first..].chunks(chunk_len));
+
+        for chunk in chunks.filter(|chunk| !chunk.is_empty()) {
+            let value = chunk
+                .iter()
+                .fold(0, |value, digit| value * radix + digit_value(*digit));
+            let base = u64::from(radix).pow(chunk.len() as u32);
+            let mut carry = u64::from(value);
+
+            // multiplies by the power of the radix and adds the chunk in place
+            for limb in magnitude.iter_mut() {
+                let product = u64::from(*limb) * base + carry;
+
+                *limb = product as u32;
+                carry = product >> 32;
+            }
+
+            if carry > 0 {
+                magnitude.push(carry as u32);
+            }
+        }
+
+        Ok(Self::from_parts(negative, magnitude))
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    /// Parses decimal digits with the optional sign
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_str_radix(s, 10)
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut chunks = vec![];
+        let mut magnitude = self.magnitude.to_vec();
+
+        while !magnitude.is_empty() {
+            let (quotient, remainder) = div_rem_small(&magnitude, DECIMAL_BASE);
+
+            chunks.push(remainder);
+            magnitude = quotient;
+        }
+
+        let mut digits = match chunks.pop() {
+            Some(top) => top.to_string(),
+            None => "0".to_string(),
+        };
+
+        for chunk in chunks.iter().rev() {
+            digits.push_str(&format!("{chunk:0width$}", width = DECIMAL_DIGITS));
+        }
+
+        f.pad_integral(!self.negative, "", &digits)
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitude(&self.magnitude, &other.magnitude),
+            (true, true) => compare_magnitude(&other.magnitude, &self.magnitude),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> Self::Output {
+        Self::from_parts(!self.negative, self.magnitude)
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> Self::Output {
+        -self.clone()
+    }
+}
+
+impl Add for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: Self) -> Self::Output {
+        if self.negative == other.negative {
+            return BigInt::from_parts(
+                self.negative,
+                add_magnitude(&self.magnitude, &other.magnitude),
+            );
+        }
+
+        match compare_magnitude(&self.magnitude, &other.magnitude) {
+            Ordering::Less => BigInt::from_parts(
+                other.negative,
+                sub_magnitude(&other.magnitude, &self.magnitude),
+            ),
+            _ => BigInt::from_parts(
+                self.negative,
+                sub_magnitude(&self.magnitude, &other.magnitude),
+            ),
+        }
+    }
+}
+
+impl Sub for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + &-other
+    }
+}
+
+impl Mul for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: Self) -> Self::Output {
+        BigInt::from_parts(
+            self.negative != other.negative,
+            mul_magnitude(&self.magnitude, &other.magnitude),
+        )
+    }
+}
+
+/// Implements the operator for the owned values through the references
+macro_rules! forward_owned {
+    ($($trait: ident :: $method: ident),*) => {
+        $(
+            impl $trait for BigInt {
+                type Output = BigInt;
+
+                fn $method(self, other: Self) -> Self::Output {
+                    (&self).$method(&other)
+                }
+            }
+        )*
+    };
+}
+
+forward_owned!(Add::add, Sub::sub, Mul::mul);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigInt {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!(big("0").to_string(), "0");
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(big("+42").to_string(), "42");
+        assert_eq!(
+            big("-123456789012345678901234567890").to_string(),
+            "-123456789012345678901234567890"
+        );
+        assert_eq!(big("000001000000000").to_string(), "1000000000");
+        assert_eq!(
+            &big(&"9".repeat(100)) + &BigInt::one(),
+            BigInt::from(10).pow(100)
+        );
+        assert_eq!("1a".parse::<BigInt>(), Err(ParseBigIntError));
+        assert_eq!("".parse::<BigInt>(), Err(ParseBigIntError));
+        assert_eq!(
+            BigInt::from_str_radix("-ffffffffffffffffffff", 16),
+            Ok(-(&BigInt::from(2).pow(80) - &BigInt::one()))
+        );
+        assert_eq!(BigInt::from_str_radix("101", 2), Ok(BigInt::from(5)));
+        assert_eq!(BigInt::from_str_radix("12", 2), Err(ParseBigIntError));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = big("99999999999999999999");
+        let b = big("-12345678901234567890");
+
+        assert_eq!(&a + &b, big("87654321098765432109"));
+        assert_eq!(&b - &a, big("-112345678901234567889"));
+        assert_eq!(&a * &b, big("-1234567890123456788987654321098765432110"));
+        assert_eq!(
+            BigInt::from(2).pow(100),
+            big("1267650600228229401496703205376")
+        );
+        assert_eq!(&a - &a, BigInt::zero());
+        assert!(b < a && -&a < b);
+    }
+
+    #[test]
+    fn division() {
+        let (quotient, remainder) = big("1267650600228229401496703205377")
+            .checked_div_rem(&big("18446744073709551617"))
+            .unwrap();
+
+        assert_eq!(quotient, big("68719476735"));
+        assert_eq!(remainder, big("18446744004990074882"));
+
+        let (quotient, remainder) = big("-7").checked_div_rem(&big("2")).unwrap();
+
+        assert_eq!((quotient, remainder), (big("-3"), big("-1")));
+        assert_eq!(big("1").checked_div_rem(&BigInt::zero()), None);
+        assert_eq!(big("-12").gcd(&big("18")), big("6"));
+        assert_eq!(BigInt::from(2).pow(100).sqrt(), BigInt::from(2).pow(50));
+        assert_eq!(big("99999999999999999999").sqrt(), big("9999999999"));
+        assert_eq!(big("3").sqrt(), big("1"));
+        assert_eq!(BigInt::zero().sqrt(), BigInt::zero());
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+        assert_eq!((-BigInt::from(i64::MIN)).to_i64(), None);
+        assert_eq!(BigInt::from(-5i64).to_i64(), Some(-5));
+        assert_eq!(big("12345678901234567890123").to_i64(), None);
+        assert_eq!(BigInt::from(i128::MIN).to_i128(), Some(i128::MIN));
+        assert_eq!(
+            big("-170141183460469231731687303715884105729").to_i128(),
+            None
+        );
+        assert_eq!(big("-1024").to_f64(), -1024.0);
+        assert_eq!(BigInt::from(2).pow(200).to_f64(), 2f64.powi(200));
+        assert_eq!(big("7").shl(70), BigInt::from(7) * BigInt::from(2).pow(70));
+        // values of 64 bits are equal whether they are computed or created inline
+        assert_eq!(
+            &big("18446744073709551616") - &BigInt::one(),
+            BigInt::from(u64::MAX)
+        );
+        assert_eq!(
+            BigInt::from(u128::from(u64::MAX) + 1),
+            big("18446744073709551616")
+        );
+    }
+}
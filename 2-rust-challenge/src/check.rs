@@ -0,0 +1,430 @@
+//! Module to validate the parsed expression without evaluating it
+//! and report all of its problems at once
+
+use std::collections::HashSet;
+
+use super::{
+    diagnostic::Diagnostic,
+    eval::{check_arity, EvalError},
+    functions::Functions,
+    parse::{Expr, ExprKind},
+    span::Span,
+    tokens::Operator,
+    value::Type,
+};
+
+/// Problems found by the [Checker] in order of their position in the source
+#[derive(Debug, PartialEq)]
+pub struct CheckError {
+    errors: Vec<EvalError>,
+}
+
+impl CheckError {
+    /// All problems, there is at least one of them
+    pub fn errors(&self) -> &[EvalError] {
+        &self.errors
+    }
+
+    /// Span of the first problem
+    pub fn span(&self) -> Span {
+        self.errors[0].span()
+    }
+
+    /// Creates report of every problem pointing to its location inside the `source`
+    pub fn diagnostics<'source>(&self, source: &'source str) -> Vec<Diagnostic<'source>> {
+        self.errors
+            .iter()
+            .map(|error| Diagnostic::new(source, error, error.span()))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.errors[0])?;
+
+        match self.errors.len() {
+            1 => Ok(()),
+            count => write!(f, ", and {} more problem(s)", count - 1),
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+/// Validates arity of the operators, calls of the functions, declared variables and types
+/// of the expression, so the invalid formula is rejected before it is evaluated.
+/// Types are checked statically like in [CompiledExpr](crate::CompiledExpr),
+/// so both branches of the conditional should have the same type.
+/// Variables are only checked when they are declared
+pub struct Checker {
+    functions: Functions,
+    variables: Option<HashSet<String>>,
+    result: Option<Type>,
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Self::with_functions(Functions::builtin())
+    }
+}
+
+impl Checker {
+    /// Creates checker with [Functions::builtin] accepting any variables and result
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks the calls against the functions that are used for the evaluation
+    pub fn with_functions(functions: Functions) -> Self {
+        Self {
+            functions,
+            variables: None,
+            result: None,
+        }
+    }
+
+    /// Declares the variable, so the ones that aren't declared are reported
+    pub fn with_variable(mut self, name: impl Into<String>) -> Self {
+        self.variables
+            .get_or_insert_with(HashSet::new)
+            .insert(name.into());
+        self
+    }
+
+    /// Declares the variables, see [Checker::with_variable]
+    pub fn with_variables<Name: Into<String>>(
+        mut self,
+        names: impl IntoIterator<Item = Name>,
+    ) -> Self {
+        self.variables
+            .get_or_insert_with(HashSet::new)
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Requires the expression to produce the value of the type, i.e. the number
+    pub fn with_result(mut self, result: Type) -> Self {
+        self.result = Some(result);
+        self
+    }
+
+    /// Checks the whole expression and returns the type of its result
+    pub fn check(&self, expr: &Expr) -> Result<Type, CheckError> {
+        let mut errors = vec![];
+        let actual = match self.result {
+            Some(expected) => self.expect(expr, expected, &mut errors).then_some(expected),
+            None => self.infer(expr, &mut errors),
+        };
+
+        match actual {
+            Some(actual) if errors.is_empty() => Ok(actual),
+            _ => {
+                // nested problems are found before the mismatch of the operand containing them
+                errors.sort_by_key(|error| error.span().start);
+
+                Err(CheckError { errors })
+            }
+        }
+    }
+
+    /// Returns type of the expression or `None` when it is unknown because of the problem,
+    /// so the problem isn't reported again by the operations using the expression
+    fn infer(&self, expr: &Expr, errors: &mut Vec<EvalError>) -> Option<Type> {
+        match &expr.kind {
+            ExprKind::Number(_) => Some(Type::Number),
+            ExprKind::Variable(name) => {
+                if self
+                    .variables
+                    .as_ref()
+                    .is_some_and(|variables| !variables.contains(name))
+                {
+                    errors.push(EvalError::UnknownVariable {
+                        name: name.clone(),
+                        span: expr.span,
+                    });
+                }
+
+                Some(Type::Number)
+            }
+            ExprKind::Group(inner) => self.infer(inner, errors),
+            ExprKind::Call { name, args } => {
+                match self.functions.get(name) {
+                    None => errors.push(EvalError::UnknownFunction {
+                        name: name.clone(),
+                        span: expr.span,
+                    }),
+                    Some(function) if !function.arity().accepts(args.len()) => {
+                        errors.push(EvalError::ArgumentCount {
+                            name: name.clone(),
+                            expected: function.arity(),
+                            actual: args.len(),
+                            span: expr.span,
+                        })
+                    }
+                    Some(_) => {}
+                }
+
+                for arg in args {
+                    self.expect(arg, Type::Number, errors);
+                }
+
+                Some(Type::Number)
+            }
+            ExprKind::Unary { operator, operand } => {
+                if let Err(error) = check_arity(operator, 1, expr.span) {
+                    errors.push(error);
+                    self.infer(operand, errors);
+
+                    return None;
+                }
+
+                match operator {
+                    Operator::Not => {
+                        self.expect(operand, Type::Bool, errors);
+                        Some(Type::Bool)
+                    }
+                    _ => {
+                        self.expect(operand, Type::Number, errors);
+                        Some(Type::Number)
+                    }
+                }
+            }
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                if let Err(error) = check_arity(operator, 2, expr.span) {
+                    errors.push(error);
+                    self.infer(left, errors);
+                    self.infer(right, errors);
+
+                    return None;
+                }
+
+                match operator {
+                    Operator::And | Operator::Or => {
+                        self.expect(left, Type::Bool, errors);
+                        self.expect(right, Type::Bool, errors);
+                        Some(Type::Bool)
+                    }
+                    // booleans are only compared for the equality
+                    Operator::Eq | Operator::Ne => {
+                        self.infer_same(left, right, errors);
+                        Some(Type::Bool)
+                    }
+                    _ => {
+                        self.expect(left, Type::Number, errors);
+                        self.expect(right, Type::Number, errors);
+
+                        match operator.is_comparison() {
+                            true => Some(Type::Bool),
+                            false => Some(Type::Number),
+                        }
+                    }
+                }
+            }
+            ExprKind::Unit { operand, .. } => {
+                self.expect(operand, Type::Number, errors);
+                Some(Type::Number)
+            }
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.expect(condition, Type::Bool, errors);
+                self.infer_same(then, otherwise, errors)
+            }
+        }
+    }
+
+    /// Checks the operand that should have the type, returns whether it has it
+    fn expect(&self, expr: &Expr, expected: Type, errors: &mut Vec<EvalError>) -> bool {
+        match self.infer(expr, errors) {
+            Some(actual) if actual != expected => {
+                errors.push(EvalError::TypeMismatch {
+                    expected,
+                    actual,
+                    span: expr.span,
+                });
+
+                false
+            }
+            actual => actual.is_some(),
+        }
+    }
+
+    /// Checks that the second operand has the same type as the first one
+    fn infer_same(&self, first: &Expr, second: &Expr, errors: &mut Vec<EvalError>) -> Option<Type> {
+        match self.infer(first, errors) {
+            Some(expected) => self.expect(second, expected, errors).then_some(expected),
+            None => self.infer(second, errors),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{functions::FunctionArity, tokens::Number};
+
+    fn int(num: i32) -> Expr {
+        Expr::number(Number::Int(num.into()))
+    }
+
+    fn check(expr: &Expr) -> Result<Type, CheckError> {
+        Checker::new().with_variable("x").check(expr)
+    }
+
+    #[test]
+    fn types() {
+        let comparison = Expr::binary(Operator::Lt, Expr::variable("x"), int(1));
+
+        assert_eq!(check(&comparison), Ok(Type::Bool));
+        assert_eq!(
+            check(&Expr::conditional(
+                Expr::unary(Operator::Not, comparison.clone()),
+                Expr::call("max", vec![Expr::variable("x"), int(2)]),
+                Expr::unary(Operator::Neg, int(3))
+            )),
+            Ok(Type::Number)
+        );
+        assert_eq!(
+            check(&Expr::binary(Operator::Eq, comparison.clone(), comparison)),
+            Ok(Type::Bool)
+        );
+        // variables aren't checked unless declared
+        assert_eq!(Checker::new().check(&Expr::variable("y")), Ok(Type::Number));
+    }
+
+    #[test]
+    fn all_problems() {
+        // y + unknown(1, 2 < x) * sqrt(1, 2) - (1 < 2) + x
+        let expr = Expr::binary(
+            Operator::Add,
+            Expr::binary(
+                Operator::Sub,
+                Expr::binary(
+                    Operator::Add,
+                    Expr::variable("y").with_span(Span::new(0, 1)),
+                    Expr::binary(
+                        Operator::Mul,
+                        Expr::call(
+                            "unknown",
+                            vec![
+                                int(1),
+                                Expr::binary(Operator::Lt, int(2), Expr::variable("x"))
+                                    .with_span(Span::new(14, 19)),
+                            ],
+                        )
+                        .with_span(Span::new(4, 20)),
+                        Expr::call("sqrt", vec![int(1), int(2)]).with_span(Span::new(23, 33)),
+                    ),
+                ),
+                Expr::group(Expr::binary(Operator::Lt, int(1), int(2)))
+                    .with_span(Span::new(36, 43)),
+            ),
+            Expr::variable("x"),
+        );
+
+        assert_eq!(
+            check(&expr).unwrap_err().errors(),
+            [
+                EvalError::UnknownVariable {
+                    name: "y".to_owned(),
+                    span: Span::new(0, 1)
+                },
+                EvalError::UnknownFunction {
+                    name: "unknown".to_owned(),
+                    span: Span::new(4, 20)
+                },
+                EvalError::TypeMismatch {
+                    expected: Type::Number,
+                    actual: Type::Bool,
+                    span: Span::new(14, 19)
+                },
+                EvalError::ArgumentCount {
+                    name: "sqrt".to_owned(),
+                    expected: FunctionArity::exact(1),
+                    actual: 2,
+                    span: Span::new(23, 33)
+                },
+                EvalError::TypeMismatch {
+                    expected: Type::Number,
+                    actual: Type::Bool,
+                    span: Span::new(36, 43)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reported_once() {
+        // type of the operation with the wrong arity is unknown, so it isn't reported again
+        let error = check(&Expr::binary(
+            Operator::And,
+            Expr::unary(Operator::Add, int(1)).with_span(Span::new(0, 2)),
+            Expr::binary(Operator::Gt, Expr::variable("x"), int(1)),
+        ))
+        .unwrap_err();
+
+        assert_eq!(
+            error.errors(),
+            [EvalError::ArityMismatch {
+                operator: Operator::Add,
+                expected: 2,
+                actual: 1,
+                span: Span::new(0, 2)
+            }]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Operator Add expects 2 operand(s), but 1 were provided"
+        );
+
+        // branches should have the same type
+        let error = check(&Expr::conditional(
+            Expr::binary(
+                Operator::Eq,
+                int(1),
+                Expr::variable("z").with_span(Span::new(5, 6)),
+            ),
+            int(1),
+            Expr::binary(
+                Operator::Or,
+                Expr::binary(Operator::Eq, int(1), int(1)),
+                Expr::binary(Operator::Eq, int(2), int(2)),
+            )
+            .with_span(Span::new(12, 18)),
+        ))
+        .unwrap_err();
+
+        assert_eq!(error.errors().len(), 2);
+        assert_eq!(error.span(), Span::new(5, 6));
+        assert_eq!(
+            error.to_string(),
+            "Unknown variable `z`, and 1 more problem(s)"
+        );
+        assert_eq!(error.diagnostics("1 == z ? 1 : 1 == 1 || 2 == 2").len(), 2);
+    }
+
+    #[test]
+    fn result_type() {
+        let checker = Checker::new().with_result(Type::Number);
+
+        assert_eq!(checker.check(&int(1)), Ok(Type::Number));
+        assert_eq!(
+            checker
+                .check(&Expr::binary(Operator::Ne, int(1), int(2)).with_span(Span::new(0, 6)))
+                .unwrap_err()
+                .errors(),
+            [EvalError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Bool,
+                span: Span::new(0, 6)
+            }]
+        );
+    }
+}
@@ -0,0 +1,730 @@
+//! Module to turn the parsed expression into the flat program
+//! that is evaluated many times against different variables
+
+use std::sync::Arc;
+
+use super::{
+    env::Environment,
+    eval::{binary_error, check_arity, CalculationError, EvalError, Result},
+    float::{FloatPolicy, Operation},
+    functions::{Function, Functions},
+    numeric::{self, Numeric},
+    parse::{Expr, ExprKind},
+    span::Span,
+    tokens::Operator,
+    value::Type,
+};
+
+/// Depth of the stack that is kept on the call stack during the evaluation,
+/// deeper programs fall back to the heap
+const INLINE_STACK: usize = 64;
+
+/// Step of the program in the postfix order
+#[derive(Debug, Clone)]
+enum Instruction {
+    Number {
+        value: f64,
+        span: Span,
+    },
+    Variable {
+        slot: usize,
+        span: Span,
+    },
+    Neg {
+        span: Span,
+    },
+    Binary {
+        operator: Operator,
+        span: Span,
+        right_span: Span,
+    },
+    Call {
+        function: Function,
+        name: String,
+        args: usize,
+        span: Span,
+    },
+    /// Booleans are kept on the stack as `1` and `0`
+    Compare {
+        operator: Operator,
+    },
+    Not,
+    Jump {
+        target: usize,
+    },
+    /// Pops the boolean and jumps when it is false
+    JumpUnless {
+        target: usize,
+    },
+}
+
+/// Expression that is parsed and checked once, so its evaluation
+/// only runs the arithmetic. Variables are resolved to slots in order
+/// of their first appearance, see [CompiledExpr::variables].
+/// Types are checked statically, so both branches of the conditional
+/// should have the same type and the result should be the number.
+///
+/// Cloning is cheap as the program is shared between the clones
+#[derive(Debug, Clone)]
+pub struct CompiledExpr {
+    program: Arc<[Instruction]>,
+    variables: Arc<[String]>,
+    stack_size: usize,
+    policy: FloatPolicy,
+}
+
+/// Builds the program tracking the depth of the stack it needs
+struct Compiler<'functions> {
+    functions: &'functions Functions,
+    program: Vec<Instruction>,
+    variables: Vec<String>,
+    depth: usize,
+    stack_size: usize,
+}
+
+impl Compiler<'_> {
+    fn push(&mut self, instruction: Instruction, consumed: usize) {
+        self.depth = self.depth + 1 - consumed;
+        self.stack_size = self.stack_size.max(self.depth);
+        self.program.push(instruction);
+    }
+
+    /// Compiles the expression and returns the type of its result
+    fn compile(&mut self, expr: &Expr) -> Result<Type> {
+        match &expr.kind {
+            ExprKind::Number(number) => {
+                let number = f64::from_number(number, &FloatPolicy::Ieee).map_err(|error| {
+                    EvalError::CalculationError {
+                        error,
+                        span: expr.span,
+                    }
+                })?;
+
+                self.push(
+                    Instruction::Number {
+                        value: number,
+                        span: expr.span,
+                    },
+                    0,
+                )
+            }
+            ExprKind::Variable(name) => {
+                let slot = match self.variables.iter().position(|known| known == name) {
+                    Some(slot) => slot,
+                    None => {
+                        self.variables.push(name.clone());
+                        self.variables.len() - 1
+                    }
+                };
+
+                self.push(
+                    Instruction::Variable {
+                        slot,
+                        span: expr.span,
+                    },
+                    0,
+                )
+            }
+            ExprKind::Group(inner) => return self.compile(inner),
+            // the program runs over the plain floating point numbers
+            ExprKind::Unit { .. } => {
+                return Err(EvalError::CalculationError {
+                    error: CalculationError::UnitsUnsupported,
+                    span: expr.span,
+                })
+            }
+            ExprKind::Call { name, args } => {
+                let Some(function) = self.functions.get(name) else {
+                    return Err(EvalError::UnknownFunction {
+                        name: name.clone(),
+                        span: expr.span,
+                    });
+                };
+
+                if !function.arity().accepts(args.len()) {
+                    return Err(EvalError::ArgumentCount {
+                        name: name.clone(),
+                        expected: function.arity(),
+                        actual: args.len(),
+                        span: expr.span,
+                    });
+                }
+
+                for arg in args {
+                    self.compile_typed(arg, Type::Number)?;
+                }
+
+                self.push(
+                    Instruction::Call {
+                        function: function.clone(),
+                        name: name.clone(),
+                        args: args.len(),
+                        span: expr.span,
+                    },
+                    args.len(),
+                )
+            }
+            ExprKind::Unary { operator, operand } => {
+                check_arity(operator, 1, expr.span)?;
+
+                if *operator == Operator::Not {
+                    self.compile_typed(operand, Type::Bool)?;
+                    self.push(Instruction::Not, 1);
+
+                    return Ok(Type::Bool);
+                }
+
+                self.compile_typed(operand, Type::Number)?;
+                self.push(Instruction::Neg { span: expr.span }, 1)
+            }
+            // `a && b` is `a ? b : false` and `a || b` is `a ? true : b`
+            ExprKind::Binary {
+                operator: Operator::And,
+                left,
+                right,
+            } => {
+                check_arity(&Operator::And, 2, expr.span)?;
+
+                return self.compile_branches(
+                    left,
+                    |compiler| {
+                        compiler
+                            .compile_typed(right, Type::Bool)
+                            .map(|_| Type::Bool)
+                    },
+                    |compiler, _| {
+                        compiler.push(
+                            Instruction::Number {
+                                value: 0.0,
+                                span: expr.span,
+                            },
+                            0,
+                        );
+                        Ok(())
+                    },
+                );
+            }
+            ExprKind::Binary {
+                operator: Operator::Or,
+                left,
+                right,
+            } => {
+                check_arity(&Operator::Or, 2, expr.span)?;
+
+                return self.compile_branches(
+                    left,
+                    |compiler| {
+                        compiler.push(
+                            Instruction::Number {
+                                value: 1.0,
+                                span: expr.span,
+                            },
+                            0,
+                        );
+                        Ok(Type::Bool)
+                    },
+                    |compiler, _| compiler.compile_typed(right, Type::Bool),
+                );
+            }
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } if operator.is_comparison() => {
+                check_arity(operator, 2, expr.span)?;
+
+                // booleans are only compared for the equality
+                let expected = match operator {
+                    Operator::Eq | Operator::Ne => self.compile(left)?,
+                    _ => {
+                        self.compile_typed(left, Type::Number)?;
+                        Type::Number
+                    }
+                };
+
+                self.compile_typed(right, expected)?;
+                self.push(
+                    Instruction::Compare {
+                        operator: operator.clone(),
+                    },
+                    2,
+                );
+
+                return Ok(Type::Bool);
+            }
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                check_arity(operator, 2, expr.span)?;
+
+                self.compile_typed(left, Type::Number)?;
+                self.compile_typed(right, Type::Number)?;
+                self.push(
+                    Instruction::Binary {
+                        operator: operator.clone(),
+                        span: expr.span,
+                        right_span: right.span,
+                    },
+                    2,
+                )
+            }
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                return self.compile_branches(
+                    condition,
+                    |compiler| compiler.compile(then),
+                    |compiler, result| compiler.compile_typed(otherwise, result),
+                )
+            }
+        }
+
+        Ok(Type::Number)
+    }
+
+    /// Compiles the operand that should have the type
+    fn compile_typed(&mut self, expr: &Expr, expected: Type) -> Result<()> {
+        let actual = self.compile(expr)?;
+
+        if actual != expected {
+            return Err(EvalError::TypeMismatch {
+                expected,
+                actual,
+                span: expr.span,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Compiles the condition followed by the jumps over the branches,
+    /// each of them leaves the single value of the type the then branch returns
+    fn compile_branches(
+        &mut self,
+        condition: &Expr,
+        then: impl FnOnce(&mut Self) -> Result<Type>,
+        otherwise: impl FnOnce(&mut Self, Type) -> Result<()>,
+    ) -> Result<Type> {
+        self.compile_typed(condition, Type::Bool)?;
+
+        let jump_unless = self.program.len();
+
+        self.push(Instruction::JumpUnless { target: 0 }, 1);
+        // the jump pushes nothing, so the depth is restored after the push accounting
+        self.depth -= 1;
+
+        let result = then(self)?;
+
+        let jump = self.program.len();
+
+        self.program.push(Instruction::Jump { target: 0 });
+        self.program[jump_unless] = Instruction::JumpUnless {
+            target: self.program.len(),
+        };
+        // the other branch starts from the same depth
+        self.depth -= 1;
+
+        otherwise(self, result)?;
+
+        self.program[jump] = Instruction::Jump {
+            target: self.program.len(),
+        };
+
+        Ok(result)
+    }
+}
+
+impl CompiledExpr {
+    /// Compiles the expression resolving its functions from the registry.
+    /// Unknown functions, wrong number of arguments and types are reported right away
+    pub fn new(expr: &Expr, functions: &Functions) -> Result<Self> {
+        let mut compiler = Compiler {
+            functions,
+            program: vec![],
+            variables: vec![],
+            depth: 0,
+            stack_size: 0,
+        };
+
+        compiler.compile_typed(expr, Type::Number)?;
+
+        Ok(Self {
+            program: compiler.program.into(),
+            variables: compiler.variables.into(),
+            stack_size: compiler.stack_size,
+            policy: FloatPolicy::default(),
+        })
+    }
+
+    /// Sets whether the non-finite results are errors, see [FloatPolicy]
+    pub fn with_policy(mut self, policy: FloatPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Names of the variables in order of their slots
+    pub fn variables(&self) -> &[String] {
+        &self.variables
+    }
+
+    /// Index of the variable inside the slots passed to [CompiledExpr::eval]
+    pub fn slot(&self, name: &str) -> Option<usize> {
+        self.variables.iter().position(|known| known == name)
+    }
+
+    /// Evaluates the expression with values of the variables in order of their slots.
+    /// Doesn't allocate unless the expression is nested deeper than 64 operands
+    pub fn eval(&self, slots: &[f64]) -> Result<f64> {
+        self.run(|slot, span| {
+            slots
+                .get(slot)
+                .copied()
+                .ok_or_else(|| self.unknown_variable(slot, span))
+        })
+    }
+
+    /// Evaluates the expression taking values of the variables from the environment
+    pub fn eval_with(&self, env: &Environment) -> Result<f64> {
+        self.run(|slot, span| {
+            env.get(&self.variables[slot])
+                .ok_or_else(|| self.unknown_variable(slot, span))
+        })
+    }
+
+    fn unknown_variable(&self, slot: usize, span: Span) -> EvalError {
+        EvalError::UnknownVariable {
+            name: self.variables[slot].clone(),
+            span,
+        }
+    }
+
+    fn run(&self, variable: impl Fn(usize, Span) -> Result<f64>) -> Result<f64> {
+        if self.stack_size <= INLINE_STACK {
+            self.run_on(&mut [0.0; INLINE_STACK], variable)
+        } else {
+            self.run_on(&mut vec![0.0; self.stack_size], variable)
+        }
+    }
+
+    fn run_on(
+        &self,
+        stack: &mut [f64],
+        variable: impl Fn(usize, Span) -> Result<f64>,
+    ) -> Result<f64> {
+        let mut top = 0;
+        let mut next = 0;
+
+        while let Some(instruction) = self.program.get(next) {
+            next += 1;
+
+            let value = match instruction {
+                // literals are compiled before the policy is known
+                Instruction::Number { value, span } => self
+                    .policy
+                    .check(Operation::Literal, &[], *value)
+                    .map_err(|error| EvalError::CalculationError { error, span: *span })?,
+                Instruction::Variable { slot, span } => self
+                    .policy
+                    .check(Operation::Variable, &[], variable(*slot, *span)?)
+                    .map_err(|error| EvalError::CalculationError { error, span: *span })?,
+                Instruction::Neg { span } => {
+                    top -= 1;
+
+                    stack[top]
+                        .neg(&self.policy)
+                        .map_err(|error| EvalError::CalculationError { error, span: *span })?
+                }
+                Instruction::Binary {
+                    operator,
+                    span,
+                    right_span,
+                } => {
+                    top -= 2;
+
+                    numeric::calculate(operator, stack[top], stack[top + 1], &self.policy)
+                        .map_err(|error| binary_error(error, *span, *right_span))?
+                }
+                Instruction::Call {
+                    function,
+                    name,
+                    args,
+                    span,
+                } => {
+                    top -= args;
+
+                    f64::call(function, name, &stack[top..top + args], &self.policy)
+                        .map_err(|error| EvalError::CalculationError { error, span: *span })?
+                }
+                Instruction::Compare { operator } => {
+                    top -= 2;
+
+                    let holds = numeric::compare(operator, &stack[top], &stack[top + 1])
+                        .expect("floating point numbers are always comparable");
+
+                    f64::from(u8::from(holds))
+                }
+                Instruction::Not => {
+                    top -= 1;
+
+                    1.0 - stack[top]
+                }
+                Instruction::Jump { target } => {
+                    next = *target;
+                    continue;
+                }
+                Instruction::JumpUnless { target } => {
+                    top -= 1;
+
+                    if stack[top] == 0.0 {
+                        next = *target;
+                    }
+
+                    continue;
+                }
+            };
+
+            stack[top] = value;
+            top += 1;
+        }
+
+        Ok(stack[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval::CalculationError, functions::FunctionArity, tokens::Number, Evaluator};
+
+    fn int(num: i32) -> Expr {
+        Expr::number(Number::Int(num.into()))
+    }
+
+    fn compile(expr: &Expr) -> Result<CompiledExpr> {
+        CompiledExpr::new(expr, &Functions::builtin())
+    }
+
+    #[test]
+    fn variables() {
+        // x * (y - x) + max(y, 1)
+        let compiled = compile(&Expr::binary(
+            Operator::Add,
+            Expr::binary(
+                Operator::Mul,
+                Expr::variable("x"),
+                Expr::group(Expr::binary(
+                    Operator::Sub,
+                    Expr::variable("y"),
+                    Expr::variable("x"),
+                )),
+            ),
+            Expr::call("max", vec![Expr::variable("y"), int(1)]),
+        ))
+        .unwrap();
+
+        assert_eq!(compiled.variables(), ["x", "y"]);
+        assert_eq!(compiled.slot("y"), Some(1));
+        assert_eq!(compiled.eval(&[2.0, 5.0]), Ok(11.0));
+        assert_eq!(compiled.eval(&[3.0, 0.0]), Ok(-8.0));
+        assert_eq!(
+            compiled.eval_with(
+                &Environment::new()
+                    .with_variable("x", 1.0)
+                    .with_variable("y", 2.0)
+            ),
+            Ok(3.0)
+        );
+        assert_eq!(
+            compiled.eval(&[1.0]),
+            Err(EvalError::UnknownVariable {
+                name: "y".to_string(),
+                span: Span::default()
+            })
+        );
+    }
+
+    #[test]
+    fn deep_expression() {
+        let expr = (0..100).fold(int(0), |expr, num| {
+            Expr::binary(Operator::Sub, int(num), Expr::unary(Operator::Neg, expr))
+        });
+
+        assert_eq!(
+            compile(&expr).unwrap().eval(&[]),
+            Evaluator::new().eval(&expr)
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            compile(&Expr::call("foo", vec![])).unwrap_err(),
+            EvalError::UnknownFunction {
+                name: "foo".to_string(),
+                span: Span::default()
+            }
+        );
+        assert_eq!(
+            compile(&Expr::call("sqrt", vec![])).unwrap_err(),
+            EvalError::ArgumentCount {
+                name: "sqrt".to_string(),
+                expected: FunctionArity::exact(1),
+                actual: 0,
+                span: Span::default()
+            }
+        );
+
+        let compiled = compile(&Expr::binary(
+            Operator::Div,
+            int(1).with_span(Span::new(0, 1)),
+            Expr::variable("x").with_span(Span::new(4, 6)),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            compiled.eval(&[0.0]),
+            Err(EvalError::CalculationError {
+                error: CalculationError::ZeroDivision,
+                span: Span::new(4, 6)
+            })
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let expr = Expr::binary(
+            Operator::Mul,
+            Expr::unary(Operator::Neg, Expr::variable("x")),
+            Expr::call("exp", vec![Expr::variable("x")]),
+        );
+        let strict = Evaluator::new().with_context(FloatPolicy::Strict);
+        let compiled = strict.compile(&expr).unwrap();
+        let env = Environment::new().with_variable("x", 800.0);
+
+        assert_eq!(
+            compile(&expr).unwrap().eval(&[800.0]),
+            Ok(f64::NEG_INFINITY)
+        );
+        assert_eq!(compiled.eval_with(&env), strict.eval_with(&expr, &env));
+        assert_eq!(
+            compiled.eval(&[f64::NAN]).unwrap_err().to_string(),
+            "variable produced NaN"
+        );
+
+        let literal = strict
+            .compile(&Expr::number(Number::Float(f64::INFINITY)))
+            .unwrap();
+
+        assert_eq!(
+            literal.eval(&[]).unwrap_err().to_string(),
+            "literal overflowed to infinity"
+        );
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CompiledExpr>();
+
+        let compiled = compile(&Expr::binary(Operator::Mul, Expr::variable("x"), int(2))).unwrap();
+
+        let results = std::thread::scope(|scope| {
+            let handles = (0..4)
+                .map(|num| {
+                    let compiled = compiled.clone();
+                    scope.spawn(move || compiled.eval(&[num as f64]))
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        assert_eq!(results, [Ok(0.0), Ok(2.0), Ok(4.0), Ok(6.0)]);
+    }
+
+    #[test]
+    fn conditions() {
+        let x = || Expr::variable("x");
+        let between = |low, high| {
+            Expr::binary(
+                Operator::And,
+                Expr::binary(Operator::Ge, x(), int(low)),
+                Expr::unary(Operator::Not, Expr::binary(Operator::Gt, x(), int(high))),
+            )
+        };
+        // x >= 0 && !(x > 10) ? x * 2 : (x < 0 || x == 20 ? -1 : 1 / 0)
+        let expr = Expr::conditional(
+            between(0, 10),
+            Expr::binary(Operator::Mul, x(), int(2)),
+            Expr::conditional(
+                Expr::binary(
+                    Operator::Or,
+                    Expr::binary(Operator::Lt, x(), int(0)),
+                    Expr::binary(Operator::Eq, x(), int(20)),
+                ),
+                int(-1),
+                Expr::binary(Operator::Div, int(1), int(0)),
+            ),
+        );
+        let compiled = compile(&expr).unwrap();
+
+        for value in [-5.0, 0.0, 4.0, 10.0, 15.0, 20.0] {
+            assert_eq!(
+                compiled.eval(&[value]),
+                Evaluator::new().eval_with(&expr, &Environment::new().with_variable("x", value)),
+                "{value}"
+            );
+        }
+
+        assert_eq!(compiled.eval(&[4.0]), Ok(8.0));
+        assert_eq!(compiled.eval(&[20.0]), Ok(-1.0));
+    }
+
+    #[test]
+    fn type_errors() {
+        let comparison = Expr::binary(Operator::Lt, Expr::variable("x"), int(1));
+
+        assert_eq!(
+            compile(&comparison.clone().with_span(Span::new(0, 5))).unwrap_err(),
+            EvalError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Bool,
+                span: Span::new(0, 5)
+            }
+        );
+        // both branches are checked, even though the condition is always true
+        assert_eq!(
+            compile(&Expr::conditional(
+                Expr::binary(Operator::Eq, int(1), int(1)),
+                int(1),
+                comparison.clone().with_span(Span::new(10, 15))
+            ))
+            .unwrap_err(),
+            EvalError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Bool,
+                span: Span::new(10, 15)
+            }
+        );
+        assert_eq!(
+            compile(&Expr::binary(
+                Operator::Ne,
+                comparison,
+                int(1).with_span(Span::new(9, 10))
+            ))
+            .unwrap_err(),
+            EvalError::TypeMismatch {
+                expected: Type::Bool,
+                actual: Type::Number,
+                span: Span::new(9, 10)
+            }
+        );
+    }
+}
@@ -0,0 +1,389 @@
+//! Module with the complex numbers of the floating point parts
+//! and the elementary functions over them
+
+use std::{
+    f64::consts::{FRAC_PI_2, LN_10},
+    fmt::Display,
+    ops::{Add, Div, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+/// Form the complex number is written in
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComplexForm {
+    /// Real and imaginary parts, i.e. `3 + 4i`
+    #[default]
+    Rectangular,
+    /// Absolute value and the argument in radians, i.e. `5∠0.9272952180016122`
+    Polar,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownComplexForm(String);
+
+impl Display for UnknownComplexForm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown complex form `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownComplexForm {}
+
+impl FromStr for ComplexForm {
+    type Err = UnknownComplexForm;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rectangular" => Ok(Self::Rectangular),
+            "polar" => Ok(Self::Polar),
+            _ => Err(UnknownComplexForm(s.to_string())),
+        }
+    }
+}
+
+/// Complex number `re + im * i`
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    re: f64,
+    im: f64,
+}
+
+impl Complex {
+    /// Creates the number turning the negative zeros into the positive ones,
+    /// so the negated real number stays on the positive side of the branch cut, i.e. `sqrt(-4) = 2i`
+    pub fn new(re: f64, im: f64) -> Self {
+        Self {
+            re: re + 0.0,
+            im: im + 0.0,
+        }
+    }
+
+    /// Number without the imaginary part
+    pub fn real(re: f64) -> Self {
+        Self::new(re, 0.0)
+    }
+
+    /// Imaginary unit, the square root of `-1`
+    pub fn i() -> Self {
+        Self::new(0.0, 1.0)
+    }
+
+    /// Number of the absolute value and the argument in radians
+    pub fn from_polar(abs: f64, arg: f64) -> Self {
+        Self::new(abs * arg.cos(), abs * arg.sin())
+    }
+
+    pub fn re(&self) -> f64 {
+        self.re
+    }
+
+    pub fn im(&self) -> f64 {
+        self.im
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.im == 0.0
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.re == 0.0 && self.im == 0.0
+    }
+
+    /// Distance from zero
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Angle from the positive real axis in `(-π, π]`
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    pub fn conj(&self) -> Self {
+        Self::new(self.re, -self.im)
+    }
+
+    /// Integer power calculated by squaring, so `i ^ 2` is exactly `-1`
+    pub fn powi(self, exponent: i32) -> Self {
+        let mut base = self;
+        let mut result = Self::real(1.0);
+        let mut rest = exponent.unsigned_abs();
+
+        while rest > 0 {
+            if rest & 1 == 1 {
+                result = result * base;
+            }
+
+            base = base * base;
+            rest >>= 1;
+        }
+
+        match exponent < 0 {
+            true => Self::real(1.0) / result,
+            false => result,
+        }
+    }
+
+    /// Principal power `e ^ (exponent * ln(self))`, the base should not be zero
+    pub fn powc(self, exponent: Self) -> Self {
+        (exponent * self.ln()).exp()
+    }
+
+    /// Principal square root with the non-negative real part
+    pub fn sqrt(self) -> Self {
+        let abs = self.abs();
+
+        Self::new(
+            ((abs + self.re) / 2.0).sqrt(),
+            ((abs - self.re) / 2.0).sqrt().copysign(self.im),
+        )
+    }
+
+    pub fn exp(self) -> Self {
+        Self::from_polar(self.re.exp(), self.im)
+    }
+
+    /// Principal natural logarithm, the imaginary part of which is the argument
+    pub fn ln(self) -> Self {
+        Self::new(self.abs().ln(), self.arg())
+    }
+
+    pub fn log10(self) -> Self {
+        let ln = self.ln();
+
+        Self::new(ln.re / LN_10, ln.im / LN_10)
+    }
+
+    pub fn sin(self) -> Self {
+        Self::new(
+            self.re.sin() * self.im.cosh(),
+            self.re.cos() * self.im.sinh(),
+        )
+    }
+
+    pub fn cos(self) -> Self {
+        Self::new(
+            self.re.cos() * self.im.cosh(),
+            -self.re.sin() * self.im.sinh(),
+        )
+    }
+
+    pub fn tan(self) -> Self {
+        self.sin() / self.cos()
+    }
+
+    /// Principal inverse sine `-i * ln(iz + sqrt(1 - z^2))`
+    pub fn asin(self) -> Self {
+        let root = (Self::real(1.0) - self * self).sqrt();
+
+        -Self::i() * (Self::i() * self + root).ln()
+    }
+
+    /// Principal inverse cosine `π/2 - asin(z)`
+    pub fn acos(self) -> Self {
+        Self::real(FRAC_PI_2) - self.asin()
+    }
+
+    /// Principal inverse tangent `i/2 * (ln(1 - iz) - ln(1 + iz))`
+    pub fn atan(self) -> Self {
+        let one = Self::real(1.0);
+        let iz = Self::i() * self;
+
+        Self::new(0.0, 0.5) * ((one - iz).ln() - (one + iz).ln())
+    }
+
+    /// Writes the number in the form, see [ComplexForm]
+    pub fn format(&self, form: ComplexForm) -> String {
+        match form {
+            ComplexForm::Rectangular => self.to_string(),
+            ComplexForm::Polar => format!("{}∠{}", self.abs(), self.arg()),
+        }
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(value: f64) -> Self {
+        Self::real(value)
+    }
+}
+
+impl Neg for Complex {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.re, -self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        // real factor is applied to both parts, so the infinite parts don't turn into NaN
+        if other.is_real() {
+            return Self::new(self.re * other.re, self.im * other.re);
+        }
+
+        if self.is_real() {
+            return Self::new(self.re * other.re, self.re * other.im);
+        }
+
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// Quotient of the numbers, the divisor should not be zero
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        if other.is_real() {
+            return Self::new(self.re / other.re, self.im / other.re);
+        }
+
+        // Smith's algorithm divides by the larger part first, so the squares of the parts
+        // don't overflow or underflow, i.e. `(1 + 1i) / (1e300 + 1e300i)` isn't zero
+        if other.re.abs() >= other.im.abs() {
+            let ratio = other.im / other.re;
+            let scale = other.re + other.im * ratio;
+
+            Self::new(
+                (self.re + self.im * ratio) / scale,
+                (self.im - self.re * ratio) / scale,
+            )
+        } else {
+            let ratio = other.re / other.im;
+            let scale = other.re * ratio + other.im;
+
+            Self::new(
+                (self.re * ratio + self.im) / scale,
+                (self.im * ratio - self.re) / scale,
+            )
+        }
+    }
+}
+
+/// Rectangular form, the part that is zero is omitted unless both are, i.e. `3`, `2i` or `1 - 1.5i`
+impl Display for Complex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.re, self.im) {
+            (re, im) if im == 0.0 => write!(f, "{re}"),
+            (re, im) if re == 0.0 => write!(f, "{im}i"),
+            (re, im) if im.is_sign_negative() => write!(f, "{re} - {}i", -im),
+            (re, im) => write!(f, "{re} + {im}i"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_3, PI};
+
+    /// Rounds both parts, so the results are compared without the error of the last bits
+    fn approx(number: Complex) -> (f64, f64) {
+        let round = |part: f64| (part * 1e12).round() / 1e12 + 0.0;
+
+        (round(number.re()), round(number.im()))
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -4.0);
+
+        assert_eq!(a + b, Complex::new(4.0, -2.0));
+        assert_eq!(a - b, Complex::new(-2.0, 6.0));
+        assert_eq!(a * b, Complex::new(11.0, 2.0));
+        assert_eq!(a / b, Complex::new(-0.2, 0.4));
+        assert_eq!(Complex::i().powi(2), Complex::real(-1.0));
+        assert_eq!(a.powi(-1), Complex::new(0.2, -0.4));
+        assert_eq!(b.abs(), 5.0);
+        assert_eq!(-a, Complex::new(-1.0, -2.0));
+
+        let one = Complex::new(1.0, 1.0);
+
+        assert_eq!(one / Complex::new(1e300, 1e300), Complex::real(1e-300));
+
+        let huge = one / Complex::new(1e-300, 1e-300);
+
+        assert_eq!(
+            approx(Complex::new(huge.re() / 1e300, huge.im())),
+            (1.0, 0.0)
+        );
+        assert_eq!(
+            Complex::new(2e-300, 4e-300) / Complex::new(1e-300, 2e-300),
+            Complex::real(2.0)
+        );
+        assert_eq!(
+            approx(Complex::new(3e200, 4e200) / Complex::new(4e200, -3e200)),
+            (0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(Complex::real(-4.0).sqrt(), Complex::new(0.0, 2.0));
+        assert_eq!(Complex::new(3.0, 4.0).sqrt(), Complex::new(2.0, 1.0));
+        assert_eq!(approx(Complex::new(0.0, PI).exp()), (-1.0, 0.0));
+        assert_eq!(Complex::real(-1.0).ln(), Complex::new(0.0, PI));
+        assert_eq!(
+            approx(Complex::i().powc(Complex::i())),
+            (0.207879576351, 0.0)
+        );
+        assert_eq!(
+            approx(Complex::real(2.0).asin()),
+            approx(Complex::new(FRAC_PI_2, -1.316957896925))
+        );
+        assert_eq!(
+            approx(Complex::new(0.5, 0.0).acos()),
+            approx(Complex::real(FRAC_PI_3))
+        );
+        assert_eq!(
+            approx(Complex::new(1.0, 1.0).atan()),
+            (1.017221967898, 0.402359478109)
+        );
+        assert_eq!(approx(Complex::i().sin()), (0.0, 1.175201193644));
+        assert_eq!(approx(Complex::real(100.0).log10()), (2.0, 0.0));
+    }
+
+    #[test]
+    fn forms() {
+        assert_eq!(Complex::new(3.0, 4.0).to_string(), "3 + 4i");
+        assert_eq!(Complex::new(1.0, -1.5).to_string(), "1 - 1.5i");
+        assert_eq!(Complex::new(0.0, -2.0).to_string(), "-2i");
+        assert_eq!(Complex::real(2.5).to_string(), "2.5");
+        assert_eq!(
+            Complex::new(3.0, 4.0).format(ComplexForm::Polar),
+            "5∠0.9272952180016122"
+        );
+        assert_eq!(
+            Complex::real(-2.0).format(ComplexForm::Polar),
+            "2∠3.141592653589793"
+        );
+        assert_eq!("polar".parse(), Ok(ComplexForm::Polar));
+        assert_eq!(
+            "spherical".parse::<ComplexForm>(),
+            Err(UnknownComplexForm("spherical".to_string()))
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Module with the fixed-point decimals for the deterministic
+//! evaluation of money with the explicit rounding
+
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use crate::{bigint::BigInt, rational::Rational};
+
+/// How the result is rounded to the scale of the decimal
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Half goes to the even neighbour, i.e. `0.125 -> 0.12`, `0.135 -> 0.14`
+    #[default]
+    HalfEven,
+    /// Half goes away from zero, i.e. `0.125 -> 0.13`, `-0.125 -> -0.13`
+    HalfUp,
+    /// Toward zero, i.e. `0.129 -> 0.12`
+    Down,
+    /// Away from zero, i.e. `0.121 -> 0.13`
+    Up,
+}
+
+impl Rounding {
+    /// Rounds the fraction to the integer
+    fn integer(self, value: &Rational) -> BigInt {
+        let (quotient, remainder) = value
+            .numer()
+            .checked_div_rem(value.denom())
+            .expect("denominator is not zero");
+        let away = match self {
+            _ if remainder.is_zero() => false,
+            Rounding::Down => false,
+            Rounding::Up => true,
+            // remainder is compared with the rest of the denominator to find the half
+            rounding => match remainder.abs().cmp(&(value.denom() - &remainder.abs())) {
+                Ordering::Less => false,
+                Ordering::Greater => true,
+                Ordering::Equal => {
+                    let (_, parity) = quotient
+                        .checked_div_rem(&BigInt::from(2))
+                        .expect("divisor is not zero");
+
+                    rounding == Rounding::HalfUp || !parity.is_zero()
+                }
+            },
+        };
+
+        match (away, value.is_negative()) {
+            (false, _) => quotient,
+            (true, false) => &quotient + &BigInt::one(),
+            (true, true) => &quotient - &BigInt::one(),
+        }
+    }
+
+    /// Rounds the fraction to the number of the fractional digits, the negative number
+    /// rounds to the tens, hundreds etc., i.e. `1250` to `-2` digits is `1200` for [Rounding::HalfEven]
+    pub fn round(self, value: &Rational, digits: i32) -> Rational {
+        let power = Rational::from(BigInt::from(10).pow(digits.unsigned_abs()));
+        let scale = match digits < 0 {
+            true => Rational::from(1)
+                .checked_div(&power)
+                .expect("power of 10 is not zero"),
+            false => power,
+        };
+
+        Rational::from(self.integer(&(value * &scale)))
+            .checked_div(&scale)
+            .expect("scale is not zero")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnknownRounding(String);
+
+impl Display for UnknownRounding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unknown rounding `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownRounding {}
+
+impl FromStr for Rounding {
+    type Err = UnknownRounding;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "half-even" => Ok(Self::HalfEven),
+            "half-up" => Ok(Self::HalfUp),
+            "down" => Ok(Self::Down),
+            "up" => Ok(Self::Up),
+            _ => Err(UnknownRounding(s.to_string())),
+        }
+    }
+}
+
+/// Number of the fractional digits and the rounding of every operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalContext {
+    pub scale: u32,
+    pub rounding: Rounding,
+}
+
+/// Cents rounded half to even
+impl Default for DecimalContext {
+    fn default() -> Self {
+        Self {
+            scale: 2,
+            rounding: Rounding::default(),
+        }
+    }
+}
+
+impl DecimalContext {
+    pub fn with_scale(mut self, scale: u32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+}
+
+/// Fixed-point decimal stored as the integer number of `10^-scale` units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    units: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Creates decimal equal to `units * 10^-scale`
+    pub fn new(units: i128, scale: u32) -> Self {
+        Self { units, scale }
+    }
+
+    pub fn units(&self) -> i128 {
+        self.units
+    }
+
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Rounds the exact value to the scale of the context,
+    /// returns `None` when the result doesn't fit
+    pub fn from_rational(value: &Rational, context: &DecimalContext) -> Option<Self> {
+        let scaled = Rational::new(
+            value.numer() * &BigInt::from(10).pow(context.scale),
+            value.denom().clone(),
+        )?;
+        let units = context.rounding.integer(&scaled);
+
+        Some(Self::new(units.to_i128()?, context.scale))
+    }
+
+    pub fn to_rational(&self) -> Rational {
+        Rational::new(self.units.into(), BigInt::from(10).pow(self.scale))
+            .expect("power of 10 is not zero")
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_rational().to_f64()
+    }
+}
+
+/// Writes all the fractional digits of the scale, i.e. `12.50`
+impl Display for Decimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scale = self.scale as usize;
+        let digits = format!("{:0>width$}", self.units.unsigned_abs(), width = scale + 1);
+        let (int, fract) = digits.split_at(digits.len() - scale);
+        let sign = if self.units < 0 { "-" } else { "" };
+
+        if fract.is_empty() {
+            write!(f, "{sign}{int}")
+        } else {
+            write!(f, "{sign}{int}.{fract}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(numer: i64, denom: i64, rounding: Rounding) -> String {
+        let context = DecimalContext::default().with_rounding(rounding);
+        let value = Rational::new(numer.into(), denom.into()).unwrap();
+
+        Decimal::from_rational(&value, &context)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn rounding() {
+        assert_eq!(round(125, 1000, Rounding::HalfEven), "0.12");
+        assert_eq!(round(135, 1000, Rounding::HalfEven), "0.14");
+        assert_eq!(round(-125, 1000, Rounding::HalfEven), "-0.12");
+        assert_eq!(round(1251, 10000, Rounding::HalfEven), "0.13");
+        assert_eq!(round(125, 1000, Rounding::HalfUp), "0.13");
+        assert_eq!(round(-125, 1000, Rounding::HalfUp), "-0.13");
+        assert_eq!(round(124, 1000, Rounding::HalfUp), "0.12");
+        assert_eq!(round(129, 1000, Rounding::Down), "0.12");
+        assert_eq!(round(-129, 1000, Rounding::Down), "-0.12");
+        assert_eq!(round(121, 1000, Rounding::Up), "0.13");
+        assert_eq!(round(-121, 1000, Rounding::Up), "-0.13");
+        assert_eq!(round(2, 1, Rounding::Up), "2.00");
+        assert_eq!(round(-1, 1000, Rounding::HalfEven), "0.00");
+
+        let value = Rational::new(1250.into(), 1.into()).unwrap();
+
+        assert_eq!(Rounding::HalfEven.round(&value, -2), Rational::from(1200));
+        assert_eq!(Rounding::HalfUp.round(&value, -2), Rational::from(1300));
+        assert_eq!(Rounding::Down.round(&-value, -3), Rational::from(-1000));
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+        assert_eq!(Decimal::new(1250, 2).to_f64(), 12.5);
+        assert_eq!(Decimal::new(42, 0).to_string(), "42");
+        assert_eq!(
+            Decimal::from_rational(
+                &Rational::from(BigInt::from(10).pow(40)),
+                &DecimalContext::default()
+            ),
+            None
+        );
+        assert_eq!("half-up".parse(), Ok(Rounding::HalfUp));
+        assert_eq!(
+            "ceil".parse::<Rounding>(),
+            Err(UnknownRounding("ceil".to_string()))
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! Module to differentiate the parsed expression
+//! with respect to the variable symbolically
+
+use std::fmt::Display;
+
+use crate::{
+    parse::{Expr, ExprKind},
+    simplify::Simplifier,
+    span::Span,
+    tokens::{Number, Operator},
+};
+
+#[derive(Debug, PartialEq)]
+pub enum DeriveError {
+    /// Function has no known derivative, i.e. the custom one
+    NotDifferentiable { name: String, span: Span },
+    /// Comparisons and logic yield booleans, only the branches of the conditional are derived
+    Boolean { span: Span },
+}
+
+impl DeriveError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::NotDifferentiable { span, .. } | Self::Boolean { span } => *span,
+        }
+    }
+}
+
+impl Display for DeriveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotDifferentiable { name, .. } => {
+                write!(f, "Function `{name}` has no known derivative")
+            }
+            Self::Boolean { .. } => write!(f, "Boolean expression has no derivative"),
+        }
+    }
+}
+
+impl std::error::Error for DeriveError {}
+
+pub type Result<T> = std::result::Result<T, DeriveError>;
+
+impl Expr {
+    /// Derivative with respect to the variable simplified by the [Simplifier].
+    /// Functions that are not differentiable everywhere, i.e. `abs` or `floor`,
+    /// get the derivative defined almost everywhere, as well as the conditionals
+    /// which are derived branch by branch, i.e. `x > 0 ? x : -x` gives `x > 0 ? 1 : -1`
+    pub fn derive(&self, variable: &str) -> Result<Expr> {
+        let derivative = Derivative { variable }.derive(self)?;
+
+        Ok(Simplifier::new().simplify(&derivative))
+    }
+}
+
+struct Derivative<'variable> {
+    variable: &'variable str,
+}
+
+impl Derivative<'_> {
+    fn derive(&self, expr: &Expr) -> Result<Expr> {
+        if !self.depends_on(expr) {
+            return Ok(int(0));
+        }
+
+        Ok(match &expr.kind {
+            ExprKind::Number(_) => int(0),
+            ExprKind::Variable(_) => int(1),
+            ExprKind::Group(inner) => self.derive(inner)?,
+            ExprKind::Unary {
+                operator: Operator::Not,
+                ..
+            } => return Err(DeriveError::Boolean { span: expr.span }),
+            ExprKind::Unary { operand, .. } => neg(self.derive(operand)?),
+            // unit is linear, i.e. `d/dx x[kg] = 1[kg]`
+            ExprKind::Unit { operand, unit } => Expr::unit(self.derive(operand)?, unit),
+            ExprKind::Binary { operator, .. }
+                if operator.is_comparison() || operator.is_logical() =>
+            {
+                return Err(DeriveError::Boolean { span: expr.span })
+            }
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => self.derive_binary(operator, left, right)?,
+            ExprKind::Call { name, args } => self.derive_call(name, args, expr.span)?,
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => Expr::conditional(
+                condition.as_ref().clone(),
+                self.derive(then)?,
+                self.derive(otherwise)?,
+            ),
+        })
+    }
+
+    fn derive_binary(&self, operator: &Operator, u: &Expr, v: &Expr) -> Result<Expr> {
+        let (du, dv) = (self.derive(u)?, self.derive(v)?);
+        let (u, v) = (u.clone(), v.clone());
+
+        Ok(match operator {
+            Operator::Add => add(du, dv),
+            Operator::Sub => sub(du, dv),
+            Operator::Mul => add(mul(du, v), mul(u, dv)),
+            Operator::Div => div(sub(mul(du, v.clone()), mul(u, dv)), pow(v, int(2))),
+            // u % v = u - v * trunc(u / v), where trunc(t) = t - t % 1
+            Operator::Mod => {
+                let ratio = div(u, v);
+
+                sub(
+                    du,
+                    mul(dv, sub(ratio.clone(), binary(Operator::Mod, ratio, int(1)))),
+                )
+            }
+            Operator::Pow if !self.depends_on(&v) => {
+                mul(mul(v.clone(), pow(u, sub(v, int(1)))), du)
+            }
+            Operator::Pow if !self.depends_on(&u) => {
+                mul(mul(pow(u.clone(), v), call("ln", vec![u])), dv)
+            }
+            Operator::Pow => mul(
+                pow(u.clone(), v.clone()),
+                add(mul(dv, call("ln", vec![u.clone()])), div(mul(v, du), u)),
+            ),
+            _ => unreachable!("only arithmetic binary operators are derived"),
+        })
+    }
+
+    fn derive_call(&self, name: &str, args: &[Expr], span: Span) -> Result<Expr> {
+        let u = || args[0].clone();
+
+        // derivative of the function of the single argument multiplied by its derivative
+        let chain = |outer: Expr| Ok(mul(outer, self.derive(&args[0])?));
+
+        match (name, args.len()) {
+            ("sqrt", 1) => chain(div(int(1), mul(int(2), call("sqrt", vec![u()])))),
+            ("abs", 1) => chain(div(u(), call("abs", vec![u()]))),
+            ("floor" | "ceil" | "round", _) => Ok(int(0)),
+            ("min" | "max", _) => self.derive_extremum(name, args),
+            ("ln", 1) => chain(div(int(1), u())),
+            ("log", 1) => chain(div(int(1), mul(u(), call("ln", vec![int(10)])))),
+            ("log", 2) => self.derive(&div(
+                call("ln", vec![u()]),
+                call("ln", vec![args[1].clone()]),
+            )),
+            ("exp", 1) => chain(call("exp", vec![u()])),
+            ("sin", 1) => chain(call("cos", vec![u()])),
+            ("cos", 1) => chain(neg(call("sin", vec![u()]))),
+            ("tan", 1) => chain(div(int(1), pow(call("cos", vec![u()]), int(2)))),
+            ("asin", 1) => chain(div(
+                int(1),
+                call("sqrt", vec![sub(int(1), pow(u(), int(2)))]),
+            )),
+            ("acos", 1) => chain(neg(div(
+                int(1),
+                call("sqrt", vec![sub(int(1), pow(u(), int(2)))]),
+            ))),
+            ("atan", 1) => chain(div(int(1), add(int(1), pow(u(), int(2))))),
+            _ => Err(DeriveError::NotDifferentiable {
+                name: name.to_string(),
+                span,
+            }),
+        }
+    }
+
+    /// Folds the arguments pairwise using `max(a, b) = (a + b + |a - b|) / 2`
+    /// and `min(a, b) = (a + b - |a - b|) / 2`
+    fn derive_extremum(&self, name: &str, args: &[Expr]) -> Result<Expr> {
+        let mut extremum = args[0].clone();
+        let mut derivative = self.derive(&extremum)?;
+
+        for arg in &args[1..] {
+            let (da, db) = (derivative, self.derive(arg)?);
+            let difference = sub(extremum.clone(), arg.clone());
+            // sign of the difference selects the derivative of the larger argument
+            let sign = div(difference.clone(), call("abs", vec![difference]));
+            let spread = mul(sign, sub(da.clone(), db.clone()));
+            let sum = match name {
+                "max" => add(add(da, db), spread),
+                _ => sub(add(da, db), spread),
+            };
+
+            derivative = div(sum, int(2));
+            extremum = call(name, vec![extremum, arg.clone()]);
+        }
+
+        Ok(derivative)
+    }
+
+    fn depends_on(&self, expr: &Expr) -> bool {
+        match &expr.kind {
+            ExprKind::Number(_) => false,
+            ExprKind::Variable(name) => name == self.variable,
+            ExprKind::Group(inner) => self.depends_on(inner),
+            ExprKind::Unary { operand, .. } | ExprKind::Unit { operand, .. } => {
+                self.depends_on(operand)
+            }
+            ExprKind::Binary { left, right, .. } => self.depends_on(left) || self.depends_on(right),
+            ExprKind::Call { args, .. } => args.iter().any(|arg| self.depends_on(arg)),
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => self.depends_on(condition) || self.depends_on(then) || self.depends_on(otherwise),
+        }
+    }
+}
+
+fn int(num: u32) -> Expr {
+    Expr::number(Number::Int(num.into()))
+}
+
+fn call(name: &str, args: Vec<Expr>) -> Expr {
+    Expr::call(name, args)
+}
+
+fn binary(operator: Operator, left: Expr, right: Expr) -> Expr {
+    Expr::binary(operator, left, right)
+}
+
+fn is_int(expr: &Expr, value: u32) -> bool {
+    matches!(&expr.kind, ExprKind::Number(Number::Int(num)) if *num == value.into())
+}
+
+// builders below drop the terms of the zero derivatives,
+// so the result doesn't carry `0 * f(x)` the simplifier can't remove safely
+
+fn neg(expr: Expr) -> Expr {
+    if is_int(&expr, 0) {
+        return expr;
+    }
+
+    Expr::unary(Operator::Neg, expr)
+}
+
+fn add(left: Expr, right: Expr) -> Expr {
+    match (is_int(&left, 0), is_int(&right, 0)) {
+        (true, _) => right,
+        (_, true) => left,
+        _ => binary(Operator::Add, left, right),
+    }
+}
+
+fn sub(left: Expr, right: Expr) -> Expr {
+    match (is_int(&left, 0), is_int(&right, 0)) {
+        (_, true) => left,
+        (true, _) => neg(right),
+        _ => binary(Operator::Sub, left, right),
+    }
+}
+
+fn mul(left: Expr, right: Expr) -> Expr {
+    if is_int(&left, 0) || is_int(&right, 0) {
+        return int(0);
+    }
+
+    binary(Operator::Mul, left, right)
+}
+
+fn div(left: Expr, right: Expr) -> Expr {
+    if is_int(&left, 0) {
+        return left;
+    }
+
+    binary(Operator::Div, left, right)
+}
+
+fn pow(left: Expr, right: Expr) -> Expr {
+    binary(Operator::Pow, left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Environment, Evaluator};
+
+    fn x() -> Expr {
+        Expr::variable("x")
+    }
+
+    /// Compares the derivative with the central difference at the points
+    fn assert_derivative(expr: Expr, points: &[f64]) {
+        let derivative = expr.derive("x").unwrap();
+        let evaluator = Evaluator::new();
+        let at = |expr: &Expr, x: f64| {
+            evaluator
+                .eval_with(expr, &Environment::new().with_variable("x", x))
+                .unwrap()
+        };
+
+        for &point in points {
+            let step = 1e-6;
+            let expected = (at(&expr, point + step) - at(&expr, point - step)) / (2.0 * step);
+            let actual = at(&derivative, point);
+
+            assert!(
+                (expected - actual).abs() < 1e-4 * expected.abs().max(1.0),
+                "{expr:?} at {point}: {actual} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let y = || Expr::variable("y");
+
+        assert_eq!(x().derive("x"), Ok(int(1)));
+        assert_eq!(y().derive("x"), Ok(int(0)));
+        assert_eq!(binary(Operator::Mul, int(3), x()).derive("x"), Ok(int(3)));
+        assert_eq!(binary(Operator::Mul, x(), y()).derive("x"), Ok(y()));
+        assert_eq!(
+            pow(x(), int(3)).derive("x"),
+            Ok(mul(int(3), pow(x(), int(2))))
+        );
+        assert_eq!(
+            Expr::unary(Operator::Neg, Expr::group(binary(Operator::Sub, y(), x()))).derive("x"),
+            Ok(int(1))
+        );
+
+        let points = [0.3, 1.7, 4.2];
+
+        assert_derivative(binary(Operator::Div, int(1), x()), &points);
+        assert_derivative(binary(Operator::Mod, pow(x(), int(2)), int(3)), &points);
+        assert_derivative(binary(Operator::Mod, int(7), x()), &points);
+        assert_derivative(pow(int(2), x()), &points);
+        assert_derivative(pow(x(), x()), &points);
+        assert_derivative(pow(x(), Expr::number(Number::Float(0.5))), &points);
+    }
+
+    #[test]
+    fn functions() {
+        let unary = |name| call(name, vec![mul(int(2), x())]);
+        let points = [0.1, 0.35, 0.45];
+
+        for name in [
+            "sqrt", "abs", "floor", "ceil", "round", "ln", "log", "exp", "sin", "cos", "tan",
+            "asin", "acos", "atan",
+        ] {
+            assert_derivative(unary(name), &points);
+        }
+
+        assert_derivative(call("log", vec![x(), int(3)]), &points);
+        assert_derivative(call("log", vec![int(3), x()]), &points);
+        assert_derivative(call("round", vec![x(), int(2)]), &[0.123]);
+        assert_derivative(
+            call("max", vec![x(), pow(x(), int(2)), int(0)]),
+            &[-2.0, 0.5, 3.0],
+        );
+        assert_derivative(call("min", vec![x(), pow(x(), int(2))]), &[-2.0, 0.5, 3.0]);
+        assert_derivative(call("max", vec![call("sin", vec![x()])]), &points);
+
+        assert_eq!(
+            call("foo", vec![x()])
+                .with_span(Span::new(1, 4))
+                .derive("x"),
+            Err(DeriveError::NotDifferentiable {
+                name: "foo".to_string(),
+                span: Span::new(1, 4)
+            })
+        );
+        assert_eq!(call("foo", vec![int(1)]).derive("x"), Ok(int(0)));
+    }
+
+    #[test]
+    fn conditionals() {
+        let positive = || binary(Operator::Gt, x(), int(0));
+        let expr = Expr::conditional(
+            positive(),
+            pow(x(), int(2)),
+            Expr::unary(Operator::Neg, x()),
+        );
+
+        assert_eq!(
+            expr.derive("x"),
+            Ok(Expr::conditional(
+                positive(),
+                mul(int(2), x()),
+                Expr::unary(Operator::Neg, int(1))
+            ))
+        );
+        assert_derivative(expr, &[-2.0, 0.5, 3.0]);
+        assert_eq!(
+            positive().with_span(Span::new(0, 5)).derive("x"),
+            Err(DeriveError::Boolean {
+                span: Span::new(0, 5)
+            })
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Module to render errors together with the source
+//! pointing to the exact place that caused them
+
+use std::fmt::Display;
+
+use crate::span::Span;
+
+/// Human readable report of the error that prints
+/// the line of the source with the underlined span, i.e.
+///
+/// ```text
+/// error: Expected operand, but the expression has ended
+///  --> 1:4
+///   |
+/// 1 | 2 b
+///   |    ^
+/// ```
+#[derive(Debug, Clone)]
+pub struct Diagnostic<'source> {
+    source: &'source str,
+    message: String,
+    span: Span,
+}
+
+impl<'source> Diagnostic<'source> {
+    pub fn new(source: &'source str, message: impl Display, span: Span) -> Self {
+        Self {
+            source,
+            message: message.to_string(),
+            span,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns 1-based line and column (in chars) of the span start
+    pub fn location(&self) -> (usize, usize) {
+        let (line_start, _) = self.line_bounds();
+        let line = self.source[..line_start].matches('\n').count() + 1;
+        let column = self.source[line_start..self.start()].chars().count() + 1;
+
+        (line, column)
+    }
+
+    /// Start of the span clamped to the source
+    /// and aligned to the char boundary
+    fn start(&self) -> usize {
+        let mut start = self.span.start.min(self.source.len());
+
+        while !self.source.is_char_boundary(start) {
+            start -= 1;
+        }
+
+        start
+    }
+
+    fn line_bounds(&self) -> (usize, usize) {
+        let start = self.start();
+        let line_start = self.source[..start].rfind('\n').map_or(0, |pos| pos + 1);
+        let line_end = self.source[start..]
+            .find('\n')
+            .map_or(self.source.len(), |pos| start + pos);
+
+        (line_start, line_end)
+    }
+}
+
+impl<'source> Display for Diagnostic<'source> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (line_start, line_end) = self.line_bounds();
+        let (line, column) = self.location();
+        let start = self.start();
+        let mut end = self.span.end.clamp(start, line_end);
+
+        while !self.source.is_char_boundary(end) {
+            end += 1;
+        }
+
+        let text = self.source[line_start..line_end].trim_end_matches('\r');
+        let underline = self.source[start..end].chars().count().max(1);
+        let gutter = " ".repeat(line.to_string().len());
+        // tabs are kept, so the carets stay under the text however wide the tab is shown
+        let padding: String = self.source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{gutter}--> {line}:{column}")?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {text}")?;
+        write!(f, "{gutter} | {padding}{}", "^".repeat(underline))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! assert_diagnostic {
+        ($source: literal, $span: expr, $expected: expr) => {
+            let diagnostic = Diagnostic::new($source, "message", $span.into());
+            assert_eq!(diagnostic.to_string(), $expected.join("\n"))
+        };
+    }
+
+    #[test]
+    fn single_line() {
+        assert_diagnostic!(
+            "1 a 2 d 0",
+            0..9,
+            [
+                "error: message",
+                " --> 1:1",
+                "  |",
+                "1 | 1 a 2 d 0",
+                "  | ^^^^^^^^^",
+            ]
+        );
+
+        assert_diagnostic!(
+            "1 : 2",
+            2..3,
+            ["error: message", " --> 1:3", "  |", "1 | 1 : 2", "  |   ^",]
+        );
+    }
+
+    #[test]
+    fn end_of_input() {
+        assert_diagnostic!(
+            "2 b",
+            Span::point(3),
+            ["error: message", " --> 1:4", "  |", "1 | 2 b", "  |    ^",]
+        );
+
+        assert_diagnostic!(
+            "",
+            Span::point(0),
+            ["error: message", " --> 1:1", "  |", "1 | ", "  | ^"]
+        );
+    }
+
+    #[test]
+    fn multiline_and_unicode() {
+        assert_diagnostic!(
+            "1 a\nää 2 c ?",
+            13..14,
+            [
+                "error: message",
+                " --> 2:8",
+                "  |",
+                "2 | ää 2 c ?",
+                "  |        ^",
+            ]
+        );
+
+        // span that crosses line end is cut at the end of the line
+        assert_diagnostic!(
+            "1 a e2\nc 3f",
+            4..11,
+            [
+                "error: message",
+                " --> 1:5",
+                "  |",
+                "1 | 1 a e2",
+                "  |     ^^",
+            ]
+        );
+    }
+
+    #[test]
+    fn tabs() {
+        assert_diagnostic!(
+            "1 a\n\t2 c\t?",
+            9..10,
+            [
+                "error: message",
+                " --> 2:6",
+                "  |",
+                "2 | \t2 c\t?",
+                "  | \t   \t^",
+            ]
+        );
+    }
+}
@@ -0,0 +1,533 @@
+//! Module that describes which symbols of the input
+//! represent operators and brackets
+
+use std::{error::Error, fmt::Display, str::FromStr, sync::OnceLock};
+
+use crate::tokens::{Branch, Group, Operator, Token};
+
+/// Meaning of the symbol inside the expression
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Symbol {
+    /// Binary operators with unary counterpart, i.e. subtraction,
+    /// are turned into unary one when operand is expected
+    Operator(Operator),
+    Group(Group),
+    /// Separator of the function arguments
+    Separator,
+    Branch(Branch),
+}
+
+impl From<Symbol> for Token {
+    fn from(symbol: Symbol) -> Self {
+        match symbol {
+            Symbol::Operator(operator) => Token::Operator(operator),
+            Symbol::Group(group) => Token::Group(group),
+            Symbol::Separator => Token::Separator,
+            Symbol::Branch(branch) => Token::Branch(branch),
+        }
+    }
+}
+
+impl From<Operator> for Symbol {
+    fn from(operator: Operator) -> Self {
+        Self::Operator(operator)
+    }
+}
+
+impl From<Group> for Symbol {
+    fn from(group: Group) -> Self {
+        Self::Group(group)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DialectError {
+    UnknownDialect(String),
+    UnknownSymbol(String),
+    InvalidSymbolDefinition(String),
+    /// Dialect has no text to print the symbol
+    MissingSymbol(Symbol),
+    /// Symbol is added with empty text that can't be matched
+    EmptySymbol(Symbol),
+    /// Exponent marker is not ASCII letter, so it could be taken for the part of the number
+    InvalidExponentMarker(char),
+    /// Comment marker is empty, so the comment would start anywhere
+    EmptyComment,
+    /// Unit bracket is empty, so the unit would start anywhere
+    EmptyUnitBrackets,
+    /// Imaginary suffix is not ASCII letter, so it could be taken for the part of the number
+    InvalidImaginarySuffix(char),
+}
+
+impl Display for DialectError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownDialect(name) => write!(f, "Unknown dialect `{name}`"),
+            Self::UnknownSymbol(name) => write!(f, "Unknown symbol `{name}`"),
+            Self::InvalidSymbolDefinition(definition) => write!(
+                f,
+                "Invalid symbol definition `{definition}`, expected `<symbol>=<text>`"
+            ),
+            Self::MissingSymbol(symbol) => write!(f, "Dialect has no text for {symbol:?}"),
+            Self::EmptySymbol(symbol) => write!(f, "Empty text for {symbol:?}"),
+            Self::InvalidExponentMarker(marker) => {
+                write!(f, "Exponent marker `{marker}` should be ASCII letter")
+            }
+            Self::EmptyComment => write!(f, "Comment markers should not be empty"),
+            Self::EmptyUnitBrackets => write!(f, "Unit brackets should not be empty"),
+            Self::InvalidImaginarySuffix(suffix) => {
+                write!(f, "Imaginary suffix `{suffix}` should be ASCII letter")
+            }
+        }
+    }
+}
+
+impl Error for DialectError {}
+
+impl From<Branch> for Symbol {
+    fn from(branch: Branch) -> Self {
+        Self::Branch(branch)
+    }
+}
+
+impl FromStr for Symbol {
+    type Err = DialectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "add" => Operator::Add.into(),
+            "sub" => Operator::Sub.into(),
+            "neg" => Operator::Neg.into(),
+            "mul" => Operator::Mul.into(),
+            "div" => Operator::Div.into(),
+            "mod" => Operator::Mod.into(),
+            "pow" => Operator::Pow.into(),
+            "eq" => Operator::Eq.into(),
+            "ne" => Operator::Ne.into(),
+            "lt" => Operator::Lt.into(),
+            "le" => Operator::Le.into(),
+            "gt" => Operator::Gt.into(),
+            "ge" => Operator::Ge.into(),
+            "and" => Operator::And.into(),
+            "or" => Operator::Or.into(),
+            "not" => Operator::Not.into(),
+            "then" => Branch::Then.into(),
+            "else" => Branch::Else.into(),
+            "open" => Group::Open.into(),
+            "close" => Group::Close.into(),
+            "separator" => Symbol::Separator,
+            _ => return Err(DialectError::UnknownSymbol(s.to_string())),
+        })
+    }
+}
+
+/// Alphabet of the expression that maps the text to the symbols.
+/// Text of the symbol could be longer than single char and
+/// when several symbols match the input the longest one is used
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Dialect {
+    symbols: Vec<(String, Symbol)>,
+    /// Set of the first bytes of the symbols, so the input that starts none is rejected at once
+    first_bytes: [u64; 4],
+    identifier_prefix: Option<String>,
+    exponent_markers: Vec<char>,
+    line_comment: Option<String>,
+    block_comment: Option<(String, String)>,
+    unit_brackets: Option<(String, String)>,
+    imaginary_suffix: Option<char>,
+}
+
+impl Dialect {
+    /// Creates dialect without any symbols
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encoding from the challenge,
+    /// i.e. `a = +`, `b = -`, `c = *`, `d = /`, `e = (`, `f = )`
+    /// extended with `g = ^` and `h = %`.
+    /// As letters are taken by operators, names start with `$`
+    /// and last until the first char that is not alphanumeric, i.e. `$sqrt e16f`,
+    /// and the exponent is marked by `E`, i.e. `1.5E-3`.
+    /// Comments are written as `// line` and `/* block */`,
+    /// conditions use the symbols of the [Dialect::standard] as they have no letters,
+    /// units are written in brackets after the operand, i.e. `5[kg]`,
+    /// and imaginary numbers end with `i`, i.e. `2.5i`
+    pub fn letters() -> Self {
+        Self::new()
+            .with_symbols([
+                ("a", Operator::Add.into()),
+                ("b", Operator::Sub.into()),
+                ("c", Operator::Mul.into()),
+                ("d", Operator::Div.into()),
+                ("e", Group::Open.into()),
+                ("f", Group::Close.into()),
+                ("g", Operator::Pow.into()),
+                ("h", Operator::Mod.into()),
+                (",", Symbol::Separator),
+            ])
+            .with_conditions()
+            .with_identifier_prefix("$")
+            .with_exponent_marker('E')
+            .and_then(|dialect| dialect.with_line_comment("//"))
+            .and_then(|dialect| dialect.with_block_comment("/*", "*/"))
+            .and_then(|dialect| dialect.with_unit_brackets("[", "]"))
+            .and_then(|dialect| dialect.with_imaginary_suffix('i'))
+            .expect("builtin markers are valid")
+    }
+
+    /// Ordinary infix notation with `+-*/^%()`, the exponent marked by `e` or `E`,
+    /// comparisons `== != < <= > >=`, logic `&& || !`, conditional `? :`
+    /// and the same comments, unit brackets and imaginary suffix as [Dialect::letters]
+    pub fn standard() -> Self {
+        Self::new()
+            .with_symbols([
+                ("+", Operator::Add.into()),
+                ("-", Operator::Sub.into()),
+                ("*", Operator::Mul.into()),
+                ("/", Operator::Div.into()),
+                ("(", Group::Open.into()),
+                (")", Group::Close.into()),
+                ("^", Operator::Pow.into()),
+                ("%", Operator::Mod.into()),
+                (",", Symbol::Separator),
+            ])
+            .with_conditions()
+            .with_exponent_marker('e')
+            .and_then(|dialect| dialect.with_exponent_marker('E'))
+            .and_then(|dialect| dialect.with_line_comment("//"))
+            .and_then(|dialect| dialect.with_block_comment("/*", "*/"))
+            .and_then(|dialect| dialect.with_unit_brackets("[", "]"))
+            .and_then(|dialect| dialect.with_imaginary_suffix('i'))
+            .expect("builtin markers are valid")
+    }
+
+    /// Symbols of the comparisons, logic and conditional shared by the builtin dialects
+    fn with_conditions(self) -> Self {
+        self.with_symbols([
+            ("==", Operator::Eq.into()),
+            ("!=", Operator::Ne.into()),
+            ("<", Operator::Lt.into()),
+            ("<=", Operator::Le.into()),
+            (">", Operator::Gt.into()),
+            (">=", Operator::Ge.into()),
+            ("&&", Operator::And.into()),
+            ("||", Operator::Or.into()),
+            ("!", Operator::Not.into()),
+            ("?", Branch::Then.into()),
+            (":", Branch::Else.into()),
+        ])
+    }
+
+    /// Adds the symbols of the builtin dialects, that all have text
+    fn with_symbols<const N: usize>(self, symbols: [(&str, Symbol); N]) -> Self {
+        symbols
+            .into_iter()
+            .try_fold(self, |dialect, (text, symbol)| {
+                dialect.with_symbol(text, symbol)
+            })
+            .expect("builtin symbols have text")
+    }
+
+    /// Shared instance of the [Dialect::letters] that is used by default
+    pub fn default_ref() -> &'static Dialect {
+        static LETTERS: OnceLock<Dialect> = OnceLock::new();
+
+        LETTERS.get_or_init(Self::letters)
+    }
+
+    /// Adds new symbol to the dialect replacing the previous meaning of the same text.
+    /// Symbols take precedence over numbers, so text starting with digit or `.`
+    /// will shadow the number literals. Symbol without text is rejected
+    pub fn with_symbol(
+        mut self,
+        text: impl Into<String>,
+        symbol: impl Into<Symbol>,
+    ) -> Result<Self, DialectError> {
+        let text = text.into();
+        let symbol = symbol.into();
+
+        if text.is_empty() {
+            return Err(DialectError::EmptySymbol(symbol));
+        }
+
+        match self.symbols.iter_mut().find(|(known, _)| *known == text) {
+            Some((_, known)) => *known = symbol,
+            None => {
+                let first = text.as_bytes()[0];
+
+                self.first_bytes[usize::from(first / 64)] |= 1 << (first % 64);
+                self.symbols.push((text, symbol));
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Requires names of the functions and variables to start with the prefix,
+    /// so they don't clash with the symbols made of letters
+    pub fn with_identifier_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.identifier_prefix = Some(prefix.into()).filter(|prefix| !prefix.is_empty());
+        self
+    }
+
+    pub fn identifier_prefix(&self) -> Option<&str> {
+        self.identifier_prefix.as_deref()
+    }
+
+    /// Allows the decimal numbers to have the exponent after the letter, i.e. `2E+5`.
+    /// Marker followed by the exponent takes precedence over the symbols inside the number,
+    /// so it should not be the letter of the symbol that could follow the number
+    pub fn with_exponent_marker(mut self, marker: char) -> Result<Self, DialectError> {
+        if !marker.is_ascii_alphabetic() {
+            return Err(DialectError::InvalidExponentMarker(marker));
+        }
+
+        if !self.exponent_markers.contains(&marker) {
+            self.exponent_markers.push(marker);
+        }
+
+        Ok(self)
+    }
+
+    /// Marker the printed decimal numbers are written with, the first one that was added
+    pub fn exponent_marker(&self) -> Option<char> {
+        self.exponent_markers.first().copied()
+    }
+
+    pub fn is_exponent_marker(&self, byte: u8) -> bool {
+        self.exponent_markers
+            .iter()
+            .any(|marker| u32::from(byte) == u32::from(*marker))
+    }
+
+    /// Skips the rest of the line after the marker, i.e. `// price with tax`.
+    /// Comments take precedence over the symbols starting with the same text
+    pub fn with_line_comment(mut self, marker: impl Into<String>) -> Result<Self, DialectError> {
+        let marker = marker.into();
+
+        if marker.is_empty() {
+            return Err(DialectError::EmptyComment);
+        }
+
+        self.line_comment = Some(marker);
+        Ok(self)
+    }
+
+    pub fn line_comment(&self) -> Option<&str> {
+        self.line_comment.as_deref()
+    }
+
+    /// Skips the text between the markers that could span several lines, i.e. `/* tax */`
+    pub fn with_block_comment(
+        mut self,
+        open: impl Into<String>,
+        close: impl Into<String>,
+    ) -> Result<Self, DialectError> {
+        let (open, close) = (open.into(), close.into());
+
+        if open.is_empty() || close.is_empty() {
+            return Err(DialectError::EmptyComment);
+        }
+
+        self.block_comment = Some((open, close));
+        Ok(self)
+    }
+
+    /// Opening and closing markers of the block comment
+    pub fn block_comment(&self) -> Option<(&str, &str)> {
+        self.block_comment
+            .as_ref()
+            .map(|(open, close)| (open.as_str(), close.as_str()))
+    }
+
+    /// Writes the unit of the operand between the brackets right after it, i.e. `5[kg]`.
+    /// Brackets take precedence over the symbols starting with the same text
+    pub fn with_unit_brackets(
+        mut self,
+        open: impl Into<String>,
+        close: impl Into<String>,
+    ) -> Result<Self, DialectError> {
+        let (open, close) = (open.into(), close.into());
+
+        if open.is_empty() || close.is_empty() {
+            return Err(DialectError::EmptyUnitBrackets);
+        }
+
+        self.unit_brackets = Some((open, close));
+        Ok(self)
+    }
+
+    /// Opening and closing brackets of the unit
+    pub fn unit_brackets(&self) -> Option<(&str, &str)> {
+        self.unit_brackets
+            .as_ref()
+            .map(|(open, close)| (open.as_str(), close.as_str()))
+    }
+
+    /// Marks the decimal number as imaginary by the letter right after it, i.e. `2i`.
+    /// Suffix should not be the letter of the symbol, the exponent marker or the digit
+    pub fn with_imaginary_suffix(mut self, suffix: char) -> Result<Self, DialectError> {
+        if !suffix.is_ascii_alphabetic() {
+            return Err(DialectError::InvalidImaginarySuffix(suffix));
+        }
+
+        self.imaginary_suffix = Some(suffix);
+        Ok(self)
+    }
+
+    pub fn imaginary_suffix(&self) -> Option<char> {
+        self.imaginary_suffix
+    }
+
+    pub fn is_imaginary_suffix(&self, byte: u8) -> bool {
+        self.imaginary_suffix
+            .is_some_and(|suffix| u32::from(byte) == u32::from(suffix))
+    }
+
+    /// Adds symbol from the definition in form of `<symbol>=<text>`, i.e. `add=plus`
+    pub fn with_definition(self, definition: &str) -> Result<Self, DialectError> {
+        let Some((symbol, text)) = definition.split_once('=') else {
+            return Err(DialectError::InvalidSymbolDefinition(
+                definition.to_string(),
+            ));
+        };
+
+        if text.is_empty() {
+            return Err(DialectError::InvalidSymbolDefinition(
+                definition.to_string(),
+            ));
+        }
+
+        self.with_symbol(text, symbol.trim().parse::<Symbol>()?)
+    }
+
+    /// Finds the longest symbol that the input starts with
+    /// and returns its length in bytes along with the meaning
+    pub fn match_symbol(&self, input: &str) -> Option<(usize, &Symbol)> {
+        let first = *input.as_bytes().first()?;
+        let mut longest = None;
+
+        if self.first_bytes[usize::from(first / 64)] & (1 << (first % 64)) == 0 {
+            return None;
+        }
+
+        // first byte rejects most of the symbols, the rest are compared as bytes,
+        // as the `str` pattern builds the searcher for every call
+        for (text, symbol) in &self.symbols {
+            if text.as_bytes()[0] == first
+                && input.as_bytes().starts_with(text.as_bytes())
+                && longest.map_or(true, |(len, _)| text.len() > len)
+            {
+                longest = Some((text.len(), symbol));
+            }
+        }
+
+        longest
+    }
+
+    /// Length in bytes of the longest text of the symbols
+    pub(crate) fn longest_symbol(&self) -> usize {
+        self.symbols
+            .iter()
+            .map(|(text, _)| text.len())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Text that represents the symbol in this dialect
+    pub fn text(&self, symbol: &Symbol) -> Option<&str> {
+        self.symbols
+            .iter()
+            .find(|(_, known)| known == symbol)
+            .map(|(text, _)| text.as_str())
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = DialectError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "letters" => Ok(Self::letters()),
+            "standard" => Ok(Self::standard()),
+            _ => Err(DialectError::UnknownDialect(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_match() {
+        let dialect = Dialect::standard()
+            .with_symbol("**", Operator::Mul)
+            .unwrap()
+            .with_symbol("*", Operator::Add)
+            .unwrap();
+
+        assert_eq!(
+            dialect.match_symbol("**2"),
+            Some((2, &Symbol::Operator(Operator::Mul)))
+        );
+        assert_eq!(
+            dialect.match_symbol("*2"),
+            Some((1, &Symbol::Operator(Operator::Add)))
+        );
+        assert_eq!(dialect.match_symbol("2*"), None);
+    }
+
+    #[test]
+    fn definitions() {
+        let dialect = Dialect::new()
+            .with_definition("add=plus")
+            .unwrap()
+            .with_definition("open=<<")
+            .unwrap();
+
+        assert_eq!(
+            dialect.match_symbol("plus"),
+            Some((4, &Symbol::Operator(Operator::Add)))
+        );
+        assert_eq!(dialect.text(&Symbol::Group(Group::Open)), Some("<<"));
+
+        assert_eq!(
+            Dialect::new().with_definition("power=^"),
+            Err(DialectError::UnknownSymbol("power".to_string()))
+        );
+        assert_eq!(
+            Dialect::new().with_definition("add"),
+            Err(DialectError::InvalidSymbolDefinition("add".to_string()))
+        );
+        assert_eq!(
+            Dialect::new().with_symbol("", Operator::Add),
+            Err(DialectError::EmptySymbol(Symbol::Operator(Operator::Add)))
+        );
+        assert_eq!(
+            Dialect::new().with_exponent_marker('1'),
+            Err(DialectError::InvalidExponentMarker('1'))
+        );
+        assert_eq!(
+            Dialect::new().with_line_comment(""),
+            Err(DialectError::EmptyComment)
+        );
+        assert_eq!(
+            Dialect::new().with_block_comment("/*", ""),
+            Err(DialectError::EmptyComment)
+        );
+        assert_eq!(
+            Dialect::new().with_unit_brackets("", "]"),
+            Err(DialectError::EmptyUnitBrackets)
+        );
+        assert_eq!(
+            Dialect::new().with_imaginary_suffix('_'),
+            Err(DialectError::InvalidImaginarySuffix('_'))
+        );
+        assert_eq!(
+            "other".parse::<Dialect>(),
+            Err(DialectError::UnknownDialect("other".to_string()))
+        );
+    }
+}
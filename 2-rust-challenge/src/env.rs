@@ -0,0 +1,68 @@
+//! Module with the values of the variables
+//! that are provided at the time of the evaluation
+
+use std::collections::HashMap;
+
+/// Named values of the variables used inside the expression
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Environment {
+    variables: HashMap<String, f64>,
+}
+
+impl Environment {
+    /// Creates environment without any variables
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the variable replacing the previous value with the same name
+    pub fn with_variable(mut self, name: impl Into<String>, value: f64) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Sets the value of the variable, so the environment could be reused for other inputs
+    pub fn set(&mut self, name: impl Into<String>, value: f64) {
+        self.variables.insert(name.into(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.variables.get(name).copied()
+    }
+}
+
+impl<Name: Into<String>> FromIterator<(Name, f64)> for Environment {
+    fn from_iter<T: IntoIterator<Item = (Name, f64)>>(iter: T) -> Self {
+        Self {
+            variables: iter
+                .into_iter()
+                .map(|(name, value)| (name.into(), value))
+                .collect(),
+        }
+    }
+}
+
+impl From<HashMap<String, f64>> for Environment {
+    fn from(variables: HashMap<String, f64>) -> Self {
+        Self { variables }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variables() {
+        let mut env = Environment::new().with_variable("x", 1.0);
+
+        assert_eq!(env.get("x"), Some(1.0));
+        assert_eq!(env.get("y"), None);
+
+        env.set("x", 2.0);
+        assert_eq!(env.get("x"), Some(2.0));
+
+        let env: Environment = [("a", 1.0), ("b", 2.0)].into_iter().collect();
+        assert_eq!(env.get("b"), Some(2.0));
+    }
+}
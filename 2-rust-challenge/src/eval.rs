@@ -1,17 +1,80 @@
-//! Module to evaluate parsed stream of tokens in
-//! prefix notation and generates single output
+//! Module to evaluate parsed expression tree
+//! and generates single output
 
-use super::tokens::*;
+use super::{
+    compile::CompiledExpr,
+    env::Environment,
+    float::Operation,
+    functions::{FunctionArity, Functions},
+    numeric::{self, Numeric},
+    parse::{Expr, ExprKind},
+    span::Span,
+    tokens::{Arity, Operator},
+    value::{Type, Value},
+};
 
 #[derive(Debug, PartialEq)]
 pub enum CalculationError {
     ZeroDivision,
+    ZeroModulus,
+    /// Negative number raised to the fractional power has no real result
+    FractionalPowerOfNegative,
+    /// Function is not defined for the provided arguments, i.e. `sqrt(-1)`
+    OutOfDomain,
+    /// Fractional power has no exact result in general, i.e. `2 ^ 0.5`
+    FractionalPower,
+    /// Result is too large to be calculated
+    Overflow,
+    /// Number can't be represented exactly, i.e. `2.5` as integer
+    Inexact,
+    /// Floating point result is too large to be finite, see [FloatPolicy](crate::FloatPolicy)
+    FloatOverflow(Operation),
+    /// Floating point result is too small to be normal and loses precision
+    FloatUnderflow(Operation),
+    /// Floating point result is not a number, i.e. `0 * inf`
+    NaN(Operation),
+    /// Operand of the floating point operation is infinite or not a number
+    NonFinite(Operation),
+    /// Numbers have no units, only the [Quantity](crate::Quantity) has them
+    UnitsUnsupported,
+    /// Unit is missing from the [Units](crate::Units) or is written wrong
+    UnknownUnit(String),
+    /// Quantities have different dimensions, i.e. grams added to dollars
+    IncompatibleUnits(String, String),
+    /// Quantity with the unit is raised to the fractional power
+    FractionalPowerOfUnit,
+    /// Real numbers have no imaginary literals, only the [Complex](crate::Complex) has them
+    ComplexUnsupported,
+    /// Operation is defined only for the real numbers, i.e. the remainder
+    NonReal(Operation),
 }
 
 impl std::fmt::Display for CalculationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::ZeroDivision => write!(f, "division by zero"),
+            Self::ZeroModulus => write!(f, "remainder of division by zero"),
+            Self::FractionalPowerOfNegative => {
+                write!(f, "negative number raised to the fractional power")
+            }
+            Self::OutOfDomain => write!(f, "argument is outside of the function domain"),
+            Self::FractionalPower => write!(f, "fractional power can't be calculated exactly"),
+            Self::Overflow => write!(f, "result is too large"),
+            Self::Inexact => write!(f, "number can't be represented exactly"),
+            Self::FloatOverflow(operation) => write!(f, "{operation} overflowed to infinity"),
+            Self::FloatUnderflow(operation) => write!(f, "{operation} underflowed"),
+            Self::NaN(operation) => write!(f, "{operation} produced NaN"),
+            Self::NonFinite(operation) => write!(f, "{operation} got non-finite operand"),
+            Self::UnitsUnsupported => write!(f, "units require the evaluation of quantities"),
+            Self::UnknownUnit(unit) => write!(f, "unknown unit `{unit}`"),
+            Self::IncompatibleUnits(left, right) => {
+                write!(f, "incompatible units `{left}` and `{right}`")
+            }
+            Self::FractionalPowerOfUnit => {
+                write!(f, "quantity with the unit raised to the fractional power")
+            }
+            Self::ComplexUnsupported => write!(f, "imaginary numbers require complex evaluation"),
+            Self::NonReal(operation) => write!(f, "{operation} got non-real operand"),
         }
     }
 }
@@ -20,19 +83,79 @@ impl std::error::Error for CalculationError {}
 
 #[derive(Debug, PartialEq)]
 pub enum EvalError {
-    UnexpectedToken(Token),
-    UnexpectedEndOfInput,
-    UnconsumedToken(Token),
-    CalculationError(CalculationError),
+    ArityMismatch {
+        operator: Operator,
+        expected: Arity,
+        actual: Arity,
+        span: Span,
+    },
+    CalculationError {
+        error: CalculationError,
+        span: Span,
+    },
+    UnknownFunction {
+        name: String,
+        span: Span,
+    },
+    /// Variable is missing from the [Environment]
+    UnknownVariable {
+        name: String,
+        span: Span,
+    },
+    ArgumentCount {
+        name: String,
+        expected: FunctionArity,
+        actual: usize,
+        span: Span,
+    },
+    /// Operand or result has the wrong type, i.e. the boolean added to the number
+    TypeMismatch {
+        expected: Type,
+        actual: Type,
+        span: Span,
+    },
+}
+
+impl EvalError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::ArityMismatch { span, .. }
+            | Self::CalculationError { span, .. }
+            | Self::UnknownFunction { span, .. }
+            | Self::UnknownVariable { span, .. }
+            | Self::ArgumentCount { span, .. }
+            | Self::TypeMismatch { span, .. } => *span,
+        }
+    }
 }
 
 impl std::fmt::Display for EvalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::UnexpectedToken(_) => write!(f, "Unexpected token"),
-            Self::UnexpectedEndOfInput => write!(f, "Input stream has ended unexpectedly"),
-            Self::UnconsumedToken(_) => write!(f, "Expression was calculated, but the stream contains more elements that were ignored"),
-            Self::CalculationError(err) => write!(f, "{err}"),
+            Self::ArityMismatch {
+                operator,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "Operator {operator:?} expects {expected} operand(s), but {actual} were provided"
+            ),
+            Self::CalculationError { error, .. } => write!(f, "{error}"),
+            Self::UnknownFunction { name, .. } => write!(f, "Unknown function `{name}`"),
+            Self::UnknownVariable { name, .. } => write!(f, "Unknown variable `{name}`"),
+            Self::ArgumentCount {
+                name,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "Function `{name}` expects {expected} argument(s), but {actual} were provided"
+            ),
+            Self::TypeMismatch {
+                expected, actual, ..
+            } => write!(f, "Expected {expected}, but found {actual}"),
         }
     }
 }
@@ -40,116 +163,300 @@ impl std::fmt::Display for EvalError {
 impl std::error::Error for EvalError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
-            Self::CalculationError(err) => Some(err),
+            Self::CalculationError { error, .. } => Some(error),
             _ => None,
         }
     }
 }
 
-impl From<CalculationError> for EvalError {
-    fn from(error: CalculationError) -> Self {
-        Self::CalculationError(error)
-    }
-}
-
 pub type Result<T> = std::result::Result<T, EvalError>;
 
-const ZERO: f64 = 0.0;
+/// Evaluates the expression tree to the numbers of type `N`, which is `f64` by default
+pub struct Evaluator<N: Numeric = f64> {
+    functions: Functions,
+    context: N::Context,
+}
 
-#[derive(Default)]
-pub struct Evaluator;
+impl<N: Numeric> Default for Evaluator<N> {
+    fn default() -> Self {
+        Self::with_functions(Functions::builtin())
+    }
+}
 
 impl Evaluator {
+    /// Creates evaluator over `f64` with [Functions::builtin],
+    /// use [Evaluator::default] for other numbers
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn eval<'token>(&self, tokens: &mut impl Iterator<Item = &'token Token>) -> Result<f64> {
-        let value = self.eval_inner(tokens)?;
+    /// Checks the expression once, so it is evaluated many times without the overhead
+    pub fn compile(&self, expr: &Expr) -> Result<CompiledExpr> {
+        Ok(CompiledExpr::new(expr, &self.functions)?.with_policy(self.context))
+    }
+}
 
-        if let Some(token) = tokens.next() {
-            return Err(EvalError::UnconsumedToken(token.clone()));
+impl<N: Numeric> Evaluator<N> {
+    pub fn with_functions(functions: Functions) -> Self {
+        Self {
+            functions,
+            context: N::Context::default(),
         }
+    }
 
-        Ok(value)
+    /// Sets up the arithmetic, i.e. the [DivisionPolicy](crate::DivisionPolicy) of the [Numeral](crate::Numeral)
+    pub fn with_context(mut self, context: N::Context) -> Self {
+        self.context = context;
+        self
     }
 
-    fn eval_inner<'token>(&self, tokens: &mut impl Iterator<Item = &'token Token>) -> Result<f64> {
-        let Some(token) = tokens.next() else {
-            return Err(EvalError::UnexpectedEndOfInput);
+    pub fn functions(&self) -> &Functions {
+        &self.functions
+    }
+
+    pub fn context(&self) -> &N::Context {
+        &self.context
+    }
+
+    /// Evaluates the expression without any variables
+    pub fn eval(&self, expr: &Expr) -> Result<N> {
+        self.eval_with(expr, &Environment::new())
+    }
+
+    /// Evaluates the expression taking values of the variables from the environment,
+    /// the boolean result is the [EvalError::TypeMismatch]
+    pub fn eval_with(&self, expr: &Expr, env: &Environment) -> Result<N> {
+        self.eval_number(expr, env)
+    }
+
+    /// Evaluates the expression that could yield the boolean without any variables
+    pub fn eval_value(&self, expr: &Expr) -> Result<Value<N>> {
+        self.eval_value_with(expr, &Environment::new())
+    }
+
+    /// Evaluates the expression that could yield the boolean taking values of the variables
+    /// from the environment. Types are checked as the values are calculated, so the branch
+    /// that is skipped by the condition or the short-circuit is not checked
+    pub fn eval_value_with(&self, expr: &Expr, env: &Environment) -> Result<Value<N>> {
+        let calculation_error = |error| EvalError::CalculationError {
+            error,
+            span: expr.span,
         };
 
-        match token {
-            Token::Number(Number::Float(num)) => Ok(*num),
-            Token::Number(Number::Int(num)) => Ok(*num as f64),
-            Token::Operator(operator) => {
-                let arity = operator.arity();
-                let right_arg = if arity > 0 {
-                    self.eval_inner(tokens)?
-                } else {
-                    ZERO
+        match &expr.kind {
+            ExprKind::Number(number) => N::from_number(number, &self.context)
+                .map(Value::Number)
+                .map_err(calculation_error),
+            ExprKind::Variable(name) => {
+                let Some(value) = env.get(name) else {
+                    return Err(EvalError::UnknownVariable {
+                        name: name.clone(),
+                        span: expr.span,
+                    });
                 };
-                let left_arg = if arity > 1 {
-                    self.eval_inner(tokens)?
-                } else {
-                    ZERO
+
+                N::from_f64(value, &self.context)
+                    .map(Value::Number)
+                    .map_err(calculation_error)
+            }
+            ExprKind::Group(inner) => self.eval_value_with(inner, env),
+            ExprKind::Call { name, args } => {
+                let Some(function) = self.functions.get(name) else {
+                    return Err(EvalError::UnknownFunction {
+                        name: name.clone(),
+                        span: expr.span,
+                    });
                 };
 
-                Ok(match operator {
-                    Operator::Neg => -right_arg,
-                    Operator::Add => left_arg + right_arg,
-                    Operator::Sub => left_arg - right_arg,
-                    Operator::Mul => left_arg * right_arg,
-                    Operator::Div => {
-                        if right_arg == ZERO {
-                            return Err(CalculationError::ZeroDivision.into());
-                        }
-
-                        left_arg / right_arg
+                if !function.arity().accepts(args.len()) {
+                    return Err(EvalError::ArgumentCount {
+                        name: name.clone(),
+                        expected: function.arity(),
+                        actual: args.len(),
+                        span: expr.span,
+                    });
+                }
+
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval_number(arg, env))
+                    .collect::<Result<Vec<_>>>()?;
+
+                N::call(function, name, &args, &self.context)
+                    .map(Value::Number)
+                    .map_err(calculation_error)
+            }
+            ExprKind::Unary { operator, operand } => {
+                check_arity(operator, 1, expr.span)?;
+
+                match operator {
+                    Operator::Not => Ok(Value::Bool(!self.eval_bool(operand, env)?)),
+                    Operator::Neg => self
+                        .eval_number(operand, env)?
+                        .neg(&self.context)
+                        .map(Value::Number)
+                        .map_err(calculation_error),
+                    _ => unreachable!("arity is checked"),
+                }
+            }
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                check_arity(operator, 2, expr.span)?;
+
+                match operator {
+                    // right operand is skipped when the left one decides the result
+                    Operator::And => Ok(Value::Bool(
+                        self.eval_bool(left, env)? && self.eval_bool(right, env)?,
+                    )),
+                    Operator::Or => Ok(Value::Bool(
+                        self.eval_bool(left, env)? || self.eval_bool(right, env)?,
+                    )),
+                    Operator::Eq | Operator::Ne => {
+                        let left_arg = self.eval_value_with(left, env)?;
+                        let right_arg = self.eval_value_with(right, env)?;
+
+                        Ok(Value::Bool(match (left_arg, right_arg) {
+                            (Value::Number(left_arg), Value::Number(right_arg)) => {
+                                numeric::compare(operator, &left_arg, &right_arg)
+                                    .map_err(calculation_error)?
+                            }
+                            (Value::Bool(left_arg), Value::Bool(right_arg)) => {
+                                (left_arg == right_arg) == (*operator == Operator::Eq)
+                            }
+                            (left_arg, right_arg) => {
+                                return Err(EvalError::TypeMismatch {
+                                    expected: left_arg.value_type(),
+                                    actual: right_arg.value_type(),
+                                    span: right.span,
+                                })
+                            }
+                        }))
+                    }
+                    _ if operator.is_comparison() => {
+                        let left_arg = self.eval_number(left, env)?;
+                        let right_arg = self.eval_number(right, env)?;
+
+                        numeric::compare(operator, &left_arg, &right_arg)
+                            .map(Value::Bool)
+                            .map_err(calculation_error)
                     }
-                })
+                    _ => {
+                        let left_arg = self.eval_number(left, env)?;
+                        let right_arg = self.eval_number(right, env)?;
+
+                        numeric::calculate(operator, left_arg, right_arg, &self.context)
+                            .map(Value::Number)
+                            .map_err(|error| binary_error(error, expr.span, right.span))
+                    }
+                }
             }
-            _ => Err(EvalError::UnexpectedToken(token.clone())),
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => match self.eval_bool(condition, env)? {
+                true => self.eval_value_with(then, env),
+                false => self.eval_value_with(otherwise, env),
+            },
+            ExprKind::Unit { operand, unit } => self
+                .eval_number(operand, env)?
+                .with_unit(unit, &self.context)
+                .map(Value::Number)
+                .map_err(calculation_error),
+        }
+    }
+
+    /// Evaluates the operand that should be the number
+    fn eval_number(&self, expr: &Expr, env: &Environment) -> Result<N> {
+        match self.eval_value_with(expr, env)? {
+            Value::Number(number) => Ok(number),
+            value => Err(type_mismatch(Type::Number, &value, expr.span)),
+        }
+    }
+
+    /// Evaluates the operand that should be the boolean
+    fn eval_bool(&self, expr: &Expr, env: &Environment) -> Result<bool> {
+        match self.eval_value_with(expr, env)? {
+            Value::Bool(value) => Ok(value),
+            value => Err(type_mismatch(Type::Bool, &value, expr.span)),
         }
     }
 }
 
+fn type_mismatch<N>(expected: Type, value: &Value<N>, span: Span) -> EvalError {
+    EvalError::TypeMismatch {
+        expected,
+        actual: value.value_type(),
+        span,
+    }
+}
+
+/// Reports the error of the binary operation pointing to the operand
+/// that makes the operation invalid if there is one
+pub(crate) fn binary_error(error: CalculationError, span: Span, right_span: Span) -> EvalError {
+    let span = match error {
+        CalculationError::ZeroDivision | CalculationError::ZeroModulus => right_span,
+        _ => span,
+    };
+
+    EvalError::CalculationError { error, span }
+}
+
+pub(crate) fn check_arity(operator: &Operator, actual: Arity, span: Span) -> Result<()> {
+    let expected = operator.arity();
+
+    if expected != actual {
+        return Err(EvalError::ArityMismatch {
+            operator: operator.clone(),
+            expected,
+            actual,
+            span,
+        });
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tokens::{Number, Operator, Token};
+    use crate::{
+        float::FloatPolicy,
+        numeral::{DivisionPolicy, Numeral},
+        rational::Rational,
+        tokens::Number,
+    };
 
     macro_rules! assert_eval {
-        ($result: literal $(, $expr: expr)*) => {
+        ($result: literal, $expr: expr) => {
             let evaluator = Evaluator::new();
-            let tokens = vec![$($expr,)*];
-            assert_eq!(evaluator.eval(&mut tokens.iter()).unwrap(), $result)
+            assert_eq!(evaluator.eval(&$expr).unwrap(), $result)
         };
     }
 
     macro_rules! assert_eval_error {
-        ($err: expr $(, $expr: expr)*) => {
+        ($err: expr, $expr: expr) => {
             let evaluator = Evaluator::new();
-            let tokens = vec![$($expr,)*];
-            assert_eq!(evaluator.eval(&mut tokens.iter()).unwrap_err(), $err)
+            assert_eq!(evaluator.eval(&$expr).unwrap_err(), $err)
         };
     }
 
+    fn int(num: i32) -> Expr {
+        Expr::number(Number::Int(num.into()))
+    }
+
+    fn float(num: f64) -> Expr {
+        Expr::number(Number::Float(num))
+    }
+
     #[test]
     fn simple_expr() {
-        assert_eval!(
-            3.0,
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(1)),
-            Token::Number(Number::Int(2))
-        );
+        assert_eval!(3.0, Expr::binary(Operator::Add, int(1), int(2)));
 
-        assert_eval!(
-            5.0,
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Float(2.5))
-        );
+        assert_eval!(5.0, Expr::binary(Operator::Mul, float(2.5), int(2)));
     }
 
     #[test]
@@ -157,84 +464,478 @@ mod tests {
         // (2 + 2 * 2 - 4 + 50 + (10 - 30)) / (3 + 2.8 * 2 - (0.3 * 2))
         assert_eval!(
             4.0,
-            // () / ()
-            Token::Operator(Operator::Div),
-            // (2+...)
-            Token::Operator(Operator::Sub),
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Float(0.3)),
-            Token::Operator(Operator::Add),
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Float(2.8)),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Int(3)),
-            // (3+...)
-            Token::Operator(Operator::Add),
-            // (10 - 30)
-            Token::Operator(Operator::Sub),
-            Token::Number(Number::Int(30)),
-            Token::Number(Number::Int(10)),
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(50)),
-            Token::Operator(Operator::Sub),
-            Token::Number(Number::Int(4)),
-            Token::Operator(Operator::Add),
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Int(2))
+            Expr::binary(
+                Operator::Div,
+                Expr::group(Expr::binary(
+                    Operator::Add,
+                    Expr::binary(
+                        Operator::Add,
+                        Expr::binary(
+                            Operator::Sub,
+                            Expr::binary(
+                                Operator::Add,
+                                int(2),
+                                Expr::binary(Operator::Mul, int(2), int(2))
+                            ),
+                            int(4)
+                        ),
+                        int(50)
+                    ),
+                    Expr::group(Expr::binary(Operator::Sub, int(10), int(30)))
+                )),
+                Expr::group(Expr::binary(
+                    Operator::Sub,
+                    Expr::binary(
+                        Operator::Add,
+                        int(3),
+                        Expr::binary(Operator::Mul, float(2.8), int(2))
+                    ),
+                    Expr::group(Expr::binary(Operator::Mul, float(0.3), int(2)))
+                ))
+            )
         );
     }
 
     #[test]
-    fn empty_expr() {
-        assert_eval_error!(EvalError::UnexpectedEndOfInput);
+    fn wrong_arity() {
+        assert_eval_error!(
+            EvalError::ArityMismatch {
+                operator: Operator::Add,
+                expected: 2,
+                actual: 1,
+                span: Span::default()
+            },
+            Expr::unary(Operator::Add, int(2))
+        );
+
+        assert_eval_error!(
+            EvalError::ArityMismatch {
+                operator: Operator::Neg,
+                expected: 1,
+                actual: 2,
+                span: Span::default()
+            },
+            Expr::binary(Operator::Neg, int(1), int(2))
+        );
     }
 
     #[test]
-    fn unexpected_end() {
+    fn division_by_zero() {
         assert_eval_error!(
-            EvalError::UnexpectedEndOfInput,
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(2))
+            EvalError::CalculationError {
+                error: CalculationError::ZeroDivision,
+                span: Span::default()
+            },
+            Expr::binary(Operator::Div, int(1), int(0))
         );
-
         assert_eval_error!(
-            EvalError::UnexpectedEndOfInput,
-            Token::Operator(Operator::Neg)
+            EvalError::CalculationError {
+                error: CalculationError::ZeroDivision,
+                span: Span::default()
+            },
+            Expr::binary(Operator::Div, float(1.0), float(0.0))
         );
     }
 
     #[test]
-    fn unconsumed_tokens() {
+    fn power_and_modulo() {
+        assert_eval!(8.0, Expr::binary(Operator::Pow, int(2), int(3)));
+        assert_eval!(0.25, Expr::binary(Operator::Pow, int(2), int(-2)));
+        assert_eval!(-8.0, Expr::binary(Operator::Pow, int(-2), int(3)));
+        assert_eval!(3.0, Expr::binary(Operator::Pow, int(9), float(0.5)));
+        assert_eval!(1.0, Expr::binary(Operator::Mod, int(7), int(3)));
+        assert_eval!(-1.0, Expr::binary(Operator::Mod, int(-7), int(3)));
+        assert_eval!(1.5, Expr::binary(Operator::Mod, float(5.5), int(2)));
+
         assert_eval_error!(
-            EvalError::UnconsumedToken(Token::Number(Number::Int(3))),
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(1)),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Int(3))
+            EvalError::CalculationError {
+                error: CalculationError::ZeroModulus,
+                span: Span::new(4, 5)
+            },
+            Expr::binary(
+                Operator::Mod,
+                int(1).with_span(Span::new(0, 1)),
+                int(0).with_span(Span::new(4, 5))
+            )
         );
         assert_eval_error!(
-            EvalError::UnconsumedToken(Token::Number(Number::Int(2))),
-            Token::Number(Number::Int(1)),
-            Token::Number(Number::Int(2))
+            EvalError::CalculationError {
+                error: CalculationError::FractionalPowerOfNegative,
+                span: Span::new(0, 7)
+            },
+            Expr::binary(
+                Operator::Pow,
+                int(-8).with_span(Span::new(0, 2)),
+                float(0.5).with_span(Span::new(4, 7))
+            )
+        );
+        assert_eval_error!(
+            EvalError::CalculationError {
+                error: CalculationError::ZeroDivision,
+                span: Span::default()
+            },
+            Expr::binary(Operator::Pow, int(0), int(-1))
         );
     }
 
     #[test]
-    fn division_by_zero() {
+    fn function_calls() {
+        assert_eval!(
+            4.0,
+            Expr::call("sqrt", vec![Expr::binary(Operator::Add, int(7), int(9))])
+        );
+        assert_eval!(1.0, Expr::call("min", vec![int(3), int(1), int(2)]));
+
+        let evaluator = Evaluator::with_functions(Functions::new().with_closure(
+            "double",
+            FunctionArity::exact(1),
+            |args| Ok(args[0] * 2.0),
+        ));
+
+        assert_eq!(evaluator.eval(&Expr::call("double", vec![int(4)])), Ok(8.0));
+        assert_eq!(
+            evaluator.eval(&Expr::call("sqrt", vec![int(4)])),
+            Err(EvalError::UnknownFunction {
+                name: "sqrt".to_string(),
+                span: Span::default()
+            })
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let evaluator = Evaluator::new();
+        let env = Environment::new()
+            .with_variable("price", 10.0)
+            .with_variable("tax", 0.2);
+        let expr = Expr::binary(
+            Operator::Mul,
+            Expr::variable("price"),
+            Expr::binary(Operator::Add, int(1), Expr::variable("tax")),
+        );
+
+        assert_eq!(evaluator.eval_with(&expr, &env), Ok(12.0));
+        assert_eq!(
+            evaluator.eval_with(&expr, &env.clone().with_variable("price", 20.0)),
+            Ok(24.0)
+        );
+        assert_eq!(
+            evaluator.eval(&expr),
+            Err(EvalError::UnknownVariable {
+                name: "price".to_string(),
+                span: Span::default()
+            })
+        );
+    }
+
+    #[test]
+    fn exact() {
+        let evaluator = Evaluator::<Rational>::default();
+        let eval = |expr| evaluator.eval(&expr);
+        let third = Expr::binary(Operator::Div, int(1), int(3));
+
+        assert_eq!(
+            eval(Expr::binary(Operator::Mul, third.clone(), int(3))),
+            Ok(Rational::from(1))
+        );
+        assert_eq!(
+            eval(Expr::binary(Operator::Add, float(0.1), float(0.2))).map(|x| x.to_string()),
+            Ok("3/10".to_string())
+        );
+        assert_eq!(
+            eval(Expr::binary(Operator::Pow, third.clone(), int(-2))),
+            Ok(Rational::from(9))
+        );
+        assert_eq!(
+            eval(Expr::call("sqrt", vec![int(16)])),
+            Ok(Rational::from(4))
+        );
+        assert_eq!(
+            eval(Expr::binary(Operator::Pow, int(2), third)),
+            Err(EvalError::CalculationError {
+                error: CalculationError::FractionalPower,
+                span: Span::default()
+            })
+        );
+    }
+
+    #[test]
+    fn numeral() {
+        let eval = |policy, expr| {
+            Evaluator::<Numeral>::default()
+                .with_context(policy)
+                .eval(&expr)
+        };
+        let big = |digits: &str| Numeral::Int(digits.parse().unwrap());
+        let half = Expr::binary(Operator::Div, int(7), int(2));
+
+        assert_eq!(
+            eval(
+                DivisionPolicy::Rational,
+                Expr::binary(Operator::Mul, int(i32::MAX), int(i32::MAX))
+            ),
+            Ok(big("4611686014132420609"))
+        );
+        assert_eq!(
+            eval(
+                DivisionPolicy::Float,
+                Expr::binary(Operator::Pow, int(10), int(20))
+            ),
+            Ok(big("100000000000000000000"))
+        );
+        assert_eq!(
+            eval(
+                DivisionPolicy::Float,
+                Expr::binary(Operator::Div, int(6), int(3))
+            ),
+            Ok(big("2"))
+        );
+        assert_eq!(
+            eval(DivisionPolicy::Rational, half.clone()).map(|x| x.to_string()),
+            Ok("7/2".to_string())
+        );
+        assert_eq!(
+            eval(DivisionPolicy::Float, half.clone()),
+            Ok(Numeral::Float(3.5))
+        );
+        assert_eq!(
+            eval(
+                DivisionPolicy::Rational,
+                Expr::binary(Operator::Mul, half, int(2))
+            ),
+            Ok(big("7"))
+        );
+        assert_eq!(
+            eval(
+                DivisionPolicy::Float,
+                Expr::binary(Operator::Pow, int(9), float(0.5))
+            ),
+            Ok(Numeral::Float(3.0))
+        );
+        assert_eq!(
+            eval(
+                DivisionPolicy::Rational,
+                Expr::binary(Operator::Mod, int(-7), int(3))
+            ),
+            Ok(big("-1"))
+        );
+        assert_eq!(
+            eval(
+                DivisionPolicy::Rational,
+                Expr::binary(Operator::Pow, int(2), int(1 << 30))
+            ),
+            Err(EvalError::CalculationError {
+                error: CalculationError::Overflow,
+                span: Span::default()
+            })
+        );
+    }
+
+    #[test]
+    fn strict() {
+        let strict = Evaluator::new().with_context(FloatPolicy::Strict);
+        let huge = Expr::binary(Operator::Mul, float(1e200), float(1e200));
+        let error = |error| {
+            Err(EvalError::CalculationError {
+                error,
+                span: Span::default(),
+            })
+        };
+
+        assert_eq!(Evaluator::new().eval(&huge), Ok(f64::INFINITY));
+        assert_eq!(
+            strict.eval(&huge),
+            error(CalculationError::FloatOverflow(Operation::Operator(
+                Operator::Mul
+            )))
+        );
+        assert_eq!(
+            strict.eval(&Expr::binary(Operator::Div, float(1e-200), float(1e200))),
+            error(CalculationError::FloatUnderflow(Operation::Operator(
+                Operator::Div
+            )))
+        );
+        assert_eq!(
+            strict.eval(&Expr::call("exp", vec![int(1000)])),
+            error(CalculationError::FloatOverflow(Operation::Function(
+                "exp".to_string()
+            )))
+        );
+        assert_eq!(
+            strict.eval_with(
+                &Expr::binary(Operator::Sub, Expr::variable("x"), Expr::variable("x")),
+                &Environment::new().with_variable("x", f64::INFINITY)
+            ),
+            error(CalculationError::FloatOverflow(Operation::Variable))
+        );
+        assert_eq!(
+            strict.eval(&float(1e-310)),
+            error(CalculationError::FloatUnderflow(Operation::Literal))
+        );
+        assert_eq!(
+            strict.eval(&Expr::binary(Operator::Sub, float(0.1), float(0.1))),
+            Ok(0.0)
+        );
+    }
+
+    #[test]
+    fn function_errors() {
+        assert_eval_error!(
+            EvalError::ArgumentCount {
+                name: "sqrt".to_string(),
+                expected: FunctionArity::exact(1),
+                actual: 2,
+                span: Span::default()
+            },
+            Expr::call("sqrt", vec![int(1), int(2)])
+        );
         assert_eval_error!(
-            EvalError::CalculationError(CalculationError::ZeroDivision),
-            Token::Operator(Operator::Div),
-            Token::Number(Number::Int(0)),
-            Token::Number(Number::Int(1))
+            EvalError::ArgumentCount {
+                name: "max".to_string(),
+                expected: FunctionArity::at_least(1),
+                actual: 0,
+                span: Span::default()
+            },
+            Expr::call("max", vec![])
         );
         assert_eval_error!(
-            EvalError::CalculationError(CalculationError::ZeroDivision),
-            Token::Operator(Operator::Div),
-            Token::Number(Number::Float(0.0)),
-            Token::Number(Number::Float(1.0))
+            EvalError::CalculationError {
+                error: CalculationError::OutOfDomain,
+                span: Span::new(0, 8)
+            },
+            Expr::call("sqrt", vec![int(-1)]).with_span(Span::new(0, 8))
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        let evaluator = Evaluator::new();
+        let eval = |expr| evaluator.eval_value(&expr);
+        let x = || Expr::variable("x").with_span(Span::new(0, 1));
+        let env = Environment::new().with_variable("x", 2.0);
+        let zero_division = Expr::binary(Operator::Div, int(1), int(0));
+
+        assert_eq!(
+            eval(Expr::binary(Operator::Le, int(2), float(2.0))),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(
+            eval(Expr::binary(Operator::Ne, float(f64::NAN), float(f64::NAN))),
+            Ok(Value::Bool(true))
+        );
+        assert_eq!(
+            eval(Expr::unary(
+                Operator::Not,
+                Expr::binary(
+                    Operator::Eq,
+                    Expr::binary(Operator::Gt, int(1), int(2)),
+                    Expr::binary(Operator::Lt, int(3), int(2))
+                )
+            )),
+            Ok(Value::Bool(false))
+        );
+
+        // skipped operands are not evaluated
+        assert_eq!(
+            eval(Expr::binary(
+                Operator::And,
+                Expr::binary(Operator::Gt, int(0), int(1)),
+                Expr::binary(Operator::Gt, zero_division.clone(), int(0))
+            )),
+            Ok(Value::Bool(false))
+        );
+        assert_eq!(
+            eval(Expr::binary(
+                Operator::Or,
+                Expr::binary(Operator::Lt, int(0), int(1)),
+                zero_division.clone()
+            )),
+            Ok(Value::Bool(true))
+        );
+
+        // x > 1 ? x * 10 : 1 / 0
+        let conditional = Expr::conditional(
+            Expr::binary(Operator::Gt, x(), int(1)),
+            Expr::binary(Operator::Mul, x(), int(10)),
+            zero_division,
+        );
+
+        assert_eq!(evaluator.eval_with(&conditional, &env), Ok(20.0));
+        assert_eq!(
+            evaluator.eval_with(&conditional, &Environment::new().with_variable("x", 0.0)),
+            Err(EvalError::CalculationError {
+                error: CalculationError::ZeroDivision,
+                span: Span::default()
+            })
+        );
+        assert_eq!(
+            Evaluator::<Rational>::default().eval_value(&Expr::binary(
+                Operator::Eq,
+                Expr::binary(Operator::Add, float(0.1), float(0.2)),
+                float(0.3)
+            )),
+            Ok(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn type_errors() {
+        let x = || Expr::variable("x").with_span(Span::new(0, 1));
+        let comparison = || Expr::binary(Operator::Lt, x(), int(1)).with_span(Span::new(0, 5));
+        let env = Environment::new().with_variable("x", 2.0);
+        let eval = |expr| Evaluator::new().eval_with(&expr, &env);
+
+        assert_eq!(
+            eval(comparison()),
+            Err(EvalError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Bool,
+                span: Span::new(0, 5)
+            })
+        );
+        assert_eq!(
+            eval(Expr::binary(Operator::Add, comparison(), int(1))),
+            Err(EvalError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Bool,
+                span: Span::new(0, 5)
+            })
+        );
+        assert_eq!(
+            Evaluator::new().eval_value_with(&Expr::binary(Operator::Or, comparison(), x()), &env),
+            Err(EvalError::TypeMismatch {
+                expected: Type::Bool,
+                actual: Type::Number,
+                span: Span::new(0, 1)
+            })
+        );
+        assert_eq!(
+            eval(Expr::conditional(x(), int(1), int(2))),
+            Err(EvalError::TypeMismatch {
+                expected: Type::Bool,
+                actual: Type::Number,
+                span: Span::new(0, 1)
+            })
+        );
+        assert_eq!(
+            eval(Expr::binary(
+                Operator::Eq,
+                comparison(),
+                x().with_span(Span::new(9, 10))
+            )),
+            Err(EvalError::TypeMismatch {
+                expected: Type::Bool,
+                actual: Type::Number,
+                span: Span::new(9, 10)
+            })
+        );
+        assert_eq!(
+            eval(Expr::call("sqrt", vec![comparison()])),
+            Err(EvalError::TypeMismatch {
+                expected: Type::Number,
+                actual: Type::Bool,
+                span: Span::new(0, 5)
+            })
         );
     }
 }
@@ -0,0 +1,112 @@
+//! Module with the policy of the floating point evaluation
+//! that decides whether the non-finite results are errors
+
+use std::fmt::Display;
+
+use crate::{eval::CalculationError, tokens::Operator};
+
+/// How the floating point results outside of the normal numbers are treated
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatPolicy {
+    /// Infinities and NaN propagate as IEEE 754 defines, only division by zero is an error
+    #[default]
+    Ieee,
+    /// Every operation producing or consuming the infinity, NaN
+    /// or the number too small to be normal is an error
+    Strict,
+}
+
+/// Operation that produced the floating point result
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Operator(Operator),
+    Function(String),
+    /// Number written in the expression, i.e. `1e400`
+    Literal,
+    /// Value the variable is given
+    Variable,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Operator(operator) => write!(f, "operator {operator:?}"),
+            Self::Function(name) => write!(f, "function `{name}`"),
+            Self::Literal => write!(f, "literal"),
+            Self::Variable => write!(f, "variable"),
+        }
+    }
+}
+
+impl FloatPolicy {
+    /// Checks the result of the operation over the arguments, any result is fine for [FloatPolicy::Ieee]
+    pub fn check(
+        &self,
+        operation: Operation,
+        args: &[f64],
+        result: f64,
+    ) -> Result<f64, CalculationError> {
+        if *self == Self::Ieee {
+            return Ok(result);
+        }
+
+        // zero is exact unless the operation can only get it by rounding the tiny result
+        let vanished = result == 0.0
+            && match &operation {
+                Operation::Operator(Operator::Mul) => args.iter().all(|arg| *arg != 0.0),
+                Operation::Operator(Operator::Div | Operator::Pow) => args[0] != 0.0,
+                _ => false,
+            };
+
+        if args.iter().any(|arg| !arg.is_finite()) {
+            Err(CalculationError::NonFinite(operation))
+        } else if result.is_nan() {
+            Err(CalculationError::NaN(operation))
+        } else if result.is_infinite() {
+            Err(CalculationError::FloatOverflow(operation))
+        } else if result.is_subnormal() || vanished {
+            Err(CalculationError::FloatUnderflow(operation))
+        } else {
+            Ok(result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let mul = || Operation::Operator(Operator::Mul);
+
+        assert_eq!(
+            FloatPolicy::Ieee.check(mul(), &[1e200, 1e200], f64::INFINITY),
+            Ok(f64::INFINITY)
+        );
+        assert_eq!(FloatPolicy::Strict.check(mul(), &[2.0, 3.0], 6.0), Ok(6.0));
+        assert_eq!(FloatPolicy::Strict.check(mul(), &[0.0, 3.0], 0.0), Ok(0.0));
+        assert_eq!(
+            FloatPolicy::Strict.check(mul(), &[1e200, 1e200], f64::INFINITY),
+            Err(CalculationError::FloatOverflow(mul()))
+        );
+        assert_eq!(
+            FloatPolicy::Strict.check(mul(), &[1e-200, 1e-200], 0.0),
+            Err(CalculationError::FloatUnderflow(mul()))
+        );
+        assert_eq!(
+            FloatPolicy::Strict.check(mul(), &[f64::INFINITY, 0.0], f64::NAN),
+            Err(CalculationError::NonFinite(mul()))
+        );
+        assert_eq!(
+            FloatPolicy::Strict.check(Operation::Function("f".to_string()), &[], f64::NAN),
+            Err(CalculationError::NaN(Operation::Function("f".to_string())))
+        );
+        assert_eq!(
+            FloatPolicy::Strict.check(Operation::Function("f".to_string()), &[1.0], 1e-310),
+            Err(CalculationError::FloatUnderflow(Operation::Function(
+                "f".to_string()
+            )))
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Module with the registry of the functions
+//! that could be called inside the expression
+
+use std::{collections::HashMap, f64::consts::PI, fmt::Display, sync::Arc};
+
+use crate::eval::CalculationError;
+
+pub type FunctionResult = std::result::Result<f64, CalculationError>;
+
+/// Number of arguments accepted by the function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionArity {
+    pub min: usize,
+    /// `None` for functions accepting any number of arguments
+    pub max: Option<usize>,
+}
+
+impl FunctionArity {
+    pub fn exact(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub fn range(min: usize, max: usize) -> Self {
+        Self {
+            min,
+            max: Some(max),
+        }
+    }
+
+    pub fn at_least(min: usize) -> Self {
+        Self { min, max: None }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.map_or(true, |max| count <= max)
+    }
+}
+
+impl Display for FunctionArity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}"),
+            Some(max) => write!(f, "from {} to {max}", self.min),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+type Implementation = dyn Fn(&[f64]) -> FunctionResult + Send + Sync;
+
+#[derive(Clone)]
+pub struct Function {
+    arity: FunctionArity,
+    implementation: Arc<Implementation>,
+}
+
+impl Function {
+    pub fn new(
+        arity: FunctionArity,
+        implementation: impl Fn(&[f64]) -> FunctionResult + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            arity,
+            implementation: Arc::new(implementation),
+        }
+    }
+
+    pub fn arity(&self) -> FunctionArity {
+        self.arity
+    }
+
+    /// Calls the function, number of the arguments should be checked beforehand
+    pub fn call(&self, args: &[f64]) -> FunctionResult {
+        (self.implementation)(args)
+    }
+}
+
+impl std::fmt::Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Function")
+            .field("arity", &self.arity)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the value or reports that argument is outside of the function domain
+fn in_domain(valid: bool, value: f64) -> FunctionResult {
+    if valid {
+        Ok(value)
+    } else {
+        Err(CalculationError::OutOfDomain)
+    }
+}
+
+/// Rounds half away from zero to the number of decimal places, the negative number rounds
+/// to the tens, hundreds etc. The value is kept when it has no digits that far,
+/// i.e. `round(1e300, 10)`, and becomes zero when the places are beyond any value
+pub(crate) fn round(value: f64, digits: f64) -> f64 {
+    let scale = 10f64.powf(digits.trunc());
+    let scaled = value * scale;
+
+    match scaled.is_finite() {
+        false => value,
+        true if scale == 0.0 => value * 0.0,
+        true => scaled.round() / scale,
+    }
+}
+
+/// Named functions available for the evaluation
+#[derive(Default, Debug, Clone)]
+pub struct Functions {
+    functions: HashMap<String, Function>,
+}
+
+impl Functions {
+    /// Creates registry without any functions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with math functions, i.e. `sqrt`, `abs`, `min`, `max`, `round`, `sin` etc.
+    /// and the parts of the complex numbers `re`, `im`, `arg` and `conj`
+    pub fn builtin() -> Self {
+        let unary = |f: fn(f64) -> FunctionResult| {
+            Function::new(FunctionArity::exact(1), move |args| f(args[0]))
+        };
+
+        Self::new()
+            .with_function("sqrt", unary(|x| in_domain(x >= 0.0, x.sqrt())))
+            .with_function("abs", unary(|x| Ok(x.abs())))
+            .with_function("floor", unary(|x| Ok(x.floor())))
+            .with_function("ceil", unary(|x| Ok(x.ceil())))
+            .with_function(
+                "round",
+                // rounds to the number of decimal places provided as the second argument
+                Function::new(FunctionArity::range(1, 2), |args| {
+                    Ok(round(args[0], args.get(1).copied().unwrap_or(0.0)))
+                }),
+            )
+            .with_function(
+                "min",
+                Function::new(FunctionArity::at_least(1), |args| {
+                    Ok(args.iter().copied().fold(f64::INFINITY, f64::min))
+                }),
+            )
+            .with_function(
+                "max",
+                Function::new(FunctionArity::at_least(1), |args| {
+                    Ok(args.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+                }),
+            )
+            .with_function("ln", unary(|x| in_domain(x > 0.0, x.ln())))
+            .with_function(
+                "log",
+                // decimal logarithm unless the base is provided as the second argument
+                Function::new(FunctionArity::range(1, 2), |args| match args.get(1) {
+                    Some(&base) => in_domain(
+                        args[0] > 0.0 && base > 0.0 && base != 1.0,
+                        args[0].log(base),
+                    ),
+                    None => in_domain(args[0] > 0.0, args[0].log10()),
+                }),
+            )
+            .with_function("exp", unary(|x| Ok(x.exp())))
+            .with_function("sin", unary(|x| Ok(x.sin())))
+            .with_function("cos", unary(|x| Ok(x.cos())))
+            .with_function("tan", unary(|x| Ok(x.tan())))
+            .with_function("asin", unary(|x| in_domain(x.abs() <= 1.0, x.asin())))
+            .with_function("acos", unary(|x| in_domain(x.abs() <= 1.0, x.acos())))
+            .with_function("atan", unary(|x| Ok(x.atan())))
+            .with_function("re", unary(Ok))
+            .with_function("im", unary(|_| Ok(0.0)))
+            .with_function("arg", unary(|x| Ok(if x < 0.0 { PI } else { 0.0 })))
+            .with_function("conj", unary(Ok))
+    }
+
+    /// Adds the function replacing the previous one with the same name
+    pub fn with_function(mut self, name: impl Into<String>, function: Function) -> Self {
+        self.functions.insert(name.into(), function);
+        self
+    }
+
+    /// Adds the Rust closure as the function
+    pub fn with_closure(
+        self,
+        name: impl Into<String>,
+        arity: FunctionArity,
+        implementation: impl Fn(&[f64]) -> FunctionResult + Send + Sync + 'static,
+    ) -> Self {
+        self.with_function(name, Function::new(arity, implementation))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! assert_call {
+        ($name: literal, [$($arg: expr),*], $result: expr) => {
+            let functions = Functions::builtin();
+            let function = functions.get($name).unwrap();
+            let args = [$($arg as f64),*];
+
+            assert!(function.arity().accepts(args.len()));
+            assert_eq!(function.call(&args), $result)
+        };
+    }
+
+    #[test]
+    fn builtin() {
+        assert_call!("sqrt", [16], Ok(4.0));
+        assert_call!("sqrt", [-1], Err(CalculationError::OutOfDomain));
+        assert_call!("abs", [-2.5], Ok(2.5));
+        assert_call!("floor", [-2.5], Ok(-3.0));
+        assert_call!("ceil", [2.1], Ok(3.0));
+        assert_call!("round", [2.5], Ok(3.0));
+        assert_call!("round", [2.345, 2], Ok(2.35));
+        assert_call!("round", [1250, -2], Ok(1300.0));
+        assert_call!("round", [1, 400], Ok(1.0));
+        assert_call!("round", [1, -400], Ok(0.0));
+        assert_call!("round", [1e300, 10], Ok(1e300));
+        assert_call!("min", [3, 1, 2], Ok(1.0));
+        assert_call!("max", [3], Ok(3.0));
+        assert_call!("ln", [1], Ok(0.0));
+        assert_call!("ln", [0], Err(CalculationError::OutOfDomain));
+        assert_call!("log", [1000], Ok(3.0));
+        assert_call!("log", [8, 2], Ok(3.0));
+        assert_call!("log", [8, 1], Err(CalculationError::OutOfDomain));
+        assert_call!("exp", [0], Ok(1.0));
+        assert_call!("sin", [0], Ok(0.0));
+        assert_call!("cos", [0], Ok(1.0));
+        assert_call!("acos", [2], Err(CalculationError::OutOfDomain));
+        assert_call!("arg", [-2], Ok(PI));
+        assert_call!("im", [2], Ok(0.0));
+    }
+
+    #[test]
+    fn arity() {
+        assert!(FunctionArity::exact(1).accepts(1));
+        assert!(!FunctionArity::exact(1).accepts(2));
+        assert!(FunctionArity::range(1, 2).accepts(2));
+        assert!(!FunctionArity::range(1, 2).accepts(0));
+        assert!(FunctionArity::at_least(1).accepts(10));
+
+        assert_eq!(FunctionArity::exact(1).to_string(), "1");
+        assert_eq!(FunctionArity::range(1, 2).to_string(), "from 1 to 2");
+        assert_eq!(FunctionArity::at_least(1).to_string(), "at least 1");
+    }
+
+    #[test]
+    fn custom() {
+        let functions =
+            Functions::builtin().with_closure("hypot", FunctionArity::exact(2), |args| {
+                Ok(args[0].hypot(args[1]))
+            });
+
+        assert_eq!(functions.get("hypot").unwrap().call(&[3.0, 4.0]), Ok(5.0));
+        assert!(functions.get("unknown").is_none());
+    }
+}
@@ -1,18 +1,85 @@
-mod eval;
-mod parse;
-mod tokens;
+pub mod bigint;
+pub mod check;
+pub mod compile;
+pub mod complex;
+pub mod decimal;
+pub mod derive;
+pub mod diagnostic;
+pub mod dialect;
+pub mod env;
+pub mod eval;
+pub mod float;
+pub mod functions;
+pub mod numeral;
+pub mod numeric;
+pub mod parse;
+pub mod print;
+pub mod rational;
+pub mod rpn;
+pub mod simplify;
+pub mod span;
+pub mod stream;
+pub mod tokens;
+pub mod units;
+pub mod value;
 
-use crate::tokens::TokenIterator;
-
-use self::{
+pub use self::{
+    check::{CheckError, Checker},
+    compile::CompiledExpr,
+    complex::{Complex, ComplexForm},
+    decimal::{Decimal, DecimalContext, Rounding},
+    derive::DeriveError,
+    diagnostic::Diagnostic,
+    dialect::{Dialect, Symbol},
+    env::Environment,
     eval::{EvalError, Evaluator},
-    parse::{ExprParser, ParserError},
+    float::{FloatPolicy, Operation},
+    functions::{Function, FunctionArity, Functions},
+    numeral::{DivisionPolicy, Numeral},
+    numeric::Numeric,
+    parse::{
+        Associativity, Expr, ExprKind, ExprParser, OperatorPrecedence, ParserConfig, ParserError,
+        Precedence,
+    },
+    print::{Notation, Parentheses, Printer},
+    rational::Rational,
+    rpn::RpnParser,
+    simplify::Simplifier,
+    span::{Span, Spanned},
+    units::{Quantity, Unit, Units},
+    value::{Type, Value},
 };
 
 #[derive(Debug)]
 pub enum ExprError {
     ParserError(ParserError),
     EvalError(EvalError),
+    DeriveError(DeriveError),
+    CheckError(CheckError),
+}
+
+impl ExprError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::ParserError(err) => err.span(),
+            Self::EvalError(err) => err.span(),
+            Self::DeriveError(err) => err.span(),
+            Self::CheckError(err) => err.span(),
+        }
+    }
+
+    /// Creates report of the error pointing to its location inside the `source`
+    pub fn diagnostic<'source>(&self, source: &'source str) -> Diagnostic<'source> {
+        Diagnostic::new(source, self, self.span())
+    }
+
+    /// Creates report of every problem found by the [Checker] or of the error itself
+    pub fn diagnostics<'source>(&self, source: &'source str) -> Vec<Diagnostic<'source>> {
+        match self {
+            Self::CheckError(err) => err.diagnostics(source),
+            _ => vec![self.diagnostic(source)],
+        }
+    }
 }
 
 impl std::fmt::Display for ExprError {
@@ -20,6 +87,8 @@ impl std::fmt::Display for ExprError {
         match self {
             Self::ParserError(err) => write!(f, "{err}"),
             Self::EvalError(err) => write!(f, "{err}"),
+            Self::DeriveError(err) => write!(f, "{err}"),
+            Self::CheckError(err) => write!(f, "{err}"),
         }
     }
 }
@@ -29,6 +98,8 @@ impl std::error::Error for ExprError {
         match self {
             Self::ParserError(err) => Some(err),
             Self::EvalError(err) => Some(err),
+            Self::DeriveError(err) => Some(err),
+            Self::CheckError(err) => Some(err),
         }
     }
 }
@@ -45,14 +116,247 @@ impl From<EvalError> for ExprError {
     }
 }
 
+impl From<DeriveError> for ExprError {
+    fn from(error: DeriveError) -> Self {
+        Self::DeriveError(error)
+    }
+}
+
+impl From<CheckError> for ExprError {
+    fn from(error: CheckError) -> Self {
+        Self::CheckError(error)
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ExprError>;
 
+/// Parses the expression from string into the tree with default settings
+pub fn parse(expr: &str) -> Result<Expr> {
+    parse_with_config(expr, &ParserConfig::default())
+}
+
+/// Parses the expression from string into the tree with the provided settings
+pub fn parse_with_config(expr: &str, config: &ParserConfig) -> Result<Expr> {
+    let parser = ExprParser::new(config.clone());
+
+    Ok(parser.parse_str(expr)?)
+}
+
+/// Parses the expression read from the source, i.e. the file, with default settings
+pub fn parse_reader(reader: impl std::io::Read) -> Result<Expr> {
+    parse_reader_with_config(reader, &ParserConfig::default())
+}
+
+/// Parses the expression read from the source with the provided settings
+pub fn parse_reader_with_config(reader: impl std::io::Read, config: &ParserConfig) -> Result<Expr> {
+    Ok(ExprParser::new(config.clone()).parse_reader(reader)?)
+}
+
+/// Parses the expression written in the reverse Polish notation with default settings
+pub fn parse_rpn(expr: &str) -> Result<Expr> {
+    parse_rpn_with_config(expr, &ParserConfig::default())
+}
+
+/// Parses the expression written in the reverse Polish notation with the dialect of the config
+pub fn parse_rpn_with_config(expr: &str, config: &ParserConfig) -> Result<Expr> {
+    Ok(RpnParser::new(config.clone()).parse_str(expr)?)
+}
+
 /// Evaluates the expression from string with default settings
 pub fn eval(expr: &str) -> Result<f64> {
-    let mut tokens = TokenIterator::from(expr);
-    let mut parser = ExprParser::new();
-    let mut parsed = parser.parse(&mut tokens)?;
+    eval_with_config(expr, &ParserConfig::default())
+}
+
+/// Evaluates the expression from string with the provided parser settings
+pub fn eval_with_config(expr: &str, config: &ParserConfig) -> Result<f64> {
+    eval_with_env_and_config(expr, &Environment::new(), config)
+}
+
+/// Evaluates the expression from string with default settings
+/// taking values of the variables from the environment
+pub fn eval_with(expr: &str, env: &Environment) -> Result<f64> {
+    eval_with_env_and_config(expr, env, &ParserConfig::default())
+}
+
+/// Evaluates the expression from string with the provided parser settings
+/// taking values of the variables from the environment
+pub fn eval_with_env_and_config(
+    expr: &str,
+    env: &Environment,
+    config: &ParserConfig,
+) -> Result<f64> {
+    let parsed = parse_with_config(expr, config)?;
     let evaluator = Evaluator::new();
 
-    Ok(evaluator.eval(&mut parsed)?)
+    Ok(evaluator.eval_with(&parsed, env)?)
+}
+
+/// Evaluates the expression from string with default settings
+/// to the number or the boolean, i.e. `1 < 2`
+pub fn eval_value(expr: &str) -> Result<Value> {
+    eval_value_with_env_and_config(expr, &Environment::new(), &ParserConfig::default())
+}
+
+/// Evaluates the expression from string with the provided parser settings
+/// to the number or the boolean taking values of the variables from the environment
+pub fn eval_value_with_env_and_config(
+    expr: &str,
+    env: &Environment,
+    config: &ParserConfig,
+) -> Result<Value> {
+    let parsed = parse_with_config(expr, config)?;
+
+    Ok(Evaluator::new().eval_value_with(&parsed, env)?)
+}
+
+/// Evaluates the expression from string with default settings using exact fractions
+pub fn eval_exact(expr: &str) -> Result<Rational> {
+    eval_exact_with_config(expr, &ParserConfig::default())
+}
+
+/// Evaluates the expression from string with the provided parser settings using exact fractions
+pub fn eval_exact_with_config(expr: &str, config: &ParserConfig) -> Result<Rational> {
+    let parsed = parse_with_config(expr, config)?;
+
+    Ok(Evaluator::<Rational>::default().eval(&parsed)?)
+}
+
+/// Evaluates the expression from string with default settings keeping the integers exact
+pub fn eval_numeral(expr: &str, policy: DivisionPolicy) -> Result<Numeral> {
+    eval_numeral_with_config(expr, policy, &ParserConfig::default())
+}
+
+/// Evaluates the expression from string with the provided parser settings
+/// keeping the integers exact
+pub fn eval_numeral_with_config(
+    expr: &str,
+    policy: DivisionPolicy,
+    config: &ParserConfig,
+) -> Result<Numeral> {
+    let parsed = parse_with_config(expr, config)?;
+    let evaluator = Evaluator::<Numeral>::default().with_context(policy);
+
+    Ok(evaluator.eval(&parsed)?)
+}
+
+/// Evaluates the expression from string with default settings
+/// rounding every result to the fixed-point decimal
+pub fn eval_decimal(expr: &str, context: DecimalContext) -> Result<Decimal> {
+    eval_decimal_with_config(expr, context, &ParserConfig::default())
+}
+
+/// Evaluates the expression from string with the provided parser settings
+/// rounding every result to the fixed-point decimal
+pub fn eval_decimal_with_config(
+    expr: &str,
+    context: DecimalContext,
+    config: &ParserConfig,
+) -> Result<Decimal> {
+    let parsed = parse_with_config(expr, config)?;
+    let evaluator = Evaluator::<Decimal>::default().with_context(context);
+
+    Ok(evaluator.eval(&parsed)?)
+}
+
+/// Evaluates the expression from string with default settings to the complex number,
+/// so the imaginary literals like `2i` are allowed and `$sqrt e0 b 1f` is `i`
+pub fn eval_complex(expr: &str) -> Result<Complex> {
+    eval_complex_with_config(expr, &ParserConfig::default())
+}
+
+/// Evaluates the expression from string with the provided parser settings to the complex number
+pub fn eval_complex_with_config(expr: &str, config: &ParserConfig) -> Result<Complex> {
+    let parsed = parse_with_config(expr, config)?;
+    let evaluator = Evaluator::<Complex>::default();
+
+    Ok(evaluator.eval(&parsed)?)
+}
+
+/// Evaluates the expression from string with default settings and [Units::builtin]
+/// to the quantity, converting it to the unit when it is requested, i.e. `2[lb]` to `kg`
+pub fn eval_quantity(expr: &str, unit: Option<&str>) -> Result<Quantity> {
+    eval_quantity_with_config(expr, unit, Units::builtin(), &ParserConfig::default())
+}
+
+/// Evaluates the expression from string with the provided parser settings and units
+/// to the quantity, converting it to the unit when it is requested
+pub fn eval_quantity_with_config(
+    expr: &str,
+    unit: Option<&str>,
+    units: Units,
+    config: &ParserConfig,
+) -> Result<Quantity> {
+    let parsed = parse_with_config(expr, config)?;
+    let conversion = |error| EvalError::CalculationError {
+        error,
+        span: parsed.span,
+    };
+    let target = unit
+        .map(|unit| units.parse(unit))
+        .transpose()
+        .map_err(conversion)?;
+    let evaluator = Evaluator::<Quantity>::default().with_context(units);
+    let result = evaluator.eval(&parsed)?;
+
+    match target {
+        Some(unit) => Ok(result.convert(&unit).map_err(conversion)?),
+        None => Ok(result),
+    }
+}
+
+/// Parses the expression from string with default settings
+/// and simplifies it, see [Simplifier]
+pub fn simplify(expr: &str) -> Result<Expr> {
+    simplify_with_config(expr, &ParserConfig::default())
+}
+
+/// Parses the expression from string with the provided parser settings
+/// and simplifies it, see [Simplifier]
+pub fn simplify_with_config(expr: &str, config: &ParserConfig) -> Result<Expr> {
+    let parsed = parse_with_config(expr, config)?;
+
+    Ok(Simplifier::new().simplify(&parsed))
+}
+
+/// Parses the expression from string with default settings
+/// and differentiates it with respect to the variable, see [Expr::derive]
+pub fn derive(expr: &str, variable: &str) -> Result<Expr> {
+    derive_with_config(expr, variable, &ParserConfig::default())
+}
+
+/// Parses the expression from string with the provided parser settings
+/// and differentiates it with respect to the variable, see [Expr::derive]
+pub fn derive_with_config(expr: &str, variable: &str, config: &ParserConfig) -> Result<Expr> {
+    let parsed = parse_with_config(expr, config)?;
+
+    Ok(parsed.derive(variable)?)
+}
+
+/// Parses the expression from string with default settings and checks it
+/// using only the declared variables, see [Checker]
+pub fn check(expr: &str, variables: &[&str]) -> Result<Type> {
+    check_with_config(expr, variables, &ParserConfig::default())
+}
+
+/// Parses the expression from string with the provided parser settings and checks it
+/// using only the declared variables, see [Checker]
+pub fn check_with_config(expr: &str, variables: &[&str], config: &ParserConfig) -> Result<Type> {
+    let parsed = parse_with_config(expr, config)?;
+    let checker = Checker::new().with_variables(variables.iter().copied());
+
+    Ok(checker.check(&parsed)?)
+}
+
+/// Parses and checks the expression from string with default settings
+/// into the program that is evaluated many times, see [CompiledExpr]
+pub fn compile(expr: &str) -> Result<CompiledExpr> {
+    compile_with_config(expr, &ParserConfig::default())
+}
+
+/// Parses and checks the expression from string with the provided parser settings
+/// into the program that is evaluated many times, see [CompiledExpr]
+pub fn compile_with_config(expr: &str, config: &ParserConfig) -> Result<CompiledExpr> {
+    let parsed = parse_with_config(expr, config)?;
+
+    Ok(Evaluator::new().compile(&parsed)?)
 }
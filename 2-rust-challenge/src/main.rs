@@ -1,8 +1,196 @@
-use std::io;
+use std::{env, io, process};
 
-use eval::eval;
+use eval::{
+    eval_complex_with_config, eval_decimal_with_config, eval_exact_with_config,
+    eval_numeral_with_config, eval_quantity_with_config, parse_with_config, ComplexForm,
+    DecimalContext, Dialect, DivisionPolicy, Evaluator, FloatPolicy, ParserConfig, Rounding, Units,
+};
+
+const USAGE: &str = "\
+Usage: eval [OPTIONS]
+
+Options:
+  --dialect <letters|standard>   alphabet of the expression [default: letters]
+  --precedence <flat|standard>   precedence of the operators [default: flat]
+  --symbol <symbol>=<text>       adds custom text for the symbol to the dialect,
+                                 i.e. `--symbol add=plus`
+  --strict                       reports overflow, underflow, NaN and infinities
+                                 of the floating point evaluation as errors
+  --exact                        evaluates with exact fractions
+  --precision <digits>           fractional digits of the exact result [default: 10]
+  --integer <rational|float>     keeps integers exact, evaluating division
+                                 without the integer result as fraction or float
+  --decimal <scale>              evaluates with fixed-point decimals
+                                 of the number of fractional digits
+  --rounding <half-even|half-up|down|up>
+                                 rounding of the decimals [default: half-even]
+  --complex <rectangular|polar>  evaluates complex numbers with the imaginary
+                                 literals, i.e. `2i`, printed in the form
+  --units                        evaluates quantities with the units, i.e. `2[kg]`
+  --unit <unit>                  evaluates quantities converting the result
+                                 to the unit, i.e. `--unit lb`
+  -h, --help                     prints this message
+
+Options choosing the numbers can't be combined, except `--units` and `--unit`";
+
+/// Numbers the expression is evaluated to
+enum Mode {
+    Float(FloatPolicy),
+    /// Exact fractions printed along with the decimal of the precision
+    Exact(usize),
+    Integer(DivisionPolicy),
+    Decimal(DecimalContext),
+    /// Complex numbers printed in the form
+    Complex(ComplexForm),
+    Quantity,
+}
+
+struct Options {
+    config: ParserConfig,
+    mode: Mode,
+    /// Unit of the quantity the result is converted to
+    unit: Option<String>,
+}
+
+/// Chooses the mode by the argument, the arguments choosing different modes conflict,
+/// except `--units` and `--unit` that both choose the quantities
+fn choose(chosen: &mut Option<(String, Mode)>, arg: &str, mode: Mode) -> Result<(), String> {
+    match chosen {
+        Some((previous, Mode::Quantity)) if matches!(mode, Mode::Quantity) => {
+            *previous = arg.to_string();
+        }
+        Some((previous, _)) if previous != arg => {
+            return Err(format!("`{arg}` can't be used along with `{previous}`"));
+        }
+        _ => *chosen = Some((arg.to_string(), mode)),
+    }
+
+    Ok(())
+}
+
+/// Reads settings from the command line arguments
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut config = ParserConfig::flat();
+    let mut dialect = Dialect::letters();
+    let mut symbols = vec![];
+    let mut precision = None;
+    let mut mode = None;
+    let mut rounding = None;
+    let mut unit = None;
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for `{arg}`"));
+
+        match arg.as_str() {
+            "--dialect" => dialect = value()?.parse().map_err(|err| format!("{err}"))?,
+            "--precedence" => {
+                config = match value()?.as_str() {
+                    "flat" => ParserConfig::flat(),
+                    "standard" => ParserConfig::standard(),
+                    other => return Err(format!("Unknown precedence `{other}`")),
+                }
+            }
+            "--symbol" => symbols.push(value()?),
+            "--strict" => choose(&mut mode, &arg, Mode::Float(FloatPolicy::Strict))?,
+            // precision is set once all the arguments are read
+            "--exact" => choose(&mut mode, &arg, Mode::Exact(0))?,
+            "--precision" => {
+                let digits = value()?;
+
+                precision = Some(
+                    digits
+                        .parse()
+                        .map_err(|_| format!("Invalid precision `{digits}`"))?,
+                );
+            }
+            "--integer" => {
+                let policy = match value()?.as_str() {
+                    "rational" => DivisionPolicy::Rational,
+                    "float" => DivisionPolicy::Float,
+                    other => return Err(format!("Unknown division policy `{other}`")),
+                };
+
+                choose(&mut mode, &arg, Mode::Integer(policy))?
+            }
+            "--decimal" => {
+                let scale = value()?;
+                let scale = scale
+                    .parse()
+                    .map_err(|_| format!("Invalid scale `{scale}`"))?;
+
+                choose(
+                    &mut mode,
+                    &arg,
+                    Mode::Decimal(DecimalContext::default().with_scale(scale)),
+                )?
+            }
+            "--rounding" => {
+                rounding = Some(
+                    value()?
+                        .parse::<Rounding>()
+                        .map_err(|err| format!("{err}"))?,
+                )
+            }
+            "--complex" => {
+                let form = value()?.parse().map_err(|err| format!("{err}"))?;
+
+                choose(&mut mode, &arg, Mode::Complex(form))?
+            }
+            "--units" => choose(&mut mode, &arg, Mode::Quantity)?,
+            "--unit" => {
+                unit = Some(value()?);
+                choose(&mut mode, &arg, Mode::Quantity)?
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            other => return Err(format!("Unknown argument `{other}`\n\n{USAGE}")),
+        }
+    }
+
+    for definition in symbols {
+        dialect = dialect
+            .with_definition(&definition)
+            .map_err(|err| format!("{err}"))?;
+    }
+
+    let mode = mode.map(|(_, mode)| mode);
+
+    // settings of the other mode would be silently ignored
+    if precision.is_some() && !matches!(mode, Some(Mode::Exact(_))) {
+        return Err("`--precision` requires `--exact`".to_string());
+    }
+
+    if rounding.is_some() && !matches!(mode, Some(Mode::Decimal(_))) {
+        return Err("`--rounding` requires `--decimal`".to_string());
+    }
+
+    let mode = match mode {
+        Some(Mode::Exact(_)) => Mode::Exact(precision.unwrap_or(10)),
+        Some(Mode::Decimal(context)) => {
+            Mode::Decimal(context.with_rounding(rounding.unwrap_or_default()))
+        }
+        Some(mode) => mode,
+        None => Mode::Float(FloatPolicy::Ieee),
+    };
+
+    Ok(Options {
+        config: config.with_dialect(dialect),
+        mode,
+        unit,
+    })
+}
 
 fn main() {
+    let Options { config, mode, unit } = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            process::exit(2);
+        }
+    };
+
     loop {
         println!("Please, enter the expression below (enter empty expression to exit):");
         let mut input = String::new();
@@ -11,15 +199,34 @@ fn main() {
             Ok(_) => {
                 let input = input.trim();
 
-                if input.len() == 0 {
+                if input.is_empty() {
                     break;
                 }
 
-                let result = eval(input.trim());
+                let result = match mode {
+                    Mode::Float(policy) => parse_with_config(input, &config).and_then(|expr| {
+                        let evaluator = Evaluator::new().with_context(policy);
+
+                        Ok(evaluator.eval_value(&expr)?.to_string())
+                    }),
+                    Mode::Exact(precision) => eval_exact_with_config(input, &config)
+                        .map(|result| format!("{result} ≈ {}", result.to_decimal(precision))),
+                    Mode::Integer(policy) => eval_numeral_with_config(input, policy, &config)
+                        .map(|result| result.to_string()),
+                    Mode::Decimal(context) => eval_decimal_with_config(input, context, &config)
+                        .map(|result| result.to_string()),
+                    Mode::Complex(form) => {
+                        eval_complex_with_config(input, &config).map(|result| result.format(form))
+                    }
+                    Mode::Quantity => {
+                        eval_quantity_with_config(input, unit.as_deref(), Units::builtin(), &config)
+                            .map(|result| result.to_string())
+                    }
+                };
 
                 match result {
                     Ok(result) => println!("Result: {result}"),
-                    Err(err) => println!("Error: {err:?}"),
+                    Err(err) => println!("{}", err.diagnostic(input)),
                 }
             }
             Err(error) => panic!("Error: {error}"),
@@ -30,3 +237,74 @@ fn main() {
 
     println!("Have a good day!");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn modes() {
+        assert!(matches!(
+            parse(&["--exact", "--precision", "3"]).map(|options| options.mode),
+            Ok(Mode::Exact(3))
+        ));
+        assert!(matches!(
+            parse(&["--units", "--unit", "lb"]).map(|options| options.mode),
+            Ok(Mode::Quantity)
+        ));
+        assert!(matches!(
+            parse(&["--decimal", "2", "--rounding", "up"]).map(|options| options.mode),
+            Ok(Mode::Decimal(_))
+        ));
+    }
+
+    #[test]
+    fn conflicts() {
+        let error = |args| parse(args).err().unwrap();
+
+        assert_eq!(
+            error(&["--exact", "--decimal", "2"]),
+            "`--decimal` can't be used along with `--exact`"
+        );
+        assert_eq!(
+            error(&["--complex", "polar", "--exact"]),
+            "`--exact` can't be used along with `--complex`"
+        );
+        assert_eq!(
+            error(&["--exact", "--integer", "float"]),
+            "`--integer` can't be used along with `--exact`"
+        );
+        assert_eq!(
+            error(&["--exact", "--strict"]),
+            "`--strict` can't be used along with `--exact`"
+        );
+        assert_eq!(
+            error(&["--unit", "kg", "--exact"]),
+            "`--exact` can't be used along with `--unit`"
+        );
+        assert_eq!(
+            error(&["--exact", "--units"]),
+            "`--units` can't be used along with `--exact`"
+        );
+        assert_eq!(
+            error(&["--units", "--complex", "polar"]),
+            "`--complex` can't be used along with `--units`"
+        );
+        assert_eq!(
+            error(&["--rounding", "up"]),
+            "`--rounding` requires `--decimal`"
+        );
+        assert_eq!(
+            error(&["--exact", "--rounding", "up"]),
+            "`--rounding` requires `--decimal`"
+        );
+        assert_eq!(
+            error(&["--precision", "3", "--decimal", "2"]),
+            "`--precision` requires `--exact`"
+        );
+    }
+}
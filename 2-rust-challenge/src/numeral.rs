@@ -0,0 +1,68 @@
+//! Module with the numbers that keep integers exact
+//! and fall back to fractions or floats only for division
+
+use std::fmt::Display;
+
+use crate::{bigint::BigInt, rational::Rational};
+
+/// How division without the integer result is evaluated
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivisionPolicy {
+    /// Keeps the exact fraction, so `7 / 2 * 2` is exactly 7
+    #[default]
+    Rational,
+    /// Turns the result into the floating point number
+    Float,
+}
+
+/// Result of the evaluation that stays integer as long as possible
+#[derive(Debug, Clone, PartialEq)]
+pub enum Numeral {
+    Int(BigInt),
+    Rational(Rational),
+    Float(f64),
+}
+
+impl Numeral {
+    /// Keeps the fraction with the denominator 1 as the integer
+    pub fn from_rational(value: Rational) -> Self {
+        if value.is_integer() {
+            Self::Int(value.numer().clone())
+        } else {
+            Self::Rational(value)
+        }
+    }
+
+    /// Exact value of the number, `None` for the floats
+    pub fn to_rational(&self) -> Option<Rational> {
+        match self {
+            Self::Int(value) => Some(Rational::from(value.clone())),
+            Self::Rational(value) => Some(value.clone()),
+            Self::Float(_) => None,
+        }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Self::Int(value) => value.to_f64(),
+            Self::Rational(value) => value.to_f64(),
+            Self::Float(value) => *value,
+        }
+    }
+}
+
+impl From<BigInt> for Numeral {
+    fn from(value: BigInt) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl Display for Numeral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Rational(value) => write!(f, "{value}"),
+            Self::Float(value) => write!(f, "{value}"),
+        }
+    }
+}
@@ -0,0 +1,1318 @@
+//! Module with the numbers the expression could be evaluated to
+//! and the arithmetic they support
+
+use std::cmp::Ordering;
+
+use crate::{
+    bigint::BigInt,
+    complex::Complex,
+    decimal::{Decimal, DecimalContext, Rounding},
+    eval::CalculationError,
+    float::{FloatPolicy, Operation},
+    functions::{self, Function},
+    numeral::{DivisionPolicy, Numeral},
+    rational::{Rational, MAX_DECIMAL_EXPONENT},
+    tokens::{Number, Operator},
+    units::{Quantity, Unit, Units},
+};
+
+pub type NumericResult<N> = std::result::Result<N, CalculationError>;
+
+/// Limit on the size of the exact power, so `2 ^ 2 ^ 30` fails instead of running out of memory
+const MAX_POWER_BITS: u64 = 1 << 24;
+
+/// Arithmetic of the numbers used by the [Evaluator](crate::Evaluator).
+/// Every operation reports the invalid or unrepresentable result as [CalculationError].
+///
+/// Functions are calculated with floating point numbers by default,
+/// so the arguments and the results are converted via `f64`
+pub trait Numeric: Clone {
+    /// Settings of the arithmetic, i.e. the division policy of the [Numeral]
+    type Context: Default + Clone;
+
+    /// Converts the literal of the expression
+    fn from_number(number: &Number, context: &Self::Context) -> NumericResult<Self>;
+    /// Converts the value of the variable or the result of the function
+    fn from_f64(value: f64, context: &Self::Context) -> NumericResult<Self>;
+    /// Converts the argument of the function
+    fn to_f64(&self) -> f64;
+
+    /// Calls the function `name` with the arguments converted via [Numeric::to_f64]
+    fn call(
+        function: &Function,
+        _name: &str,
+        args: &[Self],
+        context: &Self::Context,
+    ) -> NumericResult<Self> {
+        let args = args.iter().map(Self::to_f64).collect::<Vec<_>>();
+
+        Self::from_f64(function.call(&args)?, context)
+    }
+
+    fn neg(self, context: &Self::Context) -> NumericResult<Self>;
+    fn add(self, other: Self, context: &Self::Context) -> NumericResult<Self>;
+    fn sub(self, other: Self, context: &Self::Context) -> NumericResult<Self>;
+    fn mul(self, other: Self, context: &Self::Context) -> NumericResult<Self>;
+    fn div(self, other: Self, context: &Self::Context) -> NumericResult<Self>;
+
+    /// Remainder of the division, calculated with floating point numbers by default
+    fn rem(self, other: Self, context: &Self::Context) -> NumericResult<Self> {
+        let result = f64::rem(self.to_f64(), other.to_f64(), &FloatPolicy::Ieee)?;
+
+        Self::from_f64(result, context)
+    }
+
+    /// Power, calculated with floating point numbers by default
+    fn pow(self, other: Self, context: &Self::Context) -> NumericResult<Self> {
+        let result = f64::pow(self.to_f64(), other.to_f64(), &FloatPolicy::Ieee)?;
+
+        Self::from_f64(result, context)
+    }
+
+    /// Order of the numbers for the comparisons, calculated with floating point numbers
+    /// by default, so NaN is unordered
+    fn compare(&self, other: &Self) -> NumericResult<Option<Ordering>> {
+        Ok(self.to_f64().partial_cmp(&other.to_f64()))
+    }
+
+    /// Gives the unit to the number or converts the number that has one,
+    /// only the [Quantity] supports units
+    fn with_unit(self, _unit: &str, _context: &Self::Context) -> NumericResult<Self> {
+        Err(CalculationError::UnitsUnsupported)
+    }
+}
+
+/// Applies the arithmetic binary operator to the arguments
+pub fn calculate<N: Numeric>(
+    operator: &Operator,
+    left_arg: N,
+    right_arg: N,
+    context: &N::Context,
+) -> NumericResult<N> {
+    match operator {
+        Operator::Add => left_arg.add(right_arg, context),
+        Operator::Sub => left_arg.sub(right_arg, context),
+        Operator::Mul => left_arg.mul(right_arg, context),
+        Operator::Div => left_arg.div(right_arg, context),
+        Operator::Mod => left_arg.rem(right_arg, context),
+        Operator::Pow => left_arg.pow(right_arg, context),
+        Operator::Neg | Operator::Not => unreachable!("arity is checked"),
+        _ => unreachable!("comparisons and logic yield booleans, see [compare]"),
+    }
+}
+
+/// Applies the comparison operator to the arguments, only `!=` holds for the unordered ones
+pub fn compare<N: Numeric>(
+    operator: &Operator,
+    left_arg: &N,
+    right_arg: &N,
+) -> NumericResult<bool> {
+    let ordering = left_arg.compare(right_arg)?;
+
+    Ok(match operator {
+        Operator::Eq => ordering == Some(Ordering::Equal),
+        Operator::Ne => ordering != Some(Ordering::Equal),
+        Operator::Lt => ordering == Some(Ordering::Less),
+        Operator::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Operator::Gt => ordering == Some(Ordering::Greater),
+        Operator::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        _ => unreachable!("only comparisons yield booleans from numbers"),
+    })
+}
+
+const ZERO: f64 = 0.0;
+
+/// Floating point numbers that follow IEEE 754 except for the division by zero,
+/// non-finite results are errors only for the [FloatPolicy::Strict]
+impl Numeric for f64 {
+    type Context = FloatPolicy;
+
+    fn from_number(number: &Number, policy: &FloatPolicy) -> NumericResult<Self> {
+        match number {
+            Number::Imaginary(_) => Err(CalculationError::ComplexUnsupported),
+            number => policy.check(Operation::Literal, &[], number.to_f64()),
+        }
+    }
+
+    /// Value is checked as the one of the variable, the results of the operations are checked by them
+    fn from_f64(value: f64, policy: &FloatPolicy) -> NumericResult<Self> {
+        policy.check(Operation::Variable, &[], value)
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn call(
+        function: &Function,
+        name: &str,
+        args: &[f64],
+        policy: &FloatPolicy,
+    ) -> NumericResult<Self> {
+        let result = function.call(args)?;
+
+        match policy {
+            FloatPolicy::Ieee => Ok(result),
+            FloatPolicy::Strict => {
+                policy.check(Operation::Function(name.to_string()), args, result)
+            }
+        }
+    }
+
+    fn neg(self, policy: &FloatPolicy) -> NumericResult<Self> {
+        policy.check(Operation::Operator(Operator::Neg), &[self], -self)
+    }
+
+    fn add(self, other: Self, policy: &FloatPolicy) -> NumericResult<Self> {
+        policy.check(
+            Operation::Operator(Operator::Add),
+            &[self, other],
+            self + other,
+        )
+    }
+
+    fn sub(self, other: Self, policy: &FloatPolicy) -> NumericResult<Self> {
+        policy.check(
+            Operation::Operator(Operator::Sub),
+            &[self, other],
+            self - other,
+        )
+    }
+
+    fn mul(self, other: Self, policy: &FloatPolicy) -> NumericResult<Self> {
+        policy.check(
+            Operation::Operator(Operator::Mul),
+            &[self, other],
+            self * other,
+        )
+    }
+
+    fn div(self, other: Self, policy: &FloatPolicy) -> NumericResult<Self> {
+        if other == ZERO {
+            return Err(CalculationError::ZeroDivision);
+        }
+
+        policy.check(
+            Operation::Operator(Operator::Div),
+            &[self, other],
+            self / other,
+        )
+    }
+
+    /// Remainder has the sign of the dividend, i.e. `-7 % 3 = -1`
+    fn rem(self, other: Self, policy: &FloatPolicy) -> NumericResult<Self> {
+        if other == ZERO {
+            return Err(CalculationError::ZeroModulus);
+        }
+
+        policy.check(
+            Operation::Operator(Operator::Mod),
+            &[self, other],
+            self % other,
+        )
+    }
+
+    fn pow(self, other: Self, policy: &FloatPolicy) -> NumericResult<Self> {
+        if self < ZERO && other.fract() != ZERO {
+            return Err(CalculationError::FractionalPowerOfNegative);
+        }
+
+        if self == ZERO && other < ZERO {
+            return Err(CalculationError::ZeroDivision);
+        }
+
+        policy.check(
+            Operation::Operator(Operator::Pow),
+            &[self, other],
+            self.powf(other),
+        )
+    }
+}
+
+/// Integers that report the overflow instead of wrapping around.
+/// Division is rounded toward zero, so `7 / 2 = 3`
+impl Numeric for i64 {
+    type Context = ();
+
+    fn from_number(number: &Number, _: &()) -> NumericResult<Self> {
+        match number {
+            Number::Int(num) => num.to_i64().ok_or(CalculationError::Overflow),
+            Number::Float(num) => Self::from_f64(*num, &()),
+            Number::Decimal(_) => {
+                let value = Rational::from_number(number, &())?;
+
+                match value.is_integer() {
+                    true => value.numer().to_i64().ok_or(CalculationError::Overflow),
+                    false => Err(CalculationError::Inexact),
+                }
+            }
+            Number::Imaginary(_) => Err(CalculationError::ComplexUnsupported),
+        }
+    }
+
+    fn from_f64(value: f64, _: &()) -> NumericResult<Self> {
+        if value.fract() != ZERO || value.is_nan() {
+            return Err(CalculationError::Inexact);
+        }
+
+        // bounds are exact powers of 2, so every float in between fits
+        if value < -(2f64.powi(63)) || value >= 2f64.powi(63) {
+            return Err(CalculationError::Overflow);
+        }
+
+        Ok(value as i64)
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self as f64
+    }
+
+    fn neg(self, _: &()) -> NumericResult<Self> {
+        self.checked_neg().ok_or(CalculationError::Overflow)
+    }
+
+    fn add(self, other: Self, _: &()) -> NumericResult<Self> {
+        self.checked_add(other).ok_or(CalculationError::Overflow)
+    }
+
+    fn sub(self, other: Self, _: &()) -> NumericResult<Self> {
+        self.checked_sub(other).ok_or(CalculationError::Overflow)
+    }
+
+    fn mul(self, other: Self, _: &()) -> NumericResult<Self> {
+        self.checked_mul(other).ok_or(CalculationError::Overflow)
+    }
+
+    fn div(self, other: Self, _: &()) -> NumericResult<Self> {
+        if other == 0 {
+            return Err(CalculationError::ZeroDivision);
+        }
+
+        self.checked_div(other).ok_or(CalculationError::Overflow)
+    }
+
+    fn rem(self, other: Self, _: &()) -> NumericResult<Self> {
+        if other == 0 {
+            return Err(CalculationError::ZeroModulus);
+        }
+
+        self.checked_rem(other).ok_or(CalculationError::Overflow)
+    }
+
+    /// Negative power is the division rounded toward zero, i.e. `2 ^ -1 = 0`
+    fn pow(self, other: Self, _: &()) -> NumericResult<Self> {
+        if other < 0 {
+            return match self {
+                0 => Err(CalculationError::ZeroDivision),
+                1 => Ok(1),
+                -1 => Ok(if other % 2 == 0 { 1 } else { -1 }),
+                _ => Ok(0),
+            };
+        }
+
+        u32::try_from(other)
+            .ok()
+            .and_then(|exponent| self.checked_pow(exponent))
+            .ok_or(CalculationError::Overflow)
+    }
+
+    fn compare(&self, other: &Self) -> NumericResult<Option<Ordering>> {
+        Ok(Some(self.cmp(other)))
+    }
+}
+
+impl Rational {
+    /// Result of the builtin function calculated exactly, `None` for other functions.
+    /// `round` rounds the half according to the rounding, `sqrt` of the fraction
+    /// that is not the square and the transcendental functions have no exact result,
+    /// so they are [CalculationError::Inexact] unless the argument is outside of their domain
+    fn call_builtin(
+        function: &Function,
+        name: &str,
+        args: &[Rational],
+        rounding: Rounding,
+    ) -> Option<NumericResult<Rational>> {
+        let first = args.first()?;
+        let result = match name {
+            "abs" => first.abs(),
+            "floor" => Rational::from(first.floor()),
+            "ceil" => Rational::from(first.ceil()),
+            "round" => {
+                // more digits than the longest decimal literal change nothing that could be printed
+                let limit = BigInt::from(MAX_DECIMAL_EXPONENT);
+                let digits = args
+                    .get(1)
+                    .map_or_else(BigInt::zero, Rational::trunc)
+                    .clamp(-&limit, limit)
+                    .to_i64()
+                    .expect("digits are clamped");
+
+                rounding.round(first, digits as i32)
+            }
+            "min" => args.iter().min()?.clone(),
+            "max" => args.iter().max()?.clone(),
+            "re" | "conj" => first.clone(),
+            "im" => Rational::zero(),
+            "arg" if !first.is_negative() => Rational::zero(),
+            "sqrt" if !first.is_negative() => match first.sqrt() {
+                Some(root) => root,
+                None => return Some(Err(CalculationError::Inexact)),
+            },
+            "sqrt" | "arg" | "ln" | "log" | "exp" | "sin" | "cos" | "tan" | "asin" | "acos"
+            | "atan" => {
+                let values = args.iter().map(Rational::to_f64).collect::<Vec<_>>();
+
+                return Some(function.call(&values).and(Err(CalculationError::Inexact)));
+            }
+            _ => return None,
+        };
+
+        Some(Ok(result))
+    }
+}
+
+/// Exact fractions, so `1 / 3 * 3` is exactly 1
+impl Numeric for Rational {
+    type Context = ();
+
+    fn from_number(number: &Number, _: &()) -> NumericResult<Self> {
+        match number {
+            Number::Int(num) => Ok(Rational::from(num.clone())),
+            Number::Float(num) => <Self as Numeric>::from_f64(*num, &()),
+            Number::Decimal(digits) => {
+                Rational::from_decimal_str(digits).ok_or(CalculationError::Overflow)
+            }
+            Number::Imaginary(_) => Err(CalculationError::ComplexUnsupported),
+        }
+    }
+
+    fn from_f64(value: f64, _: &()) -> NumericResult<Self> {
+        Rational::from_f64(value).ok_or(CalculationError::OutOfDomain)
+    }
+
+    fn to_f64(&self) -> f64 {
+        Rational::to_f64(self)
+    }
+
+    /// Builtin functions are calculated exactly, `round` rounds the half away from zero,
+    /// and report [CalculationError::Inexact] without the exact result, i.e. `sqrt(2)`.
+    /// Other functions are calculated with floating point numbers
+    fn call(function: &Function, name: &str, args: &[Self], _: &()) -> NumericResult<Self> {
+        Rational::call_builtin(function, name, args, Rounding::HalfUp).unwrap_or_else(|| {
+            let values = args.iter().map(Rational::to_f64).collect::<Vec<_>>();
+
+            <Self as Numeric>::from_f64(function.call(&values)?, &())
+        })
+    }
+
+    fn neg(self, _: &()) -> NumericResult<Self> {
+        Ok(-self)
+    }
+
+    fn add(self, other: Self, _: &()) -> NumericResult<Self> {
+        Ok(&self + &other)
+    }
+
+    fn sub(self, other: Self, _: &()) -> NumericResult<Self> {
+        Ok(&self - &other)
+    }
+
+    fn mul(self, other: Self, _: &()) -> NumericResult<Self> {
+        Ok(&self * &other)
+    }
+
+    fn div(self, other: Self, _: &()) -> NumericResult<Self> {
+        self.checked_div(&other)
+            .ok_or(CalculationError::ZeroDivision)
+    }
+
+    fn rem(self, other: Self, _: &()) -> NumericResult<Self> {
+        self.checked_rem(&other)
+            .ok_or(CalculationError::ZeroModulus)
+    }
+
+    /// Only integer powers have the exact result
+    fn pow(self, other: Self, _: &()) -> NumericResult<Self> {
+        if !other.is_integer() {
+            return Err(CalculationError::FractionalPower);
+        }
+
+        // powers of zero, one and minus one don't grow, so any exponent is allowed
+        if self.is_zero() {
+            return match other.cmp(&Rational::zero()) {
+                Ordering::Less => Err(CalculationError::ZeroDivision),
+                Ordering::Equal => Ok(Rational::from(1)),
+                Ordering::Greater => Ok(self),
+            };
+        }
+
+        if self.is_integer() && self.numer().abs() == BigInt::one() {
+            let (_, parity) = other
+                .numer()
+                .checked_div_rem(&BigInt::from(2))
+                .expect("divisor is not zero");
+
+            return match parity.is_zero() {
+                true => Ok(Rational::from(1)),
+                false => Ok(self),
+            };
+        }
+
+        let exponent = other
+            .numer()
+            .to_i64()
+            .and_then(|exponent| i32::try_from(exponent).ok())
+            .ok_or(CalculationError::Overflow)?;
+        let bits = self.numer().bits().max(self.denom().bits());
+
+        if bits.saturating_mul(u64::from(exponent.unsigned_abs())) > MAX_POWER_BITS {
+            return Err(CalculationError::Overflow);
+        }
+
+        self.checked_pow(exponent)
+            .ok_or(CalculationError::ZeroDivision)
+    }
+
+    fn compare(&self, other: &Self) -> NumericResult<Option<Ordering>> {
+        Ok(Some(self.cmp(other)))
+    }
+}
+
+impl Numeral {
+    /// Keeps the integers for everything except the division without the integer result
+    fn calculate(
+        operator: &Operator,
+        left_arg: Self,
+        right_arg: Self,
+        policy: &DivisionPolicy,
+    ) -> NumericResult<Self> {
+        let (left_arg, right_arg) = match (left_arg, right_arg) {
+            (Numeral::Int(left_arg), Numeral::Int(right_arg)) => match operator {
+                Operator::Add => return Ok(Numeral::Int(&left_arg + &right_arg)),
+                Operator::Sub => return Ok(Numeral::Int(&left_arg - &right_arg)),
+                Operator::Mul => return Ok(Numeral::Int(&left_arg * &right_arg)),
+                Operator::Mod => {
+                    let (_, remainder) = left_arg
+                        .checked_div_rem(&right_arg)
+                        .ok_or(CalculationError::ZeroModulus)?;
+
+                    return Ok(Numeral::Int(remainder));
+                }
+                _ => (Rational::from(left_arg), Rational::from(right_arg)),
+            },
+            (left_arg, right_arg) => match (left_arg.to_rational(), right_arg.to_rational()) {
+                (Some(left_arg), Some(right_arg)) => (left_arg, right_arg),
+                _ => {
+                    return calculate(
+                        operator,
+                        left_arg.to_f64(),
+                        right_arg.to_f64(),
+                        &FloatPolicy::Ieee,
+                    )
+                    .map(Numeral::Float)
+                }
+            },
+        };
+
+        match (
+            calculate(operator, left_arg.clone(), right_arg.clone(), &()),
+            policy,
+        ) {
+            (Ok(result), DivisionPolicy::Float) if !result.is_integer() => {
+                Ok(Numeral::Float(result.to_f64()))
+            }
+            (Ok(result), _) => Ok(Numeral::from_rational(result)),
+            (Err(CalculationError::FractionalPower), DivisionPolicy::Float) => calculate(
+                operator,
+                left_arg.to_f64(),
+                right_arg.to_f64(),
+                &FloatPolicy::Ieee,
+            )
+            .map(Numeral::Float),
+            (Err(error), _) => Err(error),
+        }
+    }
+}
+
+/// Integers of any size that fall back to fractions or floats according to the [DivisionPolicy]
+impl Numeric for Numeral {
+    type Context = DivisionPolicy;
+
+    fn from_number(number: &Number, policy: &DivisionPolicy) -> NumericResult<Self> {
+        match number {
+            Number::Int(num) => Ok(Numeral::Int(num.clone())),
+            Number::Float(num) => Self::from_f64(*num, policy),
+            Number::Decimal(_) => match policy {
+                DivisionPolicy::Rational => {
+                    Rational::from_number(number, &()).map(Numeral::from_rational)
+                }
+                DivisionPolicy::Float => Ok(Numeral::Float(number.to_f64())),
+            },
+            Number::Imaginary(_) => Err(CalculationError::ComplexUnsupported),
+        }
+    }
+
+    fn from_f64(value: f64, policy: &DivisionPolicy) -> NumericResult<Self> {
+        match policy {
+            DivisionPolicy::Rational => Rational::from_f64(value)
+                .map(Numeral::from_rational)
+                .ok_or(CalculationError::OutOfDomain),
+            DivisionPolicy::Float => Ok(Numeral::Float(value)),
+        }
+    }
+
+    fn to_f64(&self) -> f64 {
+        Numeral::to_f64(self)
+    }
+
+    /// Builtin functions of the integers and fractions are calculated exactly when they could be,
+    /// i.e. `abs` of the integer that doesn't fit into `f64`, `round` rounds the half away from zero.
+    /// Other functions and the floating point arguments are calculated with floating point numbers
+    /// and give [Numeral::Float]
+    fn call(
+        function: &Function,
+        name: &str,
+        args: &[Self],
+        _: &DivisionPolicy,
+    ) -> NumericResult<Self> {
+        let exact = args
+            .iter()
+            .map(Numeral::to_rational)
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(exact) = exact {
+            match Rational::call_builtin(function, name, &exact, Rounding::HalfUp) {
+                None | Some(Err(CalculationError::Inexact)) => {}
+                Some(result) => return result.map(Numeral::from_rational),
+            }
+        }
+
+        let values = args.iter().map(Numeral::to_f64).collect::<Vec<_>>();
+
+        Ok(Numeral::Float(function.call(&values)?))
+    }
+
+    fn neg(self, _: &DivisionPolicy) -> NumericResult<Self> {
+        Ok(match self {
+            Numeral::Int(value) => Numeral::Int(-value),
+            Numeral::Rational(value) => Numeral::Rational(-value),
+            Numeral::Float(value) => Numeral::Float(-value),
+        })
+    }
+
+    fn add(self, other: Self, policy: &DivisionPolicy) -> NumericResult<Self> {
+        Self::calculate(&Operator::Add, self, other, policy)
+    }
+
+    fn sub(self, other: Self, policy: &DivisionPolicy) -> NumericResult<Self> {
+        Self::calculate(&Operator::Sub, self, other, policy)
+    }
+
+    fn mul(self, other: Self, policy: &DivisionPolicy) -> NumericResult<Self> {
+        Self::calculate(&Operator::Mul, self, other, policy)
+    }
+
+    fn div(self, other: Self, policy: &DivisionPolicy) -> NumericResult<Self> {
+        Self::calculate(&Operator::Div, self, other, policy)
+    }
+
+    fn rem(self, other: Self, policy: &DivisionPolicy) -> NumericResult<Self> {
+        Self::calculate(&Operator::Mod, self, other, policy)
+    }
+
+    fn pow(self, other: Self, policy: &DivisionPolicy) -> NumericResult<Self> {
+        Self::calculate(&Operator::Pow, self, other, policy)
+    }
+
+    /// Integers and fractions are compared exactly, so large integers don't collapse
+    fn compare(&self, other: &Self) -> NumericResult<Option<Ordering>> {
+        Ok(match (self.to_rational(), other.to_rational()) {
+            (Some(left), Some(right)) => Some(left.cmp(&right)),
+            _ => self.to_f64().partial_cmp(&other.to_f64()),
+        })
+    }
+}
+
+impl Decimal {
+    /// Rounds the exact result of the operation according to the context
+    fn round(value: Rational, context: &DecimalContext) -> NumericResult<Self> {
+        Decimal::from_rational(&value, context).ok_or(CalculationError::Overflow)
+    }
+}
+
+/// Fixed-point decimals, every result is rounded to the scale of the [DecimalContext]
+impl Numeric for Decimal {
+    type Context = DecimalContext;
+
+    fn from_number(number: &Number, context: &DecimalContext) -> NumericResult<Self> {
+        Self::round(Rational::from_number(number, &())?, context)
+    }
+
+    fn from_f64(value: f64, context: &DecimalContext) -> NumericResult<Self> {
+        Self::round(<Rational as Numeric>::from_f64(value, &())?, context)
+    }
+
+    fn to_f64(&self) -> f64 {
+        Decimal::to_f64(self)
+    }
+
+    /// Builtin functions are calculated exactly when they could be,
+    /// `round` rounds to the digits by the rounding of the context, i.e. `round(1.005, 2)`
+    /// is `1.01` for [Rounding::HalfUp]. Other functions are calculated with floating point numbers
+    fn call(
+        function: &Function,
+        name: &str,
+        args: &[Self],
+        context: &DecimalContext,
+    ) -> NumericResult<Self> {
+        let exact = args.iter().map(Decimal::to_rational).collect::<Vec<_>>();
+
+        match Rational::call_builtin(function, name, &exact, context.rounding) {
+            None | Some(Err(CalculationError::Inexact)) => {
+                let values = args.iter().map(Decimal::to_f64).collect::<Vec<_>>();
+
+                <Self as Numeric>::from_f64(function.call(&values)?, context)
+            }
+            Some(result) => Self::round(result?, context),
+        }
+    }
+
+    fn neg(self, context: &DecimalContext) -> NumericResult<Self> {
+        Self::round(-self.to_rational(), context)
+    }
+
+    fn add(self, other: Self, context: &DecimalContext) -> NumericResult<Self> {
+        Self::round(&self.to_rational() + &other.to_rational(), context)
+    }
+
+    fn sub(self, other: Self, context: &DecimalContext) -> NumericResult<Self> {
+        Self::round(&self.to_rational() - &other.to_rational(), context)
+    }
+
+    fn mul(self, other: Self, context: &DecimalContext) -> NumericResult<Self> {
+        Self::round(&self.to_rational() * &other.to_rational(), context)
+    }
+
+    fn div(self, other: Self, context: &DecimalContext) -> NumericResult<Self> {
+        let result = self.to_rational().div(other.to_rational(), &())?;
+
+        Self::round(result, context)
+    }
+
+    fn rem(self, other: Self, context: &DecimalContext) -> NumericResult<Self> {
+        let result = self.to_rational().rem(other.to_rational(), &())?;
+
+        Self::round(result, context)
+    }
+
+    /// Fractional powers are calculated with floating point numbers before the rounding
+    fn pow(self, other: Self, context: &DecimalContext) -> NumericResult<Self> {
+        match Numeric::pow(self.to_rational(), other.to_rational(), &()) {
+            Err(CalculationError::FractionalPower) => {
+                let result = f64::pow(self.to_f64(), other.to_f64(), &FloatPolicy::Ieee)?;
+
+                <Self as Numeric>::from_f64(result, context)
+            }
+            result => Self::round(result?, context),
+        }
+    }
+
+    fn compare(&self, other: &Self) -> NumericResult<Option<Ordering>> {
+        Ok(Some(self.to_rational().cmp(&other.to_rational())))
+    }
+}
+
+impl Quantity {
+    /// Values of both quantities in the unit of the left one
+    fn align(self, other: Self) -> NumericResult<(f64, f64, Unit)> {
+        if !self.unit().is_compatible(other.unit()) {
+            return Err(CalculationError::IncompatibleUnits(
+                self.unit().to_string(),
+                other.unit().to_string(),
+            ));
+        }
+
+        let other = other.convert(self.unit())?;
+
+        Ok((self.value(), other.value(), self.unit().clone()))
+    }
+}
+
+/// Floating point numbers with the units, see [Units].
+/// Sum, difference, remainder and comparison take the quantities of the same dimension
+/// and give the result in the unit of the left operand, i.e. `1 kg + 500 g = 1.5 kg`,
+/// while the product and the quotient combine the units, i.e. `USD/oz * oz = USD`
+impl Numeric for Quantity {
+    type Context = Units;
+
+    fn from_number(number: &Number, _: &Units) -> NumericResult<Self> {
+        Ok(Quantity::number(f64::from_number(
+            number,
+            &FloatPolicy::Ieee,
+        )?))
+    }
+
+    fn from_f64(value: f64, _: &Units) -> NumericResult<Self> {
+        Ok(Quantity::number(value))
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.value()
+    }
+
+    /// `abs`, `floor`, `ceil` and `round` keep the unit of the first argument,
+    /// `min` and `max` convert all arguments to it, other functions take plain numbers
+    fn call(function: &Function, name: &str, args: &[Self], _: &Units) -> NumericResult<Self> {
+        let none = Unit::none();
+        let unit = match (name, args.first()) {
+            ("abs" | "floor" | "ceil" | "round" | "min" | "max", Some(first)) => first.unit(),
+            _ => &none,
+        };
+        let values = args
+            .iter()
+            .enumerate()
+            .map(|(position, arg)| {
+                let expected = match (name, position) {
+                    ("min" | "max", _) | (_, 0) => unit,
+                    _ => &none,
+                };
+
+                arg.convert(expected).map(|arg| arg.value())
+            })
+            .collect::<NumericResult<Vec<_>>>()?;
+
+        Ok(Quantity::new(function.call(&values)?, unit.clone()))
+    }
+
+    fn neg(self, _: &Units) -> NumericResult<Self> {
+        Ok(Quantity::new(-self.value(), self.unit().clone()))
+    }
+
+    fn add(self, other: Self, _: &Units) -> NumericResult<Self> {
+        let (left, right, unit) = self.align(other)?;
+
+        Ok(Quantity::new(left + right, unit))
+    }
+
+    fn sub(self, other: Self, _: &Units) -> NumericResult<Self> {
+        let (left, right, unit) = self.align(other)?;
+
+        Ok(Quantity::new(left - right, unit))
+    }
+
+    fn mul(self, other: Self, _: &Units) -> NumericResult<Self> {
+        Ok(Quantity::new(
+            self.value() * other.value(),
+            self.unit().mul(other.unit()),
+        ))
+    }
+
+    fn div(self, other: Self, _: &Units) -> NumericResult<Self> {
+        let value = self.value().div(other.value(), &FloatPolicy::Ieee)?;
+
+        Ok(Quantity::new(value, self.unit().div(other.unit())))
+    }
+
+    fn rem(self, other: Self, _: &Units) -> NumericResult<Self> {
+        let (left, right, unit) = self.align(other)?;
+
+        Ok(Quantity::new(left.rem(right, &FloatPolicy::Ieee)?, unit))
+    }
+
+    /// Exponent should be the plain number, which is the integer for the quantity with the unit
+    fn pow(self, other: Self, _: &Units) -> NumericResult<Self> {
+        let exponent = other.convert(&Unit::none())?.value();
+        let value = f64::pow(self.value(), exponent, &FloatPolicy::Ieee)?;
+
+        if self.unit().is_none() {
+            return Ok(Quantity::number(value));
+        }
+
+        if exponent.fract() != ZERO || exponent.abs() > f64::from(i32::MAX) {
+            return Err(CalculationError::FractionalPowerOfUnit);
+        }
+
+        Ok(Quantity::new(value, self.unit().powi(exponent as i32)))
+    }
+
+    fn compare(&self, other: &Self) -> NumericResult<Option<Ordering>> {
+        let (left, right, _) = self.clone().align(other.clone())?;
+
+        Ok(left.partial_cmp(&right))
+    }
+
+    /// Plain number gets the unit, while the quantity is converted to it
+    fn with_unit(self, unit: &str, units: &Units) -> NumericResult<Self> {
+        let unit = units.parse(unit)?;
+
+        match self.unit().is_none() {
+            true => Ok(Quantity::new(self.value(), unit)),
+            false => self.convert(&unit),
+        }
+    }
+}
+
+impl Complex {
+    /// Result of the builtin function that is complex-aware, `None` for other functions
+    fn call_builtin(name: &str, args: &[Complex]) -> Option<NumericResult<Complex>> {
+        let first = *args.first()?;
+        let result = match name {
+            "sqrt" => first.sqrt(),
+            "exp" => first.exp(),
+            "ln" | "log" if first.is_zero() => return Some(Err(CalculationError::OutOfDomain)),
+            "ln" => first.ln(),
+            "log" => match args.get(1) {
+                Some(base) if base.is_zero() || *base == Complex::real(1.0) => {
+                    return Some(Err(CalculationError::OutOfDomain))
+                }
+                Some(base) => first.ln() / base.ln(),
+                None => first.log10(),
+            },
+            "sin" => first.sin(),
+            "cos" => first.cos(),
+            "tan" => first.tan(),
+            "asin" => first.asin(),
+            "acos" => first.acos(),
+            "atan" => first.atan(),
+            "abs" => Complex::real(first.abs()),
+            "arg" => Complex::real(first.arg()),
+            "re" => Complex::real(first.re()),
+            "im" => Complex::real(first.im()),
+            "conj" => first.conj(),
+            "floor" => Complex::new(first.re().floor(), first.im().floor()),
+            "ceil" => Complex::new(first.re().ceil(), first.im().ceil()),
+            "round" => {
+                let digits = args.get(1).copied().unwrap_or_default();
+
+                if !digits.is_real() {
+                    return Some(Err(CalculationError::NonReal(Operation::Function(
+                        name.to_string(),
+                    ))));
+                }
+
+                Complex::new(
+                    functions::round(first.re(), digits.re()),
+                    functions::round(first.im(), digits.re()),
+                )
+            }
+            _ => return None,
+        };
+
+        Some(Ok(result))
+    }
+}
+
+/// Complex numbers of the floating point parts, the imaginary literals are written as `2i`.
+/// Remainder and order are defined only for the real numbers, so the distinct non-real numbers
+/// are unordered, i.e. `1i < 2i` is false, while `1i != 2i` is true
+impl Numeric for Complex {
+    type Context = ();
+
+    fn from_number(number: &Number, _: &()) -> NumericResult<Self> {
+        Ok(match number {
+            Number::Imaginary(num) => Complex::new(0.0, *num),
+            number => Complex::real(number.to_f64()),
+        })
+    }
+
+    fn from_f64(value: f64, _: &()) -> NumericResult<Self> {
+        Ok(Complex::real(value))
+    }
+
+    /// Real part of the number
+    fn to_f64(&self) -> f64 {
+        self.re()
+    }
+
+    /// Real arguments give the same result as the floating point numbers,
+    /// unless they are outside of the function domain, i.e. `sqrt(-1)`.
+    /// The builtin functions are calculated over the complex numbers in that case
+    /// and for the non-real arguments, other functions take only the real numbers
+    fn call(function: &Function, name: &str, args: &[Self], _: &()) -> NumericResult<Self> {
+        if args.iter().all(Complex::is_real) {
+            let values = args.iter().map(Complex::re).collect::<Vec<_>>();
+
+            match function.call(&values) {
+                Err(CalculationError::OutOfDomain) => {}
+                result => return result.map(Complex::real),
+            }
+        }
+
+        Complex::call_builtin(name, args).unwrap_or_else(|| {
+            Err(CalculationError::NonReal(Operation::Function(
+                name.to_string(),
+            )))
+        })
+    }
+
+    fn neg(self, _: &()) -> NumericResult<Self> {
+        Ok(-self)
+    }
+
+    fn add(self, other: Self, _: &()) -> NumericResult<Self> {
+        Ok(self + other)
+    }
+
+    fn sub(self, other: Self, _: &()) -> NumericResult<Self> {
+        Ok(self - other)
+    }
+
+    fn mul(self, other: Self, _: &()) -> NumericResult<Self> {
+        Ok(self * other)
+    }
+
+    fn div(self, other: Self, _: &()) -> NumericResult<Self> {
+        match other.is_zero() {
+            true => Err(CalculationError::ZeroDivision),
+            false => Ok(self / other),
+        }
+    }
+
+    fn rem(self, other: Self, _: &()) -> NumericResult<Self> {
+        if !self.is_real() || !other.is_real() {
+            return Err(CalculationError::NonReal(Operation::Operator(
+                Operator::Mod,
+            )));
+        }
+
+        Ok(Complex::real(f64::rem(
+            self.re(),
+            other.re(),
+            &FloatPolicy::Ieee,
+        )?))
+    }
+
+    /// Real power is kept real unless the negative base is raised to the fractional power,
+    /// integer power is calculated by squaring and the half power is the square root,
+    /// so `(-1) ^ 0.5` is exactly `i`, other powers are principal ones
+    fn pow(self, other: Self, _: &()) -> NumericResult<Self> {
+        if self.is_real() && other.is_real() {
+            match f64::pow(self.re(), other.re(), &FloatPolicy::Ieee) {
+                Err(CalculationError::FractionalPowerOfNegative) => {}
+                result => return result.map(Complex::real),
+            }
+        }
+
+        let exponent = other.re();
+
+        if other.is_real() && exponent.fract() == ZERO && exponent.abs() <= f64::from(i32::MAX) {
+            return match self.is_zero() && exponent < ZERO {
+                true => Err(CalculationError::ZeroDivision),
+                false => Ok(self.powi(exponent as i32)),
+            };
+        }
+
+        if other == Complex::real(0.5) {
+            return Ok(self.sqrt());
+        }
+
+        if self.is_zero() {
+            return match exponent > ZERO {
+                true => Ok(Complex::default()),
+                false => Err(CalculationError::ZeroDivision),
+            };
+        }
+
+        Ok(self.powc(other))
+    }
+
+    fn compare(&self, other: &Self) -> NumericResult<Option<Ordering>> {
+        Ok(match self.is_real() && other.is_real() {
+            true => self.re().partial_cmp(&other.re()),
+            false => (self == other).then_some(Ordering::Equal),
+        })
+    }
+}
+
+impl From<i64> for Numeral {
+    fn from(value: i64) -> Self {
+        Self::Int(BigInt::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{decimal::Rounding, functions::Functions};
+
+    #[test]
+    fn checked_integers() {
+        let int = |num: i64| Number::Int(num.into());
+
+        assert_eq!(i64::from_number(&int(i64::MAX), &()), Ok(i64::MAX));
+        assert_eq!(
+            i64::from_number(&Number::Int("9223372036854775808".parse().unwrap()), &()),
+            Err(CalculationError::Overflow)
+        );
+        assert_eq!(
+            i64::from_number(&Number::Float(2.5), &()),
+            Err(CalculationError::Inexact)
+        );
+        assert_eq!(i64::from_f64(-3.0, &()), Ok(-3));
+        assert_eq!(i64::MAX.add(1, &()), Err(CalculationError::Overflow));
+        assert_eq!(i64::MIN.neg(&()), Err(CalculationError::Overflow));
+        assert_eq!(i64::MIN.div(-1, &()), Err(CalculationError::Overflow));
+        assert_eq!(7i64.div(2, &()), Ok(3));
+        assert_eq!((-7i64).rem(2, &()), Ok(-1));
+        assert_eq!(Numeric::pow(2i64, 62, &()), Ok(1 << 62));
+        assert_eq!(Numeric::pow(2i64, 63, &()), Err(CalculationError::Overflow));
+        assert_eq!(Numeric::pow(2i64, -1, &()), Ok(0));
+        assert_eq!(Numeric::pow(-1i64, -3, &()), Ok(-1));
+        assert_eq!(
+            Numeric::pow(0i64, -1, &()),
+            Err(CalculationError::ZeroDivision)
+        );
+    }
+
+    #[test]
+    fn operators() {
+        assert_eq!(
+            calculate(&Operator::Sub, 1.0, 3.0, &FloatPolicy::Ieee),
+            Ok(-2.0)
+        );
+        assert_eq!(calculate(&Operator::Mul, 6i64, 7, &()), Ok(42));
+        assert_eq!(
+            calculate(&Operator::Mod, 1.0, 0.0, &FloatPolicy::Ieee),
+            Err(CalculationError::ZeroModulus)
+        );
+        assert_eq!(
+            calculate(
+                &Operator::Div,
+                Numeral::from(7),
+                Numeral::from(2),
+                &DivisionPolicy::Float
+            ),
+            Ok(Numeral::Float(3.5))
+        );
+
+        let rational = |num: i64| Rational::from(num);
+        let huge = rational(5_000_000_000);
+
+        assert_eq!(
+            Numeric::pow(rational(1), huge.clone(), &()),
+            Ok(rational(1))
+        );
+        assert_eq!(
+            Numeric::pow(rational(-1), rational(5_000_000_001), &()),
+            Ok(rational(-1))
+        );
+        assert_eq!(
+            Numeric::pow(rational(0), huge.clone(), &()),
+            Ok(rational(0))
+        );
+        assert_eq!(
+            Numeric::pow(rational(0), -huge.clone(), &()),
+            Err(CalculationError::ZeroDivision)
+        );
+        assert_eq!(
+            Numeric::pow(rational(2), huge, &()),
+            Err(CalculationError::Overflow)
+        );
+    }
+
+    #[test]
+    fn rationals() {
+        let functions = Functions::builtin();
+        let call =
+            |name, args: &[Rational]| Rational::call(functions.get(name).unwrap(), name, args, &());
+        let ratio = |numer: i64, denom: i64| Rational::new(numer.into(), denom.into()).unwrap();
+        let int = Rational::from;
+
+        assert_eq!(call("min", &[int(1), ratio(1, 3)]), Ok(ratio(1, 3)));
+        assert_eq!(call("round", &[ratio(5, 2)]), Ok(int(3)));
+        assert_eq!(call("round", &[int(1250), int(-2)]), Ok(int(1300)));
+        assert_eq!(call("round", &[int(1), int(1_000_000)]), Ok(int(1)));
+        assert_eq!(call("floor", &[ratio(-5, 2)]), Ok(int(-3)));
+        assert_eq!(call("abs", &[int(-3)]), Ok(int(3)));
+        assert_eq!(call("sqrt", &[ratio(9, 4)]), Ok(ratio(3, 2)));
+        assert_eq!(call("sqrt", &[int(2)]), Err(CalculationError::Inexact));
+        assert_eq!(call("sqrt", &[int(-1)]), Err(CalculationError::OutOfDomain));
+        assert_eq!(call("exp", &[int(1)]), Err(CalculationError::Inexact));
+        assert_eq!(call("ln", &[int(0)]), Err(CalculationError::OutOfDomain));
+    }
+
+    #[test]
+    fn decimals() {
+        let context = DecimalContext::default();
+        let cents = |units: i128| Decimal::new(units, 2);
+
+        assert_eq!(
+            Decimal::from_number(&Number::Float(0.125), &context),
+            Ok(cents(12))
+        );
+        assert_eq!(cents(100).div(cents(300), &context), Ok(cents(33)));
+        assert_eq!(
+            cents(100).div(cents(300), &context.with_rounding(Rounding::Up)),
+            Ok(cents(34))
+        );
+        assert_eq!(cents(5).mul(cents(50), &context), Ok(cents(2)));
+        assert_eq!(
+            Numeric::pow(cents(400), cents(50), &context),
+            Ok(cents(200))
+        );
+        assert_eq!(
+            cents(100).div(cents(0), &context),
+            Err(CalculationError::ZeroDivision)
+        );
+        assert_eq!(
+            Decimal::new(i128::MAX, 2).add(cents(1), &context),
+            Err(CalculationError::Overflow)
+        );
+        assert_eq!(
+            Decimal::from_f64(f64::INFINITY, &context),
+            Err(CalculationError::OutOfDomain)
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        let big = |digits: &str| Numeral::Int(digits.parse().unwrap());
+        // differs from the next integer only beyond the precision of `f64`
+        let large = big("9007199254740993");
+
+        assert_eq!(
+            compare(&Operator::Lt, &big("9007199254740992"), &large),
+            Ok(true)
+        );
+        assert_eq!(
+            compare(&Operator::Ge, &Decimal::new(150, 2), &Decimal::new(15, 1)),
+            Ok(true)
+        );
+        assert_eq!(compare(&Operator::Le, &i64::MAX, &i64::MAX), Ok(true));
+        assert_eq!(compare(&Operator::Ne, &f64::NAN, &f64::NAN), Ok(true));
+        assert_eq!(compare(&Operator::Eq, &f64::NAN, &f64::NAN), Ok(false));
+        assert_eq!(
+            compare(&Operator::Gt, &Rational::from(1), &Rational::from(2)),
+            Ok(false)
+        );
+    }
+
+    #[test]
+    fn quantities() {
+        let units = Units::builtin();
+        let quantity = |value, unit: &str| Quantity::number(value).with_unit(unit, &units);
+        let kg = quantity(1.0, "kg").unwrap();
+        let grams = quantity(500.0, "g").unwrap();
+
+        assert_eq!(kg.clone().add(grams.clone(), &units), quantity(1.5, "kg"));
+        assert_eq!(grams.clone().sub(kg.clone(), &units), quantity(-500.0, "g"));
+        assert_eq!(
+            grams.clone().add(quantity(1.0, "USD").unwrap(), &units),
+            Err(CalculationError::IncompatibleUnits(
+                "g".to_string(),
+                "USD".to_string()
+            ))
+        );
+        assert_eq!(
+            quantity(3.0, "USD/oz")
+                .unwrap()
+                .mul(quantity(2.0, "oz").unwrap(), &units),
+            quantity(6.0, "USD")
+        );
+        assert_eq!(
+            Numeric::pow(quantity(3.0, "m").unwrap(), Quantity::number(2.0), &units),
+            quantity(9.0, "m^2")
+        );
+        assert_eq!(
+            Numeric::pow(kg.clone(), Quantity::number(0.5), &units),
+            Err(CalculationError::FractionalPowerOfUnit)
+        );
+        assert_eq!(
+            kg.clone().with_unit("g", &units),
+            Ok(Quantity::new(1000.0, units.parse("g").unwrap()))
+        );
+        assert_eq!(compare(&Operator::Gt, &kg, &grams), Ok(true));
+        assert_eq!(
+            Quantity::call(
+                Functions::builtin().get("max").unwrap(),
+                "max",
+                &[grams.clone(), kg.clone()],
+                &units
+            ),
+            quantity(1000.0, "g")
+        );
+        assert_eq!(
+            2.0.with_unit("kg", &FloatPolicy::Ieee),
+            Err(CalculationError::UnitsUnsupported)
+        );
+    }
+
+    #[test]
+    fn complexes() {
+        let functions = Functions::builtin();
+        let call =
+            |name, args: &[Complex]| Complex::call(functions.get(name).unwrap(), name, args, &());
+        let i = Complex::i();
+
+        assert_eq!(
+            Complex::from_number(&Number::Imaginary(2.5), &()),
+            Ok(Complex::new(0.0, 2.5))
+        );
+        assert_eq!(
+            f64::from_number(&Number::Imaginary(2.5), &FloatPolicy::Ieee),
+            Err(CalculationError::ComplexUnsupported)
+        );
+        assert_eq!(
+            Numeric::pow(i, Complex::real(2.0), &()),
+            Ok(Complex::real(-1.0))
+        );
+        assert_eq!(
+            Numeric::pow(Complex::real(-4.0), Complex::real(0.5), &()),
+            Ok(Complex::new(0.0, 2.0))
+        );
+        assert_eq!(
+            Numeric::pow(Complex::default(), i, &()),
+            Err(CalculationError::ZeroDivision)
+        );
+        assert_eq!(
+            i.div(Complex::default(), &()),
+            Err(CalculationError::ZeroDivision)
+        );
+        assert_eq!(
+            i.rem(Complex::real(2.0), &()),
+            Err(CalculationError::NonReal(Operation::Operator(
+                Operator::Mod
+            )))
+        );
+        assert_eq!(
+            call("sqrt", &[Complex::real(-9.0)]),
+            Ok(Complex::new(0.0, 3.0))
+        );
+        assert_eq!(call("sqrt", &[Complex::real(9.0)]), Ok(Complex::real(3.0)));
+        assert_eq!(
+            call("abs", &[Complex::new(3.0, -4.0)]),
+            Ok(Complex::real(5.0))
+        );
+        assert_eq!(
+            call("conj", &[Complex::new(3.0, -4.0)]),
+            Ok(Complex::new(3.0, 4.0))
+        );
+        assert_eq!(
+            call("ln", &[Complex::default()]),
+            Err(CalculationError::OutOfDomain)
+        );
+        assert_eq!(
+            call("round", &[Complex::new(1.25, 1e300), Complex::real(1.0)]),
+            Ok(Complex::new(1.3, 1e300))
+        );
+        assert_eq!(
+            call("round", &[Complex::new(1.0, 2.0), Complex::real(-400.0)]),
+            Ok(Complex::default())
+        );
+        assert_eq!(
+            call("max", &[i, Complex::real(1.0)]),
+            Err(CalculationError::NonReal(Operation::Function(
+                "max".to_string()
+            )))
+        );
+        assert_eq!(
+            compare(&Operator::Lt, &i, &i.add(i, &()).unwrap()),
+            Ok(false)
+        );
+        assert_eq!(compare(&Operator::Ne, &i, &Complex::real(1.0)), Ok(true));
+        assert_eq!(
+            compare(&Operator::Ge, &Complex::real(2.0), &Complex::real(1.0)),
+            Ok(true)
+        );
+    }
+}
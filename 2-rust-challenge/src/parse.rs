@@ -1,21 +1,94 @@
 //! Module that converts iterator of Token
-//! into Prefix (Polish) Notation iterator
+//! into the expression tree
 //! that later could be evaluated by eval module
 
-use std::{cmp::Ordering, fmt::Display};
+use std::{collections::HashMap, fmt::Display, io::Read, iter::Peekable};
 
-use super::tokens::{Group, Result as TokenizerResult, Token, TokenizerError};
+use super::{
+    dialect::Dialect,
+    span::{Span, Spanned},
+    stream::TokenReader,
+    tokens::{
+        Branch, Group, Number, Operator, Result as TokenizerResult, Token, TokenIterator,
+        TokenizerError,
+    },
+};
 
 #[derive(Debug, PartialEq)]
 pub enum ParserError {
     TokenizerError(TokenizerError),
-    EmptyExpr,
-    UnbalancedGroup(Option<Token>),
-    OperatorExpected(Option<Token>),
+    EmptyExpr {
+        span: Span,
+    },
+    UnbalancedGroup {
+        token: Option<Token>,
+        span: Span,
+    },
+    OperatorExpected {
+        token: Option<Token>,
+        span: Span,
+    },
     OperandExpected {
         token: Option<Token>,
         operator: Option<Token>,
+        span: Span,
+    },
+    /// Argument separator outside of the function call
+    UnexpectedSeparator {
+        span: Span,
+    },
+    /// Operator of the Polish notation has fewer operands on the stack than it takes
+    StackUnderflow {
+        token: Token,
+        needed: usize,
+        available: usize,
+        span: Span,
+    },
+    /// Stack of the Polish notation has grown over its limit
+    StackOverflow {
+        limit: usize,
+        span: Span,
+    },
+    /// Values of the Polish notation that are not consumed by any operator
+    ExtraOperands {
+        count: usize,
+        span: Span,
+    },
+    /// Function of the Polish notation without the number of its arguments
+    ArgumentCountExpected {
+        token: Option<Token>,
+        span: Span,
+    },
+    /// Token that has no meaning in its position, i.e. bracket of the Polish notation
+    /// or `:` outside of the conditional
+    UnexpectedToken {
+        token: Token,
+        span: Span,
     },
+    /// Then branch of the conditional is finished without `:` and the else branch
+    ElseExpected {
+        token: Option<Token>,
+        span: Span,
+    },
+}
+
+impl ParserError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::TokenizerError(error) => error.span(),
+            Self::EmptyExpr { span }
+            | Self::UnbalancedGroup { span, .. }
+            | Self::OperatorExpected { span, .. }
+            | Self::OperandExpected { span, .. }
+            | Self::UnexpectedSeparator { span }
+            | Self::StackUnderflow { span, .. }
+            | Self::StackOverflow { span, .. }
+            | Self::ExtraOperands { span, .. }
+            | Self::ArgumentCountExpected { span, .. }
+            | Self::UnexpectedToken { span, .. }
+            | Self::ElseExpected { span, .. } => *span,
+        }
+    }
 }
 
 impl From<TokenizerError> for ParserError {
@@ -28,10 +101,34 @@ impl Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::TokenizerError(error) => write!(f, "{error}"),
-            Self::EmptyExpr => write!(f, "Expression is empty"),
-            Self::UnbalancedGroup(_) => write!(f, "Unbalanced brackets"),
-            Self::OperatorExpected(_) => write!(f, "Expected operator"),
+            Self::EmptyExpr { .. } => write!(f, "Expression is empty"),
+            Self::UnbalancedGroup { .. } => write!(f, "Unbalanced brackets"),
+            Self::OperatorExpected { .. } => write!(f, "Expected operator"),
+            Self::OperandExpected { token: None, .. } => {
+                write!(f, "Expected operand, but the expression has ended")
+            }
             Self::OperandExpected { .. } => write!(f, "Expected operand"),
+            Self::UnexpectedSeparator { .. } => {
+                write!(f, "Argument separator outside of the function call")
+            }
+            Self::StackUnderflow {
+                needed, available, ..
+            } => write!(
+                f,
+                "Expected {needed} operands on the stack, but there are {available}"
+            ),
+            Self::StackOverflow { limit, .. } => {
+                write!(f, "Stack has exceeded the limit of {limit} values")
+            }
+            Self::ExtraOperands { count, .. } => write!(
+                f,
+                "Expected single result, but {count} values are left on the stack"
+            ),
+            Self::ArgumentCountExpected { .. } => {
+                write!(f, "Expected number of the function arguments")
+            }
+            Self::UnexpectedToken { .. } => write!(f, "Unexpected token"),
+            Self::ElseExpected { .. } => write!(f, "Expected else branch of the conditional"),
         }
     }
 }
@@ -47,6 +144,198 @@ impl std::error::Error for ParserError {
 
 pub type Result<T> = std::result::Result<T, ParserError>;
 
+/// Parsed expression in the form of the tree
+///
+/// Spans are ignored when expressions are compared
+/// as they describe the source rather than the structure
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExprKind {
+    Number(Number),
+    Unary {
+        operator: Operator,
+        operand: Box<Expr>,
+    },
+    Binary {
+        operator: Operator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// Expression explicitly wrapped in brackets
+    Group(Box<Expr>),
+    /// Call of the named function with the list of arguments
+    Call {
+        name: String,
+        args: Vec<Expr>,
+    },
+    /// Named value provided at the time of the evaluation
+    Variable(String),
+    /// Either of the branches chosen by the boolean condition, i.e. `a > b ? a : b`
+    Conditional {
+        condition: Box<Expr>,
+        then: Box<Expr>,
+        otherwise: Box<Expr>,
+    },
+    /// Operand with the unit, i.e. `5[kg]`, which converts the operand that already has the unit
+    Unit {
+        operand: Box<Expr>,
+        unit: String,
+    },
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+
+    pub fn number(number: Number) -> Self {
+        Self::new(ExprKind::Number(number), Span::default())
+    }
+
+    /// Creates unary node with the span covering the operand
+    pub fn unary(operator: Operator, operand: Expr) -> Self {
+        let span = operand.span;
+
+        Self::new(
+            ExprKind::Unary {
+                operator,
+                operand: Box::new(operand),
+            },
+            span,
+        )
+    }
+
+    /// Creates binary node with the span covering both operands
+    pub fn binary(operator: Operator, left: Expr, right: Expr) -> Self {
+        let span = left.span.join(right.span);
+
+        Self::new(
+            ExprKind::Binary {
+                operator,
+                left: Box::new(left),
+                right: Box::new(right),
+            },
+            span,
+        )
+    }
+
+    /// Creates group node with the span covering the inner expression
+    pub fn group(inner: Expr) -> Self {
+        let span = inner.span;
+
+        Self::new(ExprKind::Group(Box::new(inner)), span)
+    }
+
+    pub fn variable(name: impl Into<String>) -> Self {
+        Self::new(ExprKind::Variable(name.into()), Span::default())
+    }
+
+    pub fn call(name: impl Into<String>, args: Vec<Expr>) -> Self {
+        let span = args
+            .iter()
+            .map(|arg| arg.span)
+            .reduce(Span::join)
+            .unwrap_or_default();
+
+        Self::new(
+            ExprKind::Call {
+                name: name.into(),
+                args,
+            },
+            span,
+        )
+    }
+
+    /// Creates conditional node with the span covering the condition and both branches
+    pub fn conditional(condition: Expr, then: Expr, otherwise: Expr) -> Self {
+        let span = condition.span.join(otherwise.span);
+
+        Self::new(
+            ExprKind::Conditional {
+                condition: Box::new(condition),
+                then: Box::new(then),
+                otherwise: Box::new(otherwise),
+            },
+            span,
+        )
+    }
+
+    /// Creates unit node with the span of the operand
+    pub fn unit(operand: Expr, unit: impl Into<String>) -> Self {
+        let span = operand.span;
+
+        Self::new(
+            ExprKind::Unit {
+                operand: Box::new(operand),
+                unit: unit.into(),
+            },
+            span,
+        )
+    }
+
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Same tree without the groups, which only describe how the source was written
+    pub fn ungrouped(&self) -> Expr {
+        let kind = match &self.kind {
+            ExprKind::Group(inner) => return inner.ungrouped(),
+            ExprKind::Number(_) | ExprKind::Variable(_) => return self.clone(),
+            ExprKind::Unary { operator, operand } => ExprKind::Unary {
+                operator: operator.clone(),
+                operand: Box::new(operand.ungrouped()),
+            },
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => ExprKind::Binary {
+                operator: operator.clone(),
+                left: Box::new(left.ungrouped()),
+                right: Box::new(right.ungrouped()),
+            },
+            ExprKind::Call { name, args } => ExprKind::Call {
+                name: name.clone(),
+                args: args.iter().map(Expr::ungrouped).collect(),
+            },
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => ExprKind::Conditional {
+                condition: Box::new(condition.ungrouped()),
+                then: Box::new(then.ungrouped()),
+                otherwise: Box::new(otherwise.ungrouped()),
+            },
+            ExprKind::Unit { operand, unit } => ExprKind::Unit {
+                operand: Box::new(operand.ungrouped()),
+                unit: unit.clone(),
+            },
+        };
+
+        Expr::new(kind, self.span)
+    }
+}
+
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
+impl From<Number> for Expr {
+    fn from(number: Number) -> Self {
+        Self::number(number)
+    }
+}
+
 #[derive(Default, Debug)]
 enum State {
     #[default]
@@ -55,183 +344,619 @@ enum State {
     OperatorOrEnd,
 }
 
-#[derive(Default, Debug)]
-pub struct ExprAst {
-    stack: Vec<Token>,
-    state: State,
-    group_nesting_index: u32,
-    last_group_token: Option<Token>,
+/// Token that has finished the group
+#[derive(Debug, PartialEq)]
+enum Terminator {
+    Close,
+    Separator,
+    /// Then branch of the conditional is finished by `:`
+    Else,
+    End,
 }
 
-impl<'token> IntoIterator for &'token ExprAst {
-    type Item = &'token Token;
-
-    type IntoIter = std::iter::Rev<std::slice::Iter<'token, Token>>;
+#[derive(Debug, PartialEq)]
+enum GroupKind {
+    Bracket,
+    /// Group contains arguments of the function call
+    Call,
+    /// Then branch of the conditional that waits for `:`
+    Then,
+}
 
-    fn into_iter(self) -> Self::IntoIter {
-        self.stack.iter().rev()
-    }
+#[derive(Debug)]
+struct OpenGroup {
+    span: Span,
+    kind: GroupKind,
 }
 
-impl ExprAst {
-    fn parse(
-        &mut self,
-        tokens_iter: &mut impl Iterator<Item = TokenizerResult<Token>>,
-    ) -> Result<()> {
-        self.parse_group(tokens_iter)?;
+/// Builds the [Expr] tree from the operator and operand stacks
+/// using the shunting yard approach
+#[derive(Debug)]
+struct ParserState<'config> {
+    config: &'config ParserConfig,
+    state: State,
+    /// Opening brackets of the groups that are not closed yet
+    open_groups: Vec<OpenGroup>,
+    /// End of the last consumed token
+    position: usize,
+}
 
-        if self.group_nesting_index > 0 {
-            return Err(ParserError::UnbalancedGroup(self.last_group_token.take()));
+impl<'config> ParserState<'config> {
+    fn new(config: &'config ParserConfig) -> Self {
+        Self {
+            config,
+            state: State::default(),
+            open_groups: vec![],
+            position: 0,
         }
+    }
+
+    fn parse<I>(&mut self, tokens_iter: &mut Peekable<I>) -> Result<Expr>
+    where
+        I: Iterator<Item = TokenizerResult<Spanned<Token>>>,
+    {
+        let (expr, _) = self.parse_group(tokens_iter)?;
 
-        if let State::Start = self.state {
-            return Err(ParserError::EmptyExpr);
+        if !self.open_groups.is_empty() {
+            return Err(self.unbalanced_group());
         }
 
-        Ok(())
+        Ok(expr)
     }
 
-    fn parse_group(
-        &mut self,
-        tokens_iter: &mut impl Iterator<Item = TokenizerResult<Token>>,
-    ) -> Result<()> {
-        let mut operator_stack = vec![];
+    fn parse_group<I>(&mut self, tokens_iter: &mut Peekable<I>) -> Result<(Expr, Terminator)>
+    where
+        I: Iterator<Item = TokenizerResult<Spanned<Token>>>,
+    {
+        let mut operator_stack: Vec<Spanned<Operator>> = vec![];
+        let mut operand_stack = vec![];
+        let mut terminator = Terminator::End;
 
         while let Some(token) = tokens_iter.next() {
-            let token = token?;
+            let Spanned { node: token, span } = token?;
+
+            self.position = span.end;
 
             match self.state {
                 State::Start | State::Operand => match token {
-                    Token::Number(_) => {
-                        self.stack.push(token);
+                    Token::Number(number) => {
+                        operand_stack.push(Expr::new(ExprKind::Number(number), span));
+                        self.state = State::OperatorOrEnd
+                    }
+                    Token::Identifier(name) => {
+                        let operand = self.parse_identifier(name, span, tokens_iter)?;
+
+                        operand_stack.push(operand);
                         self.state = State::OperatorOrEnd
                     }
-                    Token::Operator(ref operator) => {
+                    Token::Operator(operator) => {
                         if operator.arity() > 1 {
                             return Err(ParserError::OperandExpected {
-                                token: Some(token),
-                                operator: operator_stack.pop(),
+                                token: Some(Token::Operator(operator)),
+                                operator: operator_stack.pop().map(Self::operator_token),
+                                span,
                             });
                         }
 
-                        let last_token = operator_stack.last();
-
-                        if let Some(Token::Operator(prev_op)) = last_token {
-                            if operator == prev_op {
+                        if let Some(prev_op) = operator_stack.last() {
+                            if operator == prev_op.node {
                                 return Err(ParserError::OperandExpected {
-                                    token: Some(token),
-                                    operator: operator_stack.pop(),
+                                    token: Some(Token::Operator(operator)),
+                                    operator: operator_stack.pop().map(Self::operator_token),
+                                    span,
                                 });
                             }
                         }
 
-                        operator_stack.push(token);
+                        operator_stack.push(Spanned::new(operator, span));
                         self.state = State::Operand;
                     }
                     Token::Group(Group::Open) => {
-                        self.group_nesting_index += 1;
+                        self.open_groups.push(OpenGroup {
+                            span,
+                            kind: GroupKind::Bracket,
+                        });
                         self.state = State::Start;
-                        self.last_group_token = Some(token);
-                        self.parse_group(tokens_iter)?;
+
+                        let (inner, _) = self.parse_group(tokens_iter)?;
+
+                        operand_stack.push(
+                            Expr::group(inner).with_span(Span::new(span.start, self.position)),
+                        );
                     }
-                    Token::Group(Group::Close) => {
-                        return Err(if operator_stack.is_empty() {
-                            if self.group_nesting_index > 0 {
-                                ParserError::EmptyExpr
-                            } else {
-                                ParserError::UnbalancedGroup(Some(token))
-                            }
-                        } else {
-                            ParserError::OperandExpected {
+                    Token::Group(Group::Close)
+                    | Token::Separator
+                    | Token::Branch(_)
+                    | Token::Unit(_) => {
+                        return Err(match operator_stack.pop() {
+                            None => match self.open_groups.last() {
+                                _ if matches!(token, Token::Branch(_) | Token::Unit(_)) => {
+                                    ParserError::OperandExpected {
+                                        token: Some(token),
+                                        operator: None,
+                                        span,
+                                    }
+                                }
+                                Some(open) => ParserError::EmptyExpr {
+                                    span: open.span.join(span),
+                                },
+                                None if token == Token::Separator => {
+                                    ParserError::UnexpectedSeparator { span }
+                                }
+                                None => ParserError::UnbalancedGroup {
+                                    token: Some(token),
+                                    span,
+                                },
+                            },
+                            operator => ParserError::OperandExpected {
                                 token: Some(token),
-                                operator: operator_stack.pop(),
-                            }
+                                operator: operator.map(Self::operator_token),
+                                span,
+                            },
                         })
                     }
                 },
                 State::OperatorOrEnd => match token {
-                    Token::Number(_) | Token::Group(Group::Open) => {
-                        return Err(ParserError::OperatorExpected(Some(token)))
+                    Token::Number(_) | Token::Identifier(_) | Token::Group(Group::Open) => {
+                        return Err(ParserError::OperatorExpected {
+                            token: Some(token),
+                            span,
+                        })
+                    }
+                    Token::Operator(operator) if operator.arity() < 2 => {
+                        return Err(ParserError::OperatorExpected {
+                            token: Some(Token::Operator(operator)),
+                            span,
+                        })
                     }
-                    Token::Operator(ref operator) => {
+                    // unit belongs to the last operand alone, so it binds tighter than any operator
+                    Token::Unit(unit) => {
+                        let operand = operand_stack.pop().expect("state guarantees operand");
+                        let span = operand.span.join(span);
+
+                        operand_stack.push(Expr::unit(operand, unit).with_span(span));
+                    }
+                    Token::Operator(operator) => {
                         while let Some(prev_op) = operator_stack.last() {
-                            if let Token::Operator(prev_op) = prev_op {
-                                if operator.cmp(prev_op) == Ordering::Less {
-                                    break;
-                                }
+                            if !self.config.binds_before(&prev_op.node, &operator) {
+                                break;
                             }
 
-                            self.stack
-                                .push(operator_stack.pop().expect("prev_op is Some"));
+                            let prev_op = operator_stack.pop().expect("prev_op is Some");
+
+                            Self::reduce(&mut operand_stack, prev_op);
                         }
 
-                        operator_stack.push(token);
+                        operator_stack.push(Spanned::new(operator, span));
                         self.state = State::Operand;
                     }
+                    Token::Group(Group::Close) | Token::Separator
+                        if self.in_group(GroupKind::Then) =>
+                    {
+                        return Err(ParserError::ElseExpected {
+                            token: Some(token),
+                            span,
+                        })
+                    }
                     Token::Group(Group::Close) => {
-                        if self.group_nesting_index == 0 {
-                            return Err(ParserError::UnbalancedGroup(Some(token)));
+                        if self.open_groups.pop().is_none() {
+                            return Err(ParserError::UnbalancedGroup {
+                                token: Some(token),
+                                span,
+                            });
                         }
 
-                        self.group_nesting_index -= 1;
-                        self.state = State::OperatorOrEnd;
+                        terminator = Terminator::Close;
+                        break;
+                    }
+                    Token::Separator => {
+                        if !self.in_group(GroupKind::Call) {
+                            return Err(ParserError::UnexpectedSeparator { span });
+                        }
+
+                        terminator = Terminator::Separator;
+                        break;
+                    }
+                    Token::Branch(Branch::Then) => {
+                        // conditional binds the loosest, so the whole group so far is its condition
+                        while let Some(operator) = operator_stack.pop() {
+                            Self::reduce(&mut operand_stack, operator);
+                        }
+
+                        let condition = operand_stack.pop().expect("state guarantees operand");
+
+                        let (conditional, end) =
+                            self.parse_branches(condition, span, tokens_iter)?;
+
+                        operand_stack.push(conditional);
+                        terminator = end;
+                        break;
+                    }
+                    Token::Branch(Branch::Else) => {
+                        if !self.in_group(GroupKind::Then) {
+                            return Err(ParserError::UnexpectedToken { token, span });
+                        }
+
+                        self.open_groups.pop();
+
+                        terminator = Terminator::Else;
                         break;
                     }
                 },
             }
         }
 
-        if let State::Operand = self.state {
-            return Err(ParserError::OperandExpected {
-                token: None,
-                operator: operator_stack.pop(),
-            });
+        match self.state {
+            State::Operand => {
+                return Err(ParserError::OperandExpected {
+                    token: None,
+                    operator: operator_stack.pop().map(Self::operator_token),
+                    span: Span::point(self.position),
+                })
+            }
+            State::Start if !self.open_groups.is_empty() => return Err(self.unbalanced_group()),
+            State::Start => {
+                return Err(ParserError::EmptyExpr {
+                    span: Span::point(self.position),
+                })
+            }
+            State::OperatorOrEnd
+                if terminator == Terminator::End && self.in_group(GroupKind::Then) =>
+            {
+                return Err(ParserError::ElseExpected {
+                    token: None,
+                    span: Span::point(self.position),
+                })
+            }
+            State::OperatorOrEnd => {}
         }
 
         while let Some(operator) = operator_stack.pop() {
-            self.stack.push(operator);
+            Self::reduce(&mut operand_stack, operator);
         }
 
-        Ok(())
+        let expr = operand_stack
+            .pop()
+            .expect("state guarantees single operand");
+
+        debug_assert!(operand_stack.is_empty());
+
+        Ok((expr, terminator))
+    }
+
+    /// Parses both branches of the conditional after its `?`.
+    /// Else branch lasts until the end of the enclosing group,
+    /// so its terminator finishes the enclosing group as well
+    fn parse_branches<I>(
+        &mut self,
+        condition: Expr,
+        span: Span,
+        tokens_iter: &mut Peekable<I>,
+    ) -> Result<(Expr, Terminator)>
+    where
+        I: Iterator<Item = TokenizerResult<Spanned<Token>>>,
+    {
+        self.open_groups.push(OpenGroup {
+            span,
+            kind: GroupKind::Then,
+        });
+        self.state = State::Operand;
+
+        let (then, _) = self.parse_group(tokens_iter)?;
+
+        self.state = State::Operand;
+
+        let (otherwise, terminator) = self.parse_group(tokens_iter)?;
+
+        Ok((Expr::conditional(condition, then, otherwise), terminator))
+    }
+
+    fn in_group(&self, kind: GroupKind) -> bool {
+        self.open_groups
+            .last()
+            .is_some_and(|group| group.kind == kind)
+    }
+
+    /// Parses the name either as the variable or, when it is followed by brackets,
+    /// as the function call with comma separated arguments
+    fn parse_identifier<I>(
+        &mut self,
+        name: String,
+        name_span: Span,
+        tokens_iter: &mut Peekable<I>,
+    ) -> Result<Expr>
+    where
+        I: Iterator<Item = TokenizerResult<Spanned<Token>>>,
+    {
+        let open = match tokens_iter.peek() {
+            Some(Ok(Spanned {
+                node: Token::Group(Group::Open),
+                span,
+            })) => *span,
+            _ => return Ok(Expr::new(ExprKind::Variable(name), name_span)),
+        };
+
+        tokens_iter.next();
+
+        self.position = open.end;
+        self.open_groups.push(OpenGroup {
+            span: open,
+            kind: GroupKind::Call,
+        });
+
+        let mut args = vec![];
+
+        if let Some(Ok(Spanned {
+            node: Token::Group(Group::Close),
+            span,
+        })) = tokens_iter.peek()
+        {
+            self.position = span.end;
+            self.open_groups.pop();
+            tokens_iter.next();
+        } else {
+            loop {
+                self.state = State::Start;
+
+                let (arg, terminator) = self.parse_group(tokens_iter)?;
+
+                args.push(arg);
+
+                if terminator != Terminator::Separator {
+                    break;
+                }
+            }
+        }
+
+        Ok(Expr::new(
+            ExprKind::Call { name, args },
+            Span::new(name_span.start, self.position),
+        ))
+    }
+
+    /// Error pointing to the innermost group that is not closed
+    fn unbalanced_group(&self) -> ParserError {
+        ParserError::UnbalancedGroup {
+            token: Some(Token::Group(Group::Open)),
+            span: self
+                .open_groups
+                .last()
+                .map_or(Span::point(self.position), |group| group.span),
+        }
+    }
+
+    fn operator_token(operator: Spanned<Operator>) -> Token {
+        Token::Operator(operator.node)
+    }
+
+    /// Replaces the top operands on the stack with the node of the operator applied to them.
+    /// Parser state guarantees that the stack has enough operands for the operator
+    fn reduce(operand_stack: &mut Vec<Expr>, operator: Spanned<Operator>) {
+        let Spanned {
+            node: operator,
+            span,
+        } = operator;
+
+        let right = operand_stack.pop().expect("operand for operator");
+
+        let expr = if operator.arity() > 1 {
+            let left = operand_stack.pop().expect("left operand for operator");
+
+            Expr::binary(operator, left, right)
+        } else {
+            let span = span.join(right.span);
+
+            Expr::unary(operator, right).with_span(span)
+        };
+
+        operand_stack.push(expr);
+    }
+}
+
+/// Binding power of the operator, operators with higher value bind tighter.
+/// Comparisons and logic have negative values, so they bind looser than the arithmetic
+pub type Precedence = i8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    /// `1 - 2 - 3` is parsed as `(1 - 2) - 3`
+    Left,
+    /// `1 - 2 - 3` is parsed as `1 - (2 - 3)`
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperatorPrecedence {
+    pub precedence: Precedence,
+    pub associativity: Associativity,
+}
+
+impl OperatorPrecedence {
+    pub fn left(precedence: Precedence) -> Self {
+        Self {
+            precedence,
+            associativity: Associativity::Left,
+        }
+    }
+
+    pub fn right(precedence: Precedence) -> Self {
+        Self {
+            precedence,
+            associativity: Associativity::Right,
+        }
+    }
+}
+
+/// Settings that control how the source is turned into [Expr]
+#[derive(Debug, Clone)]
+pub struct ParserConfig {
+    operators: HashMap<Operator, OperatorPrecedence>,
+    dialect: Dialect,
+}
+
+impl ParserConfig {
+    /// Binary operators except power have the same priority and are applied
+    /// strictly from left to right, i.e. `2 + 3 * 4 = 20`.
+    /// Power binds the tightest and is right associative, i.e. `2 ^ 3 ^ 2 = 512` and `-2 ^ 2 = -4`,
+    /// while negation binds tighter than other binary operators, i.e. `-2 * 3 = -6`.
+    /// Comparisons and logic bind looser in both configs, see [ParserConfig::with_conditions]
+    pub fn flat() -> Self {
+        Self::with_conditions([
+            (Operator::Neg, OperatorPrecedence::right(2)),
+            (Operator::Add, OperatorPrecedence::left(1)),
+            (Operator::Sub, OperatorPrecedence::left(1)),
+            (Operator::Mul, OperatorPrecedence::left(1)),
+            (Operator::Div, OperatorPrecedence::left(1)),
+            (Operator::Mod, OperatorPrecedence::left(1)),
+            (Operator::Pow, OperatorPrecedence::right(3)),
+        ])
+    }
+
+    /// Conventional math precedence where multiplication, division and remainder
+    /// are applied before addition and subtraction, i.e. `2 + 3 * 4 = 14`.
+    /// Power binds the tightest and is right associative, i.e. `2 ^ 3 ^ 2 = 512`,
+    /// and is applied before negation, i.e. `-2 ^ 2 = -4`
+    pub fn standard() -> Self {
+        Self::with_conditions([
+            (Operator::Neg, OperatorPrecedence::right(3)),
+            (Operator::Add, OperatorPrecedence::left(1)),
+            (Operator::Sub, OperatorPrecedence::left(1)),
+            (Operator::Mul, OperatorPrecedence::left(2)),
+            (Operator::Div, OperatorPrecedence::left(2)),
+            (Operator::Mod, OperatorPrecedence::left(2)),
+            (Operator::Pow, OperatorPrecedence::right(4)),
+        ])
+    }
+
+    /// Extends the arithmetic table with comparisons applied before the negation of booleans,
+    /// which is applied before `&&` and then `||`, i.e. `!a < b || c == d && e`
+    /// is `(!(a < b)) || ((c == d) && e)`. Conditional binds the loosest of all
+    fn with_conditions(
+        arithmetic: impl IntoIterator<Item = (Operator, OperatorPrecedence)>,
+    ) -> Self {
+        let conditions = [
+            (Operator::Eq, OperatorPrecedence::left(-1)),
+            (Operator::Ne, OperatorPrecedence::left(-1)),
+            (Operator::Lt, OperatorPrecedence::left(-1)),
+            (Operator::Le, OperatorPrecedence::left(-1)),
+            (Operator::Gt, OperatorPrecedence::left(-1)),
+            (Operator::Ge, OperatorPrecedence::left(-1)),
+            (Operator::Not, OperatorPrecedence::right(-2)),
+            (Operator::And, OperatorPrecedence::left(-3)),
+            (Operator::Or, OperatorPrecedence::left(-4)),
+        ];
+
+        Self {
+            operators: arithmetic.into_iter().chain(conditions).collect(),
+            dialect: Dialect::letters(),
+        }
+    }
+
+    /// Replaces the alphabet used to read the source
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn dialect(&self) -> &Dialect {
+        &self.dialect
+    }
+
+    /// Overrides precedence and associativity of the single operator
+    pub fn with_operator(
+        mut self,
+        operator: Operator,
+        precedence: Precedence,
+        associativity: Associativity,
+    ) -> Self {
+        self.operators.insert(
+            operator,
+            OperatorPrecedence {
+                precedence,
+                associativity,
+            },
+        );
+        self
+    }
+
+    /// Operators missing in the table have the lowest precedence
+    pub fn precedence(&self, operator: &Operator) -> OperatorPrecedence {
+        self.operators
+            .get(operator)
+            .copied()
+            .unwrap_or(OperatorPrecedence::left(Precedence::MIN))
+    }
+
+    /// Checks whether the operator that is already on the stack
+    /// should be applied before the incoming binary operator
+    pub(crate) fn binds_before(&self, prev: &Operator, next: &Operator) -> bool {
+        let prev = self.precedence(prev);
+        let next = self.precedence(next);
+
+        prev.precedence > next.precedence
+            || (prev.precedence == next.precedence && next.associativity == Associativity::Left)
+    }
+}
+
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self::flat()
     }
 }
 
 #[derive(Default)]
 pub struct ExprParser {
-    inner_parser: ExprAst,
+    config: ParserConfig,
 }
 
 impl ExprParser {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(config: ParserConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn config(&self) -> &ParserConfig {
+        &self.config
     }
 
     pub fn parse(
-        &mut self,
-        tokens_iter: &mut impl Iterator<Item = TokenizerResult<Token>>,
-    ) -> Result<impl Iterator<Item = &Token>> {
-        self.inner_parser.parse(tokens_iter)?;
+        &self,
+        tokens_iter: &mut impl Iterator<Item = TokenizerResult<Spanned<Token>>>,
+    ) -> Result<Expr> {
+        ParserState::new(&self.config).parse(&mut tokens_iter.peekable())
+    }
+
+    /// Tokenizes the source with the configured dialect and parses it
+    pub fn parse_str(&self, source: &str) -> Result<Expr> {
+        self.parse(&mut TokenIterator::with_dialect(
+            source,
+            self.config.dialect(),
+        ))
+    }
 
-        Ok(self.inner_parser.into_iter())
+    /// Tokenizes the source with the configured dialect as it is read and parses it
+    pub fn parse_reader(&self, reader: impl Read) -> Result<Expr> {
+        self.parse(&mut TokenReader::with_dialect(
+            reader,
+            self.config.dialect(),
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tokens::{Number, Operator, TokenIterator};
 
     macro_rules! assert_parse {
-                ($expr:literal, $( $token:expr ),* $(,)?) => {
-                    let mut parser = ExprParser::new();
-                    let parsed = parser.parse(&mut TokenIterator::from($expr)).unwrap();
-                    assert_eq!(parsed.collect::<Vec<_>>(), vec![$(&$token, )*])
-                };
-            }
+        ($expr:literal, $tree:expr) => {
+            let parser = ExprParser::default();
+            let parsed = parser.parse(&mut TokenIterator::from($expr)).unwrap();
+            assert_eq!(parsed, $tree)
+        };
+    }
 
     macro_rules! assert_parse_error {
         ($expr: literal, $err: expr) => {
-            let mut parser = ExprParser::new();
+            let parser = ExprParser::default();
             let err = parser
                 .parse(&mut TokenIterator::from($expr))
                 .map(|_| ())
@@ -240,130 +965,280 @@ mod tests {
         };
     }
 
+    fn int(num: i32) -> Expr {
+        Expr::number(Number::Int(num.into()))
+    }
+
     #[test]
     fn unit_expr() {
-        assert_parse!("2", Token::Number(Number::Int(2)));
-        assert_parse!("3.7", Token::Number(Number::Float(3.7)));
+        assert_parse!("2", int(2));
+        assert_parse!("3.7", Expr::number(Number::Float(3.7)));
     }
 
     #[test]
     fn simple_expr() {
-        assert_parse!(
-            "2 a  3",
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(3)),
-            Token::Number(Number::Int(2))
-        );
+        assert_parse!("2 a  3", Expr::binary(Operator::Add, int(2), int(3)));
     }
 
     #[test]
     fn priority_expr() {
         assert_parse!(
             "2 a 2 b 3",
-            Token::Operator(Operator::Sub),
-            Token::Number(Number::Int(3)),
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Int(2))
+            Expr::binary(
+                Operator::Sub,
+                Expr::binary(Operator::Add, int(2), int(2)),
+                int(3)
+            )
         );
 
         assert_parse!(
             "2 a 2 c 3",
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Int(3)),
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Int(2))
+            Expr::binary(
+                Operator::Mul,
+                Expr::binary(Operator::Add, int(2), int(2)),
+                int(3)
+            )
         );
 
         assert_parse!(
             "2 c 2 a 3",
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(3)),
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Int(2))
+            Expr::binary(
+                Operator::Add,
+                Expr::binary(Operator::Mul, int(2), int(2)),
+                int(3)
+            )
         );
 
         assert_parse!(
             "2 a 2 c 3 c b2",
-            Token::Operator(Operator::Mul),
-            Token::Operator(Operator::Neg),
-            Token::Number(Number::Int(2)),
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Int(3)),
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(2)),
-            Token::Number(Number::Int(2)),
+            Expr::binary(
+                Operator::Mul,
+                Expr::binary(
+                    Operator::Mul,
+                    Expr::binary(Operator::Add, int(2), int(2)),
+                    int(3)
+                ),
+                Expr::unary(Operator::Neg, int(2))
+            )
+        );
+    }
+
+    #[test]
+    fn standard_precedence() {
+        let parser = ExprParser::new(ParserConfig::standard());
+        let parsed = parser
+            .parse(&mut TokenIterator::from("2 a 2 c 3 c b2 b 1"))
+            .unwrap();
+
+        assert_eq!(
+            parsed,
+            Expr::binary(
+                Operator::Sub,
+                Expr::binary(
+                    Operator::Add,
+                    int(2),
+                    Expr::binary(
+                        Operator::Mul,
+                        Expr::binary(Operator::Mul, int(2), int(3)),
+                        Expr::unary(Operator::Neg, int(2))
+                    )
+                ),
+                int(1)
+            )
+        );
+
+        let parser = ExprParser::new(ParserConfig::standard().with_operator(
+            Operator::Div,
+            2,
+            Associativity::Right,
+        ));
+        let parsed = parser.parse(&mut TokenIterator::from("8 d 4 d 2")).unwrap();
+
+        assert_eq!(
+            parsed,
+            Expr::binary(
+                Operator::Div,
+                int(8),
+                Expr::binary(Operator::Div, int(4), int(2))
+            )
+        );
+    }
+
+    #[test]
+    fn power_associativity() {
+        let parser = ExprParser::new(ParserConfig::standard());
+
+        assert_eq!(
+            parser.parse_str("2 g 3 g 2").unwrap(),
+            Expr::binary(
+                Operator::Pow,
+                int(2),
+                Expr::binary(Operator::Pow, int(3), int(2))
+            )
+        );
+        assert_eq!(
+            parser.parse_str("b2 g 2").unwrap(),
+            Expr::unary(Operator::Neg, Expr::binary(Operator::Pow, int(2), int(2)))
+        );
+        assert_eq!(
+            parser.parse_str("2 g b2 c 3").unwrap(),
+            Expr::binary(
+                Operator::Mul,
+                Expr::binary(Operator::Pow, int(2), Expr::unary(Operator::Neg, int(2))),
+                int(3)
+            )
+        );
+
+        assert_parse!(
+            "b2 g 2",
+            Expr::unary(Operator::Neg, Expr::binary(Operator::Pow, int(2), int(2)))
+        );
+        assert_parse!(
+            "2 g 3 g 2",
+            Expr::binary(
+                Operator::Pow,
+                int(2),
+                Expr::binary(Operator::Pow, int(3), int(2))
+            )
         );
     }
 
     #[test]
     fn grouping() {
-        assert_parse!("e1f", Token::Number(Number::Int(1)));
-        assert_parse!("eee1fff", Token::Number(Number::Int(1)));
+        assert_parse!("e1f", Expr::group(int(1)));
+        assert_parse!("eee1fff", Expr::group(Expr::group(Expr::group(int(1)))));
         assert_parse!(
             "bebe1ff",
-            Token::Operator(Operator::Neg),
-            Token::Operator(Operator::Neg),
-            Token::Number(Number::Int(1)),
+            Expr::unary(
+                Operator::Neg,
+                Expr::group(Expr::unary(Operator::Neg, Expr::group(int(1))))
+            )
         );
 
         assert_parse!(
             "2 c 3 a e2 a 3 f c 5.1", // 2 * 3 + ( 2 + 3 ) * 5.1
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Float(5.1)),
-            Token::Operator(Operator::Add),
-            Token::Operator(Operator::Add),
-            Token::Number(Number::Int(3)),
-            Token::Number(Number::Int(2)),
-            Token::Operator(Operator::Mul),
-            Token::Number(Number::Int(3)),
-            Token::Number(Number::Int(2)),
+            Expr::binary(
+                Operator::Mul,
+                Expr::binary(
+                    Operator::Add,
+                    Expr::binary(Operator::Mul, int(2), int(3)),
+                    Expr::group(Expr::binary(Operator::Add, int(2), int(3)))
+                ),
+                Expr::number(Number::Float(5.1))
+            )
         );
     }
 
+    #[test]
+    fn expr_spans() {
+        let parser = ExprParser::default();
+        let parsed = parser
+            .parse(&mut TokenIterator::from(" 2 a eb3f "))
+            .unwrap();
+
+        assert_eq!(parsed.span, Span::new(1, 9));
+
+        let ExprKind::Binary { left, right, .. } = parsed.kind else {
+            panic!("expected binary expression");
+        };
+
+        assert_eq!(left.span, Span::new(1, 2));
+        assert_eq!(right.span, Span::new(5, 9));
+
+        let ExprKind::Group(inner) = right.kind else {
+            panic!("expected group");
+        };
+
+        assert_eq!(inner.span, Span::new(6, 8));
+    }
+
+    #[test]
+    fn parser_is_reusable() {
+        let parser = ExprParser::default();
+
+        assert_eq!(
+            parser.parse(&mut TokenIterator::from("e1")).unwrap_err(),
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Open)),
+                span: Span::new(0, 1)
+            }
+        );
+        assert_eq!(parser.parse(&mut TokenIterator::from("1")).unwrap(), int(1));
+    }
+
     #[test]
     fn unbalanced_brackets() {
         assert_parse_error!(
             "f",
-            ParserError::UnbalancedGroup(Some(Token::Group(Group::Close)))
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Close)),
+                span: Span::new(0, 1)
+            }
         );
         assert_parse_error!(
             "5f",
-            ParserError::UnbalancedGroup(Some(Token::Group(Group::Close)))
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Close)),
+                span: Span::new(1, 2)
+            }
         );
         assert_parse_error!(
             "e",
-            ParserError::UnbalancedGroup(Some(Token::Group(Group::Open)))
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Open)),
+                span: Span::new(0, 1)
+            }
         );
         assert_parse_error!(
             "e 3",
-            ParserError::UnbalancedGroup(Some(Token::Group(Group::Open)))
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Open)),
+                span: Span::new(0, 1)
+            }
         );
         assert_parse_error!(
             "1 a 2 c e3 b 2 f a f",
             ParserError::OperandExpected {
                 token: Some(Token::Group(Group::Close)),
-                operator: Some(Token::Operator(Operator::Add))
+                operator: Some(Token::Operator(Operator::Add)),
+                span: Span::new(19, 20)
             }
         );
         assert_parse_error!(
             "1 a 2 c e3 b 2 f c e",
-            ParserError::UnbalancedGroup(Some(Token::Group(Group::Open)))
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Open)),
+                span: Span::new(19, 20)
+            }
         );
         assert_parse_error!(
             "e 3 a e2 a 2f",
-            ParserError::UnbalancedGroup(Some(Token::Group(Group::Open)))
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Open)),
+                span: Span::new(0, 1)
+            }
         );
         assert_parse_error!(
             "e 3 a e2 a 2f f f",
-            ParserError::UnbalancedGroup(Some(Token::Group(Group::Close)))
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Close)),
+                span: Span::new(16, 17)
+            }
         );
 
-        assert_parse_error!("", ParserError::EmptyExpr);
+        assert_parse_error!(
+            "",
+            ParserError::EmptyExpr {
+                span: Span::point(0)
+            }
+        );
 
-        assert_parse_error!("ef", ParserError::EmptyExpr);
+        assert_parse_error!(
+            "ef",
+            ParserError::EmptyExpr {
+                span: Span::new(0, 2)
+            }
+        );
     }
 
     #[test]
@@ -372,70 +1247,215 @@ mod tests {
             "a",
             ParserError::OperandExpected {
                 token: Some(Token::Operator(Operator::Add)),
-                operator: None
+                operator: None,
+                span: Span::new(0, 1)
             }
         );
         assert_parse_error!(
             "c 3 b 2",
             ParserError::OperandExpected {
                 token: Some(Token::Operator(Operator::Mul)),
-                operator: None
+                operator: None,
+                span: Span::new(0, 1)
             }
         );
         assert_parse_error!(
             "2 b",
             ParserError::OperandExpected {
                 token: None,
-                operator: Some(Token::Operator(Operator::Sub))
+                operator: Some(Token::Operator(Operator::Sub)),
+                span: Span::point(3)
             }
         );
         assert_parse_error!(
             "2 a e3 b 2f c",
             ParserError::OperandExpected {
                 token: None,
-                operator: Some(Token::Operator(Operator::Mul))
+                operator: Some(Token::Operator(Operator::Mul)),
+                span: Span::point(13)
             }
         );
         assert_parse_error!(
             "2 c d 2",
             ParserError::OperandExpected {
                 token: Some(Token::Operator(Operator::Div)),
-                operator: Some(Token::Operator(Operator::Mul))
+                operator: Some(Token::Operator(Operator::Mul)),
+                span: Span::new(4, 5)
             }
         );
         assert_parse_error!(
             "b ",
             ParserError::OperandExpected {
                 token: None,
-                operator: Some(Token::Operator(Operator::Neg))
+                operator: Some(Token::Operator(Operator::Neg)),
+                span: Span::point(1)
             }
         );
         assert_parse_error!(
             " 2 a b",
             ParserError::OperandExpected {
                 token: None,
-                operator: Some(Token::Operator(Operator::Neg))
+                operator: Some(Token::Operator(Operator::Neg)),
+                span: Span::point(6)
             }
         );
         assert_parse_error!(
             " 2 a ebf",
             ParserError::OperandExpected {
                 token: Some(Token::Group(Group::Close)),
-                operator: Some(Token::Operator(Operator::Neg))
+                operator: Some(Token::Operator(Operator::Neg)),
+                span: Span::new(7, 8)
             }
         );
         assert_parse_error!(
             "b b 2",
             ParserError::OperandExpected {
                 token: Some(Token::Operator(Operator::Sub)),
-                operator: Some(Token::Operator(Operator::Neg))
+                operator: Some(Token::Operator(Operator::Neg)),
+                span: Span::new(2, 3)
             }
         );
         assert_parse_error!(
             "2 c b b 2",
             ParserError::OperandExpected {
                 token: Some(Token::Operator(Operator::Sub)),
-                operator: Some(Token::Operator(Operator::Neg))
+                operator: Some(Token::Operator(Operator::Neg)),
+                span: Span::new(6, 7)
+            }
+        );
+    }
+
+    #[test]
+    fn unary_operator_in_binary_position() {
+        let parser = ExprParser::new(
+            ParserConfig::default()
+                .with_dialect(Dialect::standard().with_symbol("~", Operator::Neg).unwrap()),
+        );
+
+        assert_eq!(
+            parser.parse_str("2 ~ 3").unwrap_err(),
+            ParserError::OperatorExpected {
+                token: Some(Token::Operator(Operator::Neg)),
+                span: Span::new(2, 3)
+            }
+        );
+        assert_eq!(
+            parser.parse_str("2 - ~3").unwrap(),
+            Expr::binary(Operator::Sub, int(2), Expr::unary(Operator::Neg, int(3)))
+        );
+    }
+
+    #[test]
+    fn function_calls() {
+        assert_parse!("$pi e f", Expr::call("pi", vec![]));
+        assert_parse!(
+            "$max e1, 2 c 3, $min e4ff",
+            Expr::call(
+                "max",
+                vec![
+                    int(1),
+                    Expr::binary(Operator::Mul, int(2), int(3)),
+                    Expr::call("min", vec![int(4)])
+                ]
+            )
+        );
+        assert_parse!(
+            "b$sqrt eb2 a e3ff c 2",
+            Expr::binary(
+                Operator::Mul,
+                Expr::unary(
+                    Operator::Neg,
+                    Expr::call(
+                        "sqrt",
+                        vec![Expr::binary(
+                            Operator::Add,
+                            Expr::unary(Operator::Neg, int(2)),
+                            Expr::group(int(3))
+                        )]
+                    )
+                ),
+                int(2)
+            )
+        );
+
+        let parser = ExprParser::default();
+        let parsed = parser.parse_str("1 a $abs eb2f").unwrap();
+        let ExprKind::Binary { right, .. } = parsed.kind else {
+            panic!("expected binary expression");
+        };
+
+        assert_eq!(right.span, Span::new(4, 13));
+    }
+
+    #[test]
+    fn variables() {
+        assert_parse!("$x", Expr::variable("x"));
+        assert_parse!(
+            "$price c e1 a $tax_rate f b $f e$x f",
+            Expr::binary(
+                Operator::Sub,
+                Expr::binary(
+                    Operator::Mul,
+                    Expr::variable("price"),
+                    Expr::group(Expr::binary(
+                        Operator::Add,
+                        int(1),
+                        Expr::variable("tax_rate")
+                    ))
+                ),
+                Expr::call("f", vec![Expr::variable("x")])
+            )
+        );
+
+        assert_parse_error!(
+            "$sqrt 2",
+            ParserError::OperatorExpected {
+                token: Some(Token::Number(Number::Int(2.into()))),
+                span: Span::new(6, 7)
+            }
+        );
+    }
+
+    #[test]
+    fn function_call_errors() {
+        assert_parse_error!(
+            "$max e1,f",
+            ParserError::EmptyExpr {
+                span: Span::new(5, 9)
+            }
+        );
+        assert_parse_error!(
+            "$max e1 a, 2f",
+            ParserError::OperandExpected {
+                token: Some(Token::Separator),
+                operator: Some(Token::Operator(Operator::Add)),
+                span: Span::new(9, 10)
+            }
+        );
+        assert_parse_error!(
+            "$max e1, 2",
+            ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Open)),
+                span: Span::new(5, 6)
+            }
+        );
+        assert_parse_error!(
+            "1, 2",
+            ParserError::UnexpectedSeparator {
+                span: Span::new(1, 2)
+            }
+        );
+        assert_parse_error!(
+            "$max e1 a e2, 3ff",
+            ParserError::UnexpectedSeparator {
+                span: Span::new(12, 13)
+            }
+        );
+        assert_parse_error!(
+            "2 $abs e1f",
+            ParserError::OperatorExpected {
+                token: Some(Token::Identifier("abs".to_string())),
+                span: Span::new(2, 6)
             }
         );
     }
@@ -444,11 +1464,194 @@ mod tests {
     fn missing_operator() {
         assert_parse_error!(
             "2 3",
-            ParserError::OperatorExpected(Some(Token::Number(Number::Int(3))))
+            ParserError::OperatorExpected {
+                token: Some(Token::Number(Number::Int(3.into()))),
+                span: Span::new(2, 3)
+            }
         );
         assert_parse_error!(
             "e2 a3 f e5c6f",
-            ParserError::OperatorExpected(Some(Token::Group(Group::Open)))
+            ParserError::OperatorExpected {
+                token: Some(Token::Group(Group::Open)),
+                span: Span::new(8, 9)
+            }
+        );
+    }
+
+    #[test]
+    fn conditionals() {
+        let var = Expr::variable;
+
+        assert_parse!(
+            "$a < 1 || !$b >= 2 && $c == 3",
+            Expr::binary(
+                Operator::Or,
+                Expr::binary(Operator::Lt, var("a"), int(1)),
+                Expr::binary(
+                    Operator::And,
+                    Expr::unary(Operator::Not, Expr::binary(Operator::Ge, var("b"), int(2))),
+                    Expr::binary(Operator::Eq, var("c"), int(3))
+                )
+            )
+        );
+        assert_parse!(
+            "$a a 1 > 2 ? 3 : $b ? 4 : 5",
+            Expr::conditional(
+                Expr::binary(
+                    Operator::Gt,
+                    Expr::binary(Operator::Add, var("a"), int(1)),
+                    int(2)
+                ),
+                int(3),
+                Expr::conditional(var("b"), int(4), int(5))
+            )
+        );
+        assert_parse!(
+            "$a ? $b ? 1 : 2 : 3",
+            Expr::conditional(
+                var("a"),
+                Expr::conditional(var("b"), int(1), int(2)),
+                int(3)
+            )
+        );
+        assert_parse!(
+            "$max e$a ? 1 : 2, e$b ? 3 : 4f c 5f",
+            Expr::call(
+                "max",
+                vec![
+                    Expr::conditional(var("a"), int(1), int(2)),
+                    Expr::binary(
+                        Operator::Mul,
+                        Expr::group(Expr::conditional(var("b"), int(3), int(4))),
+                        int(5)
+                    )
+                ]
+            )
+        );
+
+        let parsed = ExprParser::default().parse_str("1 a e$a ? 1 : 2f").unwrap();
+        let ExprKind::Binary { right, .. } = parsed.kind else {
+            panic!("expected binary expression");
+        };
+        let ExprKind::Group(inner) = right.kind else {
+            panic!("expected group");
+        };
+
+        assert_eq!(right.span, Span::new(4, 16));
+        assert_eq!(inner.span, Span::new(5, 15));
+    }
+
+    #[test]
+    fn conditional_errors() {
+        assert_parse_error!(
+            "$a ? 1",
+            ParserError::ElseExpected {
+                token: None,
+                span: Span::point(6)
+            }
+        );
+        assert_parse_error!(
+            "e$a ? 1f",
+            ParserError::ElseExpected {
+                token: Some(Token::Group(Group::Close)),
+                span: Span::new(7, 8)
+            }
+        );
+        assert_parse_error!(
+            "$max e$a ? 1, 2f",
+            ParserError::ElseExpected {
+                token: Some(Token::Separator),
+                span: Span::new(12, 13)
+            }
+        );
+        assert_parse_error!(
+            "$a ? 1 ? 2 : 3",
+            ParserError::ElseExpected {
+                token: None,
+                span: Span::point(14)
+            }
+        );
+        assert_parse_error!(
+            "1 : 2",
+            ParserError::UnexpectedToken {
+                token: Token::Branch(Branch::Else),
+                span: Span::new(2, 3)
+            }
+        );
+        assert_parse_error!(
+            "$a ? : 1",
+            ParserError::OperandExpected {
+                token: Some(Token::Branch(Branch::Else)),
+                operator: None,
+                span: Span::new(5, 6)
+            }
+        );
+        assert_parse_error!(
+            "1 a ? 2 : 3",
+            ParserError::OperandExpected {
+                token: Some(Token::Branch(Branch::Then)),
+                operator: Some(Token::Operator(Operator::Add)),
+                span: Span::new(4, 5)
+            }
+        );
+    }
+
+    #[test]
+    fn units() {
+        let var = Expr::variable;
+
+        assert_parse!(
+            "b2[kg] a $price[ USD/oz ] c 3[oz][g]",
+            Expr::binary(
+                Operator::Mul,
+                Expr::binary(
+                    Operator::Add,
+                    Expr::unary(Operator::Neg, Expr::unit(int(2), "kg")),
+                    Expr::unit(var("price"), "USD/oz")
+                ),
+                Expr::unit(Expr::unit(int(3), "oz"), "g")
+            )
+        );
+        assert_parse!(
+            "e1 a 2f[m] g 2",
+            Expr::binary(
+                Operator::Pow,
+                Expr::unit(
+                    Expr::group(Expr::binary(Operator::Add, int(1), int(2))),
+                    "m"
+                ),
+                int(2)
+            )
+        );
+
+        let parsed = ExprParser::default().parse_str("1 a $max e2f[g]").unwrap();
+        let ExprKind::Binary { right, .. } = parsed.kind else {
+            panic!("expected binary expression");
+        };
+
+        assert_eq!(right.span, Span::new(4, 15));
+
+        assert_parse_error!(
+            "1 a [kg]",
+            ParserError::OperandExpected {
+                token: Some(Token::Unit("kg".to_owned())),
+                operator: Some(Token::Operator(Operator::Add)),
+                span: Span::new(4, 8)
+            }
+        );
+        assert_parse_error!(
+            "[kg] 1",
+            ParserError::OperandExpected {
+                token: Some(Token::Unit("kg".to_owned())),
+                operator: None,
+                span: Span::new(0, 4)
+            }
+        );
+        assert_parse_error!(
+            "1[kg",
+            ParserError::TokenizerError(TokenizerError::UnclosedUnit {
+                span: Span::new(1, 2)
+            })
         );
     }
 }
@@ -0,0 +1,737 @@
+//! Module to render the parsed expression back to the source
+//! in any dialect with the brackets the precedence requires
+
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    dialect::{Dialect, DialectError, Symbol},
+    parse::{Expr, ExprKind, ParserConfig},
+    rpn::NEGATION,
+    tokens::{Branch, Group, Number, Operator, TokenIterator},
+};
+
+/// Which operands are wrapped into brackets
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parentheses {
+    /// Only where the precedence or associativity requires them
+    #[default]
+    Minimal,
+    /// Around every operand that is an operation itself
+    Full,
+}
+
+/// Where the operator is written relative to its operands
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    /// Between the operands, i.e. `1 + 2`
+    #[default]
+    Infix,
+    /// Before the operands, i.e. `+ 1 2`, also known as Polish notation
+    Prefix,
+    /// After the operands, i.e. `1 2 +`, also known as reverse Polish notation
+    Postfix,
+}
+
+/// Position of the operand relative to its operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+    /// Operand of the unary operator
+    Operand,
+}
+
+/// Writes the expression in the dialect of the config, so parsing the output
+/// with the same config gives the same tree once the groups are removed, see [Expr::ungrouped].
+/// Groups of the source are not kept, brackets are placed according to [Parentheses].
+///
+/// Prefix and postfix notations separate all tokens by spaces and write the number
+/// of the arguments in brackets after the function name, i.e. `1 2 3 max(3)`,
+/// the conditional as `?` of three operands, i.e. `c a b ?`, and the unit after its operand.
+/// The postfix output is read back by the [RpnParser](crate::rpn::RpnParser)
+#[derive(Debug, Clone)]
+pub struct Printer {
+    config: ParserConfig,
+    parentheses: Parentheses,
+    notation: Notation,
+}
+
+/// Prints with [ParserConfig::default]
+impl Default for Printer {
+    fn default() -> Self {
+        Self::new(ParserConfig::default()).expect("letters dialect has all symbols")
+    }
+}
+
+impl Printer {
+    /// Creates printer for the config, reports the symbol the dialect has no text for.
+    /// Comparisons, logic and conditional are optional, see [Printer::text]
+    pub fn new(config: ParserConfig) -> Result<Self, DialectError> {
+        let dialect = config.dialect();
+        let symbols = [
+            Operator::Add.into(),
+            Operator::Sub.into(),
+            Operator::Mul.into(),
+            Operator::Div.into(),
+            Operator::Mod.into(),
+            Operator::Pow.into(),
+            Group::Open.into(),
+            Group::Close.into(),
+            Symbol::Separator,
+        ];
+
+        if let Some(symbol) = symbols
+            .into_iter()
+            .find(|symbol| dialect.text(symbol).is_none())
+        {
+            return Err(DialectError::MissingSymbol(symbol));
+        }
+
+        Ok(Self {
+            config,
+            parentheses: Parentheses::default(),
+            notation: Notation::default(),
+        })
+    }
+
+    /// Brackets of the infix notation, the other notations don't need any
+    pub fn with_parentheses(mut self, parentheses: Parentheses) -> Self {
+        self.parentheses = parentheses;
+        self
+    }
+
+    pub fn with_notation(mut self, notation: Notation) -> Self {
+        self.notation = notation;
+        self
+    }
+
+    pub fn print(&self, expr: &Expr) -> String {
+        let expr = normalize(expr);
+        let mut writer = Writer {
+            printer: self,
+            output: String::new(),
+            last: String::new(),
+            space: false,
+            grouped: HashMap::new(),
+        };
+
+        match self.notation {
+            Notation::Infix => writer.write(&expr),
+            notation => writer.write_polish(&expr, notation),
+        }
+
+        writer.output
+    }
+
+    /// Text of the symbol, negation falls back to the operator it shares the symbol with
+    /// or to [NEGATION] in the Polish notations. Symbols of the conditions
+    /// the dialect has no text for fall back to the ones of the [Dialect::letters]
+    fn text(&self, symbol: Symbol) -> &str {
+        let dialect = self.config.dialect();
+
+        match symbol {
+            Symbol::Operator(Operator::Neg) => {
+                dialect
+                    .text(&Operator::Neg.into())
+                    .or_else(|| match self.notation {
+                        Notation::Infix => dialect.text(&Operator::Sub.into()),
+                        _ => Some(NEGATION),
+                    })
+            }
+            symbol => dialect
+                .text(&symbol)
+                .or_else(|| Dialect::default_ref().text(&symbol)),
+        }
+        .expect("symbols are checked by the constructor")
+    }
+
+    /// Text of the unit in the brackets of the dialect or of the [Dialect::letters]
+    fn unit(&self, unit: &str) -> String {
+        let (open, close) = self
+            .config
+            .dialect()
+            .unit_brackets()
+            .or_else(|| Dialect::default_ref().unit_brackets())
+            .expect("letters dialect has unit brackets");
+
+        format!("{open}{unit}{close}")
+    }
+
+    /// Text of the imaginary number with the suffix of the dialect or of the [Dialect::letters]
+    fn imaginary(&self, num: f64) -> String {
+        let suffix = self
+            .config
+            .dialect()
+            .imaginary_suffix()
+            .or_else(|| Dialect::default_ref().imaginary_suffix())
+            .expect("letters dialect has imaginary suffix");
+
+        format!("{num}{suffix}")
+    }
+}
+
+/// Removes groups and turns negative literals into negation, so the tree matches the output
+fn normalize(expr: &Expr) -> Expr {
+    let kind = match &expr.kind {
+        ExprKind::Group(inner) => return normalize(inner),
+        ExprKind::Number(Number::Int(num)) if num.is_negative() => ExprKind::Unary {
+            operator: Operator::Neg,
+            operand: Box::new(Expr::new(
+                ExprKind::Number(Number::Int(-num.clone())),
+                expr.span,
+            )),
+        },
+        ExprKind::Number(Number::Float(num)) if num.is_sign_negative() => ExprKind::Unary {
+            operator: Operator::Neg,
+            operand: Box::new(Expr::new(ExprKind::Number(Number::Float(-num)), expr.span)),
+        },
+        ExprKind::Number(_) | ExprKind::Variable(_) => return expr.clone(),
+        ExprKind::Unary { operator, operand } => ExprKind::Unary {
+            operator: operator.clone(),
+            operand: Box::new(normalize(operand)),
+        },
+        ExprKind::Binary {
+            operator,
+            left,
+            right,
+        } => ExprKind::Binary {
+            operator: operator.clone(),
+            left: Box::new(normalize(left)),
+            right: Box::new(normalize(right)),
+        },
+        ExprKind::Call { name, args } => ExprKind::Call {
+            name: name.clone(),
+            args: args.iter().map(normalize).collect(),
+        },
+        ExprKind::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => ExprKind::Conditional {
+            condition: Box::new(normalize(condition)),
+            then: Box::new(normalize(then)),
+            otherwise: Box::new(normalize(otherwise)),
+        },
+        ExprKind::Unit { operand, unit } => ExprKind::Unit {
+            operand: Box::new(normalize(operand)),
+            unit: unit.clone(),
+        },
+    };
+
+    Expr::new(kind, expr.span)
+}
+
+struct Writer<'printer> {
+    printer: &'printer Printer,
+    output: String,
+    /// Text of the last token to check whether the next one would merge with it
+    last: String,
+    /// Next token is separated by the space
+    space: bool,
+    /// Whether the operand is wrapped into brackets, cached as the checks
+    /// of the outer operators visit the same operands again
+    grouped: HashMap<*const Expr, bool>,
+}
+
+impl Writer<'_> {
+    fn write(&mut self, expr: &Expr) {
+        let prefix = self
+            .printer
+            .config
+            .dialect()
+            .identifier_prefix()
+            .unwrap_or_default();
+
+        match &expr.kind {
+            ExprKind::Number(Number::Int(num)) => self.token(&num.to_string()),
+            ExprKind::Number(Number::Float(num)) => {
+                // integer floats are written with the fraction, so they are not read as integers
+                let text = num.to_string();
+
+                match text.contains('.') {
+                    true => self.token(&text),
+                    false => self.token(&format!("{text}.0")),
+                }
+            }
+            ExprKind::Number(Number::Decimal(digits)) => {
+                // digits keep `e` as the exponent marker, so it is written the dialect's way
+                let marker = self
+                    .printer
+                    .config
+                    .dialect()
+                    .exponent_marker()
+                    .unwrap_or('e');
+
+                self.token(&digits.replace('e', &marker.to_string()))
+            }
+            ExprKind::Number(Number::Imaginary(num)) => self.token(&self.printer.imaginary(*num)),
+            ExprKind::Variable(name) => self.token(&format!("{prefix}{name}")),
+            ExprKind::Group(inner) => self.write(inner),
+            ExprKind::Call { name, args } => {
+                self.token(&format!("{prefix}{name}"));
+                self.symbol(Group::Open.into());
+
+                for (position, arg) in args.iter().enumerate() {
+                    if position > 0 {
+                        self.symbol(Symbol::Separator);
+                        self.space = true;
+                    }
+
+                    self.write(arg);
+                }
+
+                self.symbol(Group::Close.into());
+            }
+            ExprKind::Unary { operator, operand } => {
+                self.symbol(operator.clone().into());
+                self.operand(operand, operator, Side::Operand);
+            }
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                self.operand(left, operator, Side::Left);
+                self.space = true;
+                self.symbol(operator.clone().into());
+                self.space = true;
+                self.operand(right, operator, Side::Right);
+            }
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                // only the conditional binds looser than the conditional itself
+                let nested = matches!(condition.kind, ExprKind::Conditional { .. });
+
+                self.branch(condition, nested);
+                self.space = true;
+                self.symbol(Branch::Then.into());
+                self.space = true;
+                self.branch(then, false);
+                self.space = true;
+                self.symbol(Branch::Else.into());
+                self.space = true;
+                self.branch(otherwise, false);
+            }
+            ExprKind::Unit { operand, unit } => {
+                // unit binds tighter than any operator
+                self.branch(operand, is_operation(operand));
+                self.token(&self.printer.unit(unit));
+            }
+        }
+    }
+
+    /// Writes the part of the conditional or the operand of the unit
+    fn branch(&mut self, expr: &Expr, grouped: bool) {
+        if grouped || (self.printer.parentheses == Parentheses::Full && is_operation(expr)) {
+            self.symbol(Group::Open.into());
+            self.write(expr);
+            self.symbol(Group::Close.into());
+        } else {
+            self.write(expr);
+        }
+    }
+
+    /// Writes the operator before or after its operands, every token is separated by the space
+    fn write_polish(&mut self, expr: &Expr, notation: Notation) {
+        let operands = match &expr.kind {
+            ExprKind::Unary { operand, .. } => vec![operand.as_ref()],
+            ExprKind::Binary { left, right, .. } => vec![left.as_ref(), right.as_ref()],
+            ExprKind::Call { args, .. } => args.iter().collect(),
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => vec![condition.as_ref(), then.as_ref(), otherwise.as_ref()],
+            ExprKind::Unit { operand, .. } => vec![operand.as_ref()],
+            _ => {
+                self.space = true;
+                return self.write(expr);
+            }
+        };
+
+        if notation == Notation::Prefix {
+            self.polish_operator(expr);
+        }
+
+        for operand in operands {
+            self.write_polish(operand, notation);
+        }
+
+        if notation == Notation::Postfix {
+            self.polish_operator(expr);
+        }
+    }
+
+    /// Writes the operator or the function name with the number of its arguments
+    fn polish_operator(&mut self, expr: &Expr) {
+        self.space = true;
+
+        match &expr.kind {
+            ExprKind::Unary { operator, .. } | ExprKind::Binary { operator, .. } => {
+                self.symbol(operator.clone().into())
+            }
+            ExprKind::Call { name, args } => {
+                let prefix = self
+                    .printer
+                    .config
+                    .dialect()
+                    .identifier_prefix()
+                    .unwrap_or_default();
+
+                self.token(&format!("{prefix}{name}"));
+                self.symbol(Group::Open.into());
+                self.token(&args.len().to_string());
+                self.symbol(Group::Close.into());
+            }
+            ExprKind::Conditional { .. } => self.symbol(Branch::Then.into()),
+            ExprKind::Unit { unit, .. } => self.token(&self.printer.unit(unit)),
+            _ => unreachable!("only operations have operator"),
+        }
+    }
+
+    fn operand(&mut self, expr: &Expr, operator: &Operator, side: Side) {
+        if self.is_grouped(expr, operator, side) {
+            self.symbol(Group::Open.into());
+            self.write(expr);
+            self.symbol(Group::Close.into());
+        } else {
+            self.write(expr);
+        }
+    }
+
+    fn symbol(&mut self, symbol: Symbol) {
+        let text = self.printer.text(symbol).to_string();
+
+        self.token(&text);
+    }
+
+    /// Appends the token separating it from the previous one
+    /// when they would be read as the single token otherwise
+    fn token(&mut self, text: &str) {
+        if !self.last.is_empty() && (self.space || self.merges(text)) {
+            self.output.push(' ');
+        }
+
+        self.output.push_str(text);
+        self.last = text.to_string();
+        self.space = false;
+    }
+
+    fn merges(&self, next: &str) -> bool {
+        let joined = format!("{}{next}", self.last);
+        let mut tokens = TokenIterator::with_dialect(&joined, self.printer.config.dialect());
+
+        !matches!(tokens.next(), Some(Ok(token)) if token.span.end == self.last.len())
+    }
+
+    /// Checks whether the operand needs brackets to stay the operand of the operator
+    fn is_grouped(&mut self, expr: &Expr, operator: &Operator, side: Side) -> bool {
+        let key = expr as *const Expr;
+
+        if let Some(grouped) = self.grouped.get(&key) {
+            return *grouped;
+        }
+
+        let grouped = match self.printer.parentheses {
+            Parentheses::Full => is_operation(expr),
+            // conditional binds the loosest, so it is the operand only inside the brackets
+            Parentheses::Minimal if matches!(expr.kind, ExprKind::Conditional { .. }) => true,
+            Parentheses::Minimal if side == Side::Left => !self.ends_before(expr, operator),
+            Parentheses::Minimal => !self.starts_after(expr, operator, side),
+        };
+
+        self.grouped.insert(key, grouped);
+        grouped
+    }
+
+    /// Operators at the end of the expression written without brackets
+    /// are applied before the `next` operator that follows it
+    fn ends_before(&mut self, mut expr: &Expr, next: &Operator) -> bool {
+        loop {
+            let (operator, rest, side) = match &expr.kind {
+                ExprKind::Binary {
+                    operator, right, ..
+                } => (operator, right, Side::Right),
+                ExprKind::Unary { operator, operand } => (operator, operand, Side::Operand),
+                _ => return true,
+            };
+
+            if !self.printer.config.binds_before(operator, next) {
+                return false;
+            }
+
+            if self.is_grouped(rest, operator, side) {
+                return true;
+            }
+
+            expr = rest;
+        }
+    }
+
+    /// Operators at the start of the expression written without brackets
+    /// are applied before the `prev` operator that precedes it
+    fn starts_after(&mut self, mut expr: &Expr, prev: &Operator, side: Side) -> bool {
+        loop {
+            match &expr.kind {
+                ExprKind::Binary { operator, left, .. } => {
+                    if self.printer.config.binds_before(prev, operator) {
+                        return false;
+                    }
+
+                    if self.is_grouped(left, operator, Side::Left) {
+                        return true;
+                    }
+
+                    expr = left;
+                }
+                // the same unary operator can't follow itself, i.e. `--1`
+                ExprKind::Unary { operator, .. } => {
+                    return side != Side::Operand || operator != prev
+                }
+                _ => return true,
+            }
+        }
+    }
+}
+
+fn is_operation(expr: &Expr) -> bool {
+    matches!(
+        expr.kind,
+        ExprKind::Unary { .. } | ExprKind::Binary { .. } | ExprKind::Conditional { .. }
+    )
+}
+
+/// Writes the expression with the [Printer::default]
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Printer::default().print(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dialect::Dialect, parse::ExprParser};
+
+    fn standard() -> ParserConfig {
+        ParserConfig::standard().with_dialect(Dialect::standard())
+    }
+
+    fn int(num: i32) -> Expr {
+        Expr::number(Number::Int(num.into()))
+    }
+
+    /// Prints the source parsed with the config and checks the output is parsed to the same tree
+    fn reprint(source: &str, config: ParserConfig, parentheses: Parentheses) -> String {
+        let parser = ExprParser::new(config.clone());
+        let expr = parser.parse_str(source).unwrap();
+        let printed = Printer::new(config)
+            .unwrap()
+            .with_parentheses(parentheses)
+            .print(&expr);
+
+        assert_eq!(
+            parser.parse_str(&printed).unwrap().ungrouped(),
+            expr.ungrouped(),
+            "{printed}"
+        );
+
+        printed
+    }
+
+    #[test]
+    fn minimal() {
+        let minimal = |source| reprint(source, standard(), Parentheses::Minimal);
+
+        assert_eq!(minimal("((1 + 2)) * (3 * 4)"), "(1 + 2) * (3 * 4)");
+        assert_eq!(minimal("1 - (2 - 3) - (4 + 5)"), "1 - (2 - 3) - (4 + 5)");
+        assert_eq!(
+            minimal("(2 ^ 3) ^ 2 + 2 ^ (3 ^ 2)"),
+            "(2 ^ 3) ^ 2 + 2 ^ 3 ^ 2"
+        );
+        assert_eq!(minimal("-(2 ^ 2) * (-2) ^ 2"), "-2 ^ 2 * (-2) ^ 2");
+        assert_eq!(minimal("-(-(x)) - (-y)"), "-(-x) - -y");
+        assert_eq!(minimal("max((1), (a + b) * 2,c)"), "max(1, (a + b) * 2, c)");
+        assert_eq!(minimal("2.0 * .5 % 1"), "2.0 * 0.5 % 1");
+    }
+
+    #[test]
+    fn flat_letters() {
+        let minimal = |source| reprint(source, ParserConfig::flat(), Parentheses::Minimal);
+
+        assert_eq!(minimal("e1 a 2f c 3"), "1 a 2 c 3");
+        assert_eq!(minimal("1 a e2 c 3f"), "1 a e2 c 3f");
+        assert_eq!(minimal("be2 g 2f"), "b2 g 2");
+        assert_eq!(minimal("eb2f g 2"), "eb2f g 2");
+        assert_eq!(minimal("e2 g 3f g 2"), "e2 g 3f g 2");
+        assert_eq!(minimal("$max e$x, 2f a $y"), "$max e$x, 2f a $y");
+    }
+
+    #[test]
+    fn full() {
+        let full = |source| reprint(source, standard(), Parentheses::Full);
+
+        assert_eq!(full("1 + 2 * 3 - 4"), "(1 + (2 * 3)) - 4");
+        assert_eq!(full("-x ^ 2"), "-(x ^ 2)");
+        assert_eq!(full("sqrt(1 + 2)"), "sqrt(1 + 2)");
+    }
+
+    #[test]
+    fn translation() {
+        let expr = ExprParser::default()
+            .parse_str("e2 a 3f c $price g 2")
+            .unwrap();
+        let printer = Printer::new(standard()).unwrap();
+
+        assert_eq!(printer.print(&expr), "(2 + 3) * price ^ 2");
+        assert_eq!(expr.to_string(), "2 a 3 c $price g 2");
+
+        // tree built by hand with the negative literal and the custom negation
+        let expr = Expr::binary(Operator::Sub, int(1), int(-2));
+        let config = standard().with_dialect(
+            Dialect::standard()
+                .with_symbol("neg", Operator::Neg)
+                .unwrap(),
+        );
+
+        assert_eq!(Printer::new(config).unwrap().print(&expr), "1 - neg 2");
+        assert_eq!(
+            Printer::new(ParserConfig::default().with_dialect(Dialect::new())).unwrap_err(),
+            DialectError::MissingSymbol(Operator::Add.into())
+        );
+    }
+
+    #[test]
+    fn custom_precedence() {
+        use crate::parse::Associativity;
+
+        let config = standard()
+            .with_operator(Operator::Sub, 1, Associativity::Right)
+            .with_operator(Operator::Neg, 0, Associativity::Right);
+
+        for source in [
+            "1 - 2 - 3",
+            "(1 - 2) - 3",
+            "1 + 2 - 3",
+            "(1 + 2) - 3 + 4",
+            "-1 + 2",
+            "(-1) + 2",
+            "2 * -x",
+            "(-x) * 2",
+        ] {
+            reprint(source, config.clone(), Parentheses::Minimal);
+        }
+    }
+
+    #[test]
+    fn polish() {
+        let expr = ExprParser::new(standard())
+            .parse_str("-(1 + 2) * max(x, 3 ^ 2, 4) / 5.0")
+            .unwrap();
+        let printer = |notation| Printer::new(standard()).unwrap().with_notation(notation);
+
+        assert_eq!(
+            printer(Notation::Prefix).print(&expr),
+            "/ * ~ + 1 2 max(3) x ^ 3 2 4 5.0"
+        );
+        assert_eq!(
+            printer(Notation::Postfix).print(&expr),
+            "1 2 + ~ x 3 2 ^ 4 max(3) * 5.0 /"
+        );
+        assert_eq!(
+            Printer::default()
+                .with_notation(Notation::Postfix)
+                .print(&expr),
+            "1 2 a ~ $x 3 2 g 4 $max e3f c 5.0 d"
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        let minimal = |source| reprint(source, standard(), Parentheses::Minimal);
+        let full = |source| reprint(source, standard(), Parentheses::Full);
+
+        assert_eq!(minimal("(a < b) == (c > d)"), "a < b == (c > d)");
+        assert_eq!(minimal("!(a < b) && (c || d)"), "!a < b && (c || d)");
+        assert_eq!(minimal("(!a) < b"), "(!a) < b");
+        assert_eq!(minimal("-(a ? b : c) + 1"), "-(a ? b : c) + 1");
+        assert_eq!(
+            minimal("(a ? b : c) ? (d ? e : f) : (g ? h : i)"),
+            "(a ? b : c) ? d ? e : f : g ? h : i"
+        );
+        assert_eq!(
+            minimal("x > 0 ? -x : max(x, y ? 1 : 2)"),
+            "x > 0 ? -x : max(x, y ? 1 : 2)"
+        );
+        assert_eq!(full("a + 1 > b ? a : -b"), "((a + 1) > b) ? a : (-b)");
+
+        let expr = ExprParser::new(standard())
+            .parse_str("x >= 0 ? x : -x")
+            .unwrap();
+
+        assert_eq!(
+            Printer::new(standard())
+                .unwrap()
+                .with_notation(Notation::Postfix)
+                .print(&expr),
+            "x 0 >= x x ~ ?"
+        );
+        assert_eq!(expr.to_string(), "$x >= 0 ? $x : b$x");
+    }
+
+    #[test]
+    fn units() {
+        let minimal = |source| reprint(source, standard(), Parentheses::Minimal);
+
+        assert_eq!(
+            minimal("(2[lb] + 500[g])[kg] * -x[ USD/kg ]"),
+            "(2[lb] + 500[g])[kg] * -x[USD/kg]"
+        );
+        assert_eq!(minimal("(-2)[m] ^ (2)[s]"), "(-2)[m] ^ 2[s]");
+        assert_eq!(
+            reprint("max(1, 2)[g][kg]", standard(), Parentheses::Full),
+            "max(1, 2)[g][kg]"
+        );
+        // brackets take precedence over `<` and `>`
+        assert_eq!(
+            Printer::new(
+                ParserConfig::default()
+                    .with_dialect(Dialect::letters().with_unit_brackets("<", ">").unwrap())
+            )
+            .unwrap()
+            .print(&Expr::unit(int(1), "m")),
+            "1<m>"
+        );
+
+        let expr = ExprParser::new(standard())
+            .parse_str("3[oz] * price[USD/oz]")
+            .unwrap();
+
+        assert_eq!(
+            Printer::new(standard())
+                .unwrap()
+                .with_notation(Notation::Postfix)
+                .print(&expr),
+            "3 [oz] price [USD/oz] *"
+        );
+    }
+
+    #[test]
+    fn imaginary_numbers() {
+        let minimal = |source| reprint(source, standard(), Parentheses::Minimal);
+
+        assert_eq!(minimal("(1 + 2.5i) * -1e3i"), "(1 + 2.5i) * -1000i");
+        assert_eq!(
+            Printer::new(
+                ParserConfig::default()
+                    .with_dialect(Dialect::letters().with_imaginary_suffix('j').unwrap())
+            )
+            .unwrap()
+            .print(&Expr::number(Number::Imaginary(2.0))),
+            "2j"
+        );
+    }
+}
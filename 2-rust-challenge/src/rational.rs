@@ -0,0 +1,351 @@
+//! Module with the exact fractions of the arbitrary-precision integers
+//! that are used by the exact evaluation
+
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use crate::bigint::BigInt;
+
+/// Limit on the exponent of the decimal number, so `1e1000000000` is rejected
+/// instead of running out of memory
+pub const MAX_DECIMAL_EXPONENT: u32 = 10_000;
+
+/// Fraction that is always kept in the lowest terms with the positive denominator
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numer: BigInt,
+    denom: BigInt,
+}
+
+impl Rational {
+    /// Creates the fraction reducing it to the lowest terms,
+    /// returns `None` for the zero denominator
+    pub fn new(numer: BigInt, denom: BigInt) -> Option<Self> {
+        if denom.is_zero() {
+            return None;
+        }
+
+        let gcd = numer.gcd(&denom);
+        let (mut numer, _) = numer.checked_div_rem(&gcd)?;
+        let (mut denom, _) = denom.checked_div_rem(&gcd)?;
+
+        if denom.is_negative() {
+            numer = -numer;
+            denom = -denom;
+        }
+
+        Some(Self { numer, denom })
+    }
+
+    pub fn zero() -> Self {
+        Self::from(BigInt::zero())
+    }
+
+    pub fn numer(&self) -> &BigInt {
+        &self.numer
+    }
+
+    pub fn denom(&self) -> &BigInt {
+        &self.denom
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numer.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numer.is_negative()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == BigInt::one()
+    }
+
+    /// Exact value of the decimal number with the optional exponent, i.e. `-12.05` or `1.5e-3`,
+    /// returns `None` for the malformed number or the exponent beyond [MAX_DECIMAL_EXPONENT]
+    pub fn from_decimal_str(s: &str) -> Option<Self> {
+        let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+            None => (s, 0),
+        };
+
+        if exponent.unsigned_abs() > u64::from(MAX_DECIMAL_EXPONENT) {
+            return None;
+        }
+
+        let (int, fract) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let numer = format!("{int}{fract}").parse::<BigInt>().ok()?;
+        let shift = exponent - fract.len() as i64;
+        let scale = BigInt::from(10).pow(u32::try_from(shift.unsigned_abs()).ok()?);
+
+        match shift >= 0 {
+            true => Self::new(&numer * &scale, BigInt::one()),
+            false => Self::new(numer, scale),
+        }
+    }
+
+    /// Value of the number as it is written by the shortest representation,
+    /// so `0.1` is exactly `1/10`. Returns `None` for the infinities and NaN
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        Self::from_decimal_str(&value.to_string())
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        // keeps 64 significant bits in the quotient, so large terms don't overflow
+        let shift = self.denom.bits() as i64 - self.numer.bits() as i64 + 64;
+        let (quotient, _) = if shift >= 0 {
+            self.numer.shl(shift as u64).checked_div_rem(&self.denom)
+        } else {
+            self.numer
+                .checked_div_rem(&self.denom.shl(shift.unsigned_abs()))
+        }
+        .expect("denominator is not zero");
+
+        quotient.to_f64() * 2f64.powi((-shift).clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+    }
+
+    /// Integer part of the fraction rounded toward zero
+    pub fn trunc(&self) -> BigInt {
+        let (quotient, _) = self
+            .numer
+            .checked_div_rem(&self.denom)
+            .expect("denominator is not zero");
+
+        quotient
+    }
+
+    /// Greatest integer that is not above the fraction
+    pub fn floor(&self) -> BigInt {
+        match self.is_negative() && !self.is_integer() {
+            true => &self.trunc() - &BigInt::one(),
+            false => self.trunc(),
+        }
+    }
+
+    /// Least integer that is not below the fraction
+    pub fn ceil(&self) -> BigInt {
+        match self.is_negative() || self.is_integer() {
+            true => self.trunc(),
+            false => &self.trunc() + &BigInt::one(),
+        }
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            numer: self.numer.abs(),
+            denom: self.denom.clone(),
+        }
+    }
+
+    /// Exact square root, returns `None` for the negative fraction
+    /// and the one that is not the square of a fraction, i.e. `2`
+    pub fn sqrt(&self) -> Option<Self> {
+        if self.is_negative() {
+            return None;
+        }
+
+        let (numer, denom) = (self.numer.sqrt(), self.denom.sqrt());
+
+        (&numer * &numer == self.numer && &denom * &denom == self.denom)
+            .then_some(Self { numer, denom })
+    }
+
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        Self::new(&self.numer * &other.denom, &self.denom * &other.numer)
+    }
+
+    /// Remainder of the division that has the sign of the dividend
+    pub fn checked_rem(&self, other: &Self) -> Option<Self> {
+        let quotient = Self::from(self.checked_div(other)?.trunc());
+
+        Some(self - &(other * &quotient))
+    }
+
+    /// Raises to the integer power, returns `None` for the negative power of zero
+    pub fn checked_pow(&self, exponent: i32) -> Option<Self> {
+        let numer = self.numer.pow(exponent.unsigned_abs());
+        let denom = self.denom.pow(exponent.unsigned_abs());
+
+        if exponent < 0 {
+            Self::new(denom, numer)
+        } else {
+            Self::new(numer, denom)
+        }
+    }
+
+    /// Decimal representation with the number of fractional digits,
+    /// the last digit is rounded half away from zero
+    pub fn to_decimal(&self, precision: usize) -> String {
+        let scale = BigInt::from(10).pow(precision as u32);
+        let scaled = &self.numer.abs() * &scale;
+        let (quotient, remainder) = scaled
+            .checked_div_rem(&self.denom)
+            .expect("denominator is not zero");
+        let quotient = match (&remainder + &remainder).cmp(&self.denom) {
+            Ordering::Less => quotient,
+            _ => &quotient + &BigInt::one(),
+        };
+
+        let digits = format!("{quotient:0>width$}", width = precision + 1);
+        let (int, fract) = digits.split_at(digits.len() - precision);
+        let sign = if self.is_negative() && !quotient.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+
+        if fract.is_empty() {
+            format!("{sign}{int}")
+        } else {
+            format!("{sign}{int}.{fract}")
+        }
+    }
+}
+
+impl From<BigInt> for Rational {
+    fn from(numer: BigInt) -> Self {
+        Self {
+            numer,
+            denom: BigInt::one(),
+        }
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Self::from(BigInt::from(value))
+    }
+}
+
+/// Writes the fraction as `numer/denom` or just the integer when the denominator is 1
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_integer() {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.numer * &other.denom).cmp(&(&other.numer * &self.denom))
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            numer: -self.numer,
+            denom: self.denom,
+        }
+    }
+}
+
+impl Add for &Rational {
+    type Output = Rational;
+
+    fn add(self, other: Self) -> Self::Output {
+        Rational::new(
+            &(&self.numer * &other.denom) + &(&other.numer * &self.denom),
+            &self.denom * &other.denom,
+        )
+        .expect("denominators are not zero")
+    }
+}
+
+impl Sub for &Rational {
+    type Output = Rational;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + &-other.clone()
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+
+    fn mul(self, other: Self) -> Self::Output {
+        Rational::new(&self.numer * &other.numer, &self.denom * &other.denom)
+            .expect("denominators are not zero")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ratio(numer: i64, denom: i64) -> Rational {
+        Rational::new(numer.into(), denom.into()).unwrap()
+    }
+
+    #[test]
+    fn lowest_terms() {
+        assert_eq!(ratio(2, 4), ratio(1, 2));
+        assert_eq!(ratio(3, -6).to_string(), "-1/2");
+        assert_eq!(ratio(6, 3).to_string(), "2");
+        assert_eq!(Rational::new(1.into(), 0.into()), None);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let third = ratio(1, 3);
+
+        assert_eq!(&(&third + &third) + &third, ratio(1, 1));
+        assert_eq!(&third - &ratio(1, 2), ratio(-1, 6));
+        assert_eq!(&third * &ratio(3, 1), ratio(1, 1));
+        assert_eq!(third.checked_div(&ratio(2, 3)), Some(ratio(1, 2)));
+        assert_eq!(third.checked_div(&Rational::zero()), None);
+        assert_eq!(ratio(-7, 1).checked_rem(&ratio(3, 1)), Some(ratio(-1, 1)));
+        assert_eq!(ratio(11, 2).checked_rem(&ratio(2, 1)), Some(ratio(3, 2)));
+        assert_eq!(ratio(2, 3).checked_pow(-2), Some(ratio(9, 4)));
+        assert_eq!(Rational::zero().checked_pow(-1), None);
+        assert!(ratio(1, 3) < ratio(1, 2));
+        assert_eq!(ratio(-7, 2).floor(), BigInt::from(-4));
+        assert_eq!(ratio(-7, 2).ceil(), BigInt::from(-3));
+        assert_eq!(ratio(7, 2).ceil(), BigInt::from(4));
+        assert_eq!(ratio(-4, 1).floor(), BigInt::from(-4));
+        assert_eq!(ratio(-2, 3).abs(), ratio(2, 3));
+        assert_eq!(ratio(9, 4).sqrt(), Some(ratio(3, 2)));
+        assert_eq!(ratio(2, 1).sqrt(), None);
+        assert_eq!(ratio(-1, 1).sqrt(), None);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(Rational::from_f64(0.1), Some(ratio(1, 10)));
+        assert_eq!(Rational::from_f64(-2.5), Some(ratio(-5, 2)));
+        assert_eq!(Rational::from_f64(f64::NAN), None);
+        assert_eq!(Rational::from_decimal_str("12.050"), Some(ratio(241, 20)));
+        assert_eq!(Rational::from_decimal_str("1.5e-3"), Some(ratio(3, 2000)));
+        assert_eq!(Rational::from_decimal_str("-.25E+2"), Some(ratio(-25, 1)));
+        assert_eq!(Rational::from_decimal_str("1e20001"), None);
+        assert_eq!(ratio(1, 3).to_f64(), 1.0 / 3.0);
+        assert_eq!(ratio(-5, 2).to_f64(), -2.5);
+    }
+
+    #[test]
+    fn decimal() {
+        assert_eq!(ratio(2, 3).to_decimal(4), "0.6667");
+        assert_eq!(ratio(-1, 8).to_decimal(2), "-0.13");
+        assert_eq!(ratio(-1, 1000).to_decimal(2), "0.00");
+        assert_eq!(ratio(5, 2).to_decimal(0), "3");
+        assert_eq!(ratio(1, 1).to_decimal(3), "1.000");
+    }
+}
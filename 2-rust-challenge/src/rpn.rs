@@ -0,0 +1,384 @@
+//! Module that reads the expression written in the reverse Polish notation,
+//! where operators follow their operands, i.e. `2 3 a 4 c`
+
+use std::iter::Peekable;
+
+use crate::{
+    dialect::Dialect,
+    parse::{Expr, ExprKind, ParserConfig, ParserError, Result},
+    span::{Span, Spanned},
+    tokens::{Branch, Group, Number, Operator, Result as TokenizerResult, Token, TokenIterator},
+};
+
+/// Text of the negation in the Polish notations when the dialect has no own symbol for it,
+/// as the position of the operator doesn't tell it from subtraction there
+pub const NEGATION: &str = "~";
+
+/// Parser of the stack based source, i.e. logs of the stack calculators,
+/// that builds the same [Expr] as the infix source, so it could be evaluated by any evaluator.
+///
+/// Tokens are read with the dialect of the config, precedence is not used.
+/// Function name is followed by the number of its arguments in brackets, i.e. `1 2 3 $max e3f`,
+/// names without it are variables. Conditional takes three operands, i.e. `c a b ?`,
+/// and the unit applies to the single one, i.e. `5 [kg]`
+#[derive(Debug, Clone)]
+pub struct RpnParser {
+    dialect: Dialect,
+    max_depth: Option<usize>,
+}
+
+impl Default for RpnParser {
+    fn default() -> Self {
+        Self::new(ParserConfig::default())
+    }
+}
+
+impl RpnParser {
+    /// Creates parser with the dialect of the config, that gets [NEGATION] if it has no negation
+    pub fn new(config: ParserConfig) -> Self {
+        let dialect = config.dialect().clone();
+        let dialect = match dialect.text(&Operator::Neg.into()) {
+            Some(_) => dialect,
+            None => dialect
+                .with_symbol(NEGATION, Operator::Neg)
+                .expect("negation has text"),
+        };
+
+        Self {
+            dialect,
+            max_depth: None,
+        }
+    }
+
+    /// Limits the number of values on the stack, i.e. `4` for the classic calculators
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn parse_str(&self, source: &str) -> Result<Expr> {
+        let mut tokens_iter = TokenIterator::with_dialect(source, &self.dialect)
+            .with_unary_detection(false)
+            .peekable();
+        let mut stack = vec![];
+        let mut position = 0;
+
+        while let Some(token) = tokens_iter.next() {
+            let Spanned { node: token, span } = token?;
+
+            position = span.end;
+
+            let expr = match token {
+                Token::Number(number) => Expr::new(ExprKind::Number(number), span),
+                Token::Identifier(name) => match Self::argument_count(&mut tokens_iter)? {
+                    Some(Spanned {
+                        node: count,
+                        span: end,
+                    }) => {
+                        let span = span.join(end);
+                        let args =
+                            Self::pop(&mut stack, count, Token::Identifier(name.clone()), span)?;
+                        let span = args.first().map_or(span, |arg| arg.span.join(span));
+
+                        position = span.end;
+                        Expr::new(ExprKind::Call { name, args }, span)
+                    }
+                    None => Expr::new(ExprKind::Variable(name), span),
+                },
+                Token::Operator(operator) => {
+                    let needed = operator.arity().into();
+                    let mut operands =
+                        Self::pop(&mut stack, needed, Token::Operator(operator.clone()), span)?;
+                    let span = operands[0].span.join(span);
+                    let right = operands.pop().expect("operand for operator");
+
+                    match operands.pop() {
+                        Some(left) => Expr::binary(operator, left, right),
+                        None => Expr::unary(operator, right),
+                    }
+                    .with_span(span)
+                }
+                Token::Branch(Branch::Then) => {
+                    let mut operands = Self::pop(&mut stack, 3, Token::Branch(Branch::Then), span)?;
+                    let span = operands[0].span.join(span);
+                    let otherwise = operands.pop().expect("else branch");
+                    let then = operands.pop().expect("then branch");
+                    let condition = operands.pop().expect("condition");
+
+                    Expr::conditional(condition, then, otherwise).with_span(span)
+                }
+                Token::Unit(unit) => {
+                    let mut operands = Self::pop(&mut stack, 1, Token::Unit(unit.clone()), span)?;
+                    let operand = operands.pop().expect("operand of the unit");
+                    let span = operand.span.join(span);
+
+                    Expr::unit(operand, unit).with_span(span)
+                }
+                token => return Err(ParserError::UnexpectedToken { token, span }),
+            };
+
+            if let Some(limit) = self.max_depth.filter(|limit| stack.len() >= *limit) {
+                return Err(ParserError::StackOverflow {
+                    limit,
+                    span: Span::new(span.start, position),
+                });
+            }
+
+            stack.push(expr);
+        }
+
+        match stack.len() {
+            0 => Err(ParserError::EmptyExpr {
+                span: Span::point(position),
+            }),
+            1 => Ok(stack.pop().expect("stack has single value")),
+            count => Err(ParserError::ExtraOperands {
+                count,
+                // values below the result that no operator has consumed
+                span: stack[0].span.join(stack[count - 2].span),
+            }),
+        }
+    }
+
+    /// Reads the number of the arguments in brackets after the function name,
+    /// returns it with the span up to the closing bracket or `None` for the variable
+    fn argument_count<I>(tokens_iter: &mut Peekable<I>) -> Result<Option<Spanned<usize>>>
+    where
+        I: Iterator<Item = TokenizerResult<Spanned<Token>>>,
+    {
+        if !matches!(
+            tokens_iter.peek(),
+            Some(Ok(Spanned {
+                node: Token::Group(Group::Open),
+                ..
+            }))
+        ) {
+            return Ok(None);
+        }
+
+        let open = tokens_iter.next().expect("open bracket is peeked")?.span;
+        let count = match tokens_iter.next().transpose()? {
+            Some(Spanned {
+                node: Token::Number(Number::Int(count)),
+                span,
+            }) => match count.to_i64().and_then(|count| usize::try_from(count).ok()) {
+                Some(count) => count,
+                None => {
+                    return Err(ParserError::ArgumentCountExpected {
+                        token: Some(Token::Number(Number::Int(count))),
+                        span,
+                    })
+                }
+            },
+            Some(Spanned { node, span }) => {
+                return Err(ParserError::ArgumentCountExpected {
+                    token: Some(node),
+                    span,
+                })
+            }
+            None => {
+                return Err(ParserError::ArgumentCountExpected {
+                    token: None,
+                    span: Span::point(open.end),
+                })
+            }
+        };
+
+        match tokens_iter.next().transpose()? {
+            Some(Spanned {
+                node: Token::Group(Group::Close),
+                span,
+            }) => Ok(Some(Spanned::new(count, span))),
+            Some(Spanned { node, span }) => Err(ParserError::UnbalancedGroup {
+                token: Some(node),
+                span,
+            }),
+            None => Err(ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Open)),
+                span: open,
+            }),
+        }
+    }
+
+    /// Takes the operands from the top of the stack in the order they were pushed
+    fn pop(stack: &mut Vec<Expr>, needed: usize, token: Token, span: Span) -> Result<Vec<Expr>> {
+        if stack.len() < needed {
+            return Err(ParserError::StackUnderflow {
+                token,
+                needed,
+                available: stack.len(),
+                span,
+            });
+        }
+
+        Ok(stack.split_off(stack.len() - needed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dialect::Dialect,
+        parse::ExprParser,
+        print::{Notation, Printer},
+    };
+
+    fn parse(source: &str) -> Result<Expr> {
+        RpnParser::default().parse_str(source)
+    }
+
+    #[test]
+    fn operators() {
+        let infix = |source| ExprParser::default().parse_str(source).unwrap().ungrouped();
+
+        assert_eq!(parse("2 3 a 4 c"), Ok(infix("2 a 3 c 4")));
+        assert_eq!(parse("2 3 4 c a"), Ok(infix("2 a e3 c 4f")));
+        assert_eq!(parse("1 2 b ~"), Ok(infix("be1 b 2f")));
+        assert_eq!(parse("5 $x ~ g"), Ok(infix("5 g b$x")));
+        assert_eq!(
+            parse("1 2 3 $max e3f $pi e0f a $sqrt e1f"),
+            Ok(infix("$sqrt e$max e1, 2, 3f a $pi ef f"))
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let expr = parse("1 2 a $max e1f").unwrap();
+
+        assert_eq!(expr.span, Span::new(0, 14));
+
+        let ExprKind::Call { args, .. } = expr.kind else {
+            panic!("call is expected");
+        };
+
+        assert_eq!(args[0].span, Span::new(0, 5));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse("1 a 2"),
+            Err(ParserError::StackUnderflow {
+                token: Token::Operator(Operator::Add),
+                needed: 2,
+                available: 1,
+                span: Span::new(2, 3)
+            })
+        );
+        assert_eq!(
+            parse("1 $max e3f"),
+            Err(ParserError::StackUnderflow {
+                token: Token::Identifier("max".to_string()),
+                needed: 3,
+                available: 1,
+                span: Span::new(2, 10)
+            })
+        );
+        assert_eq!(
+            parse("1 2 3 a"),
+            Err(ParserError::ExtraOperands {
+                count: 2,
+                span: Span::new(0, 1)
+            })
+        );
+        assert_eq!(
+            RpnParser::default()
+                .with_max_depth(2)
+                .parse_str("1 2 a 3 4"),
+            Err(ParserError::StackOverflow {
+                limit: 2,
+                span: Span::new(8, 9)
+            })
+        );
+        assert_eq!(
+            parse("1 e2 a"),
+            Err(ParserError::UnexpectedToken {
+                token: Token::Group(Group::Open),
+                span: Span::new(2, 3)
+            })
+        );
+        assert_eq!(
+            parse("1 $f e$x f"),
+            Err(ParserError::ArgumentCountExpected {
+                token: Some(Token::Identifier("x".to_string())),
+                span: Span::new(6, 8)
+            })
+        );
+        assert_eq!(
+            parse("1 $f e1"),
+            Err(ParserError::UnbalancedGroup {
+                token: Some(Token::Group(Group::Open)),
+                span: Span::new(5, 6)
+            })
+        );
+        assert_eq!(
+            parse(" "),
+            Err(ParserError::EmptyExpr {
+                span: Span::point(0)
+            })
+        );
+    }
+
+    #[test]
+    fn printed() {
+        let config = ParserConfig::standard().with_dialect(Dialect::standard());
+        let expr = ExprParser::new(config.clone())
+            .parse_str("-(x - 2) ^ 2 % 3 / min(4, -y) - f()")
+            .unwrap();
+        let postfix = Printer::new(config.clone())
+            .unwrap()
+            .with_notation(Notation::Postfix)
+            .print(&expr);
+
+        assert_eq!(
+            RpnParser::new(config).parse_str(&postfix),
+            Ok(expr.ungrouped())
+        );
+    }
+
+    #[test]
+    fn conditionals() {
+        let infix = |source| ExprParser::default().parse_str(source).unwrap().ungrouped();
+
+        assert_eq!(
+            parse("$x 0 > $y ! && 1 $z 2 3 ? ?"),
+            Ok(infix("$x > 0 && !$y ? 1 : $z ? 2 : 3"))
+        );
+        assert_eq!(
+            parse("1 2 ?"),
+            Err(ParserError::StackUnderflow {
+                token: Token::Branch(Branch::Then),
+                needed: 3,
+                available: 2,
+                span: Span::new(4, 5)
+            })
+        );
+        assert_eq!(
+            parse("1 2 :"),
+            Err(ParserError::UnexpectedToken {
+                token: Token::Branch(Branch::Else),
+                span: Span::new(4, 5)
+            })
+        );
+    }
+
+    #[test]
+    fn units() {
+        let infix = |source| ExprParser::default().parse_str(source).unwrap().ungrouped();
+
+        assert_eq!(
+            parse("2 [kg] 3 [lb] a [g]"),
+            Ok(infix("e2[kg] a 3[lb]f[g]"))
+        );
+        assert_eq!(
+            parse("[kg]"),
+            Err(ParserError::StackUnderflow {
+                token: Token::Unit("kg".to_string()),
+                needed: 1,
+                available: 0,
+                span: Span::new(0, 4)
+            })
+        );
+    }
+}
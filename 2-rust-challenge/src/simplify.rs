@@ -0,0 +1,436 @@
+//! Module to rewrite the parsed expression into the smaller one
+//! that evaluates to the same result
+
+use crate::{
+    check::Checker,
+    eval::Evaluator,
+    functions::Functions,
+    parse::{Expr, ExprKind},
+    rational::Rational,
+    tokens::{Number, Operator},
+    value::{Type, Value},
+};
+
+/// Integers up to this number of bits are exact in every [Numeric](crate::Numeric) backend
+const EXACT_BITS: u64 = f64::MANTISSA_DIGITS as u64;
+
+/// Folds constant subtrees, removes double negation and groups
+/// and applies identities, i.e. `x * 1 = x` and `x + 0 = x`,
+/// when the [Checker] finds the kept operand to be the number, so `(1 < 2) + 0` is kept.
+/// Zero isn't added to the operand with the unit, as `3[g] + 0` fails on the dimensionless zero.
+///
+/// Constant subtree is folded only when it is the integer that fits into `f64` exactly
+/// and both floating point and exact evaluation agree on it, so the simplified
+/// expression evaluates to the same result in any mode.
+/// Subtrees that fail to evaluate are kept, so the error is reported at the evaluation.
+/// Conditional with the constant condition is replaced by the chosen branch
+pub struct Simplifier {
+    checker: Checker,
+    exact: Evaluator<Rational>,
+    float: Evaluator<f64>,
+    assume_finite: bool,
+}
+
+impl Default for Simplifier {
+    fn default() -> Self {
+        Self::with_functions(Functions::builtin())
+    }
+}
+
+impl Simplifier {
+    /// Creates simplifier with [Functions::builtin]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the functions to fold the calls with constant arguments,
+    /// they should be the same as the ones used for the evaluation
+    pub fn with_functions(functions: Functions) -> Self {
+        Self {
+            checker: Checker::with_functions(functions.clone()),
+            exact: Evaluator::with_functions(functions.clone()),
+            float: Evaluator::with_functions(functions),
+            assume_finite: false,
+        }
+    }
+
+    /// Assumes values of the variables are finite, so `x * 0` becomes `0`
+    /// as long as `x` consists only of variables, literals, additions and multiplications
+    pub fn with_assume_finite(mut self, assume_finite: bool) -> Self {
+        self.assume_finite = assume_finite;
+        self
+    }
+
+    pub fn simplify(&self, expr: &Expr) -> Expr {
+        let simplified = match &expr.kind {
+            ExprKind::Number(_) | ExprKind::Variable(_) => return expr.clone(),
+            ExprKind::Group(inner) => return self.simplify(inner),
+            ExprKind::Unary { operator, operand } => {
+                Expr::unary(operator.clone(), self.simplify(operand))
+            }
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => Expr::binary(operator.clone(), self.simplify(left), self.simplify(right)),
+            ExprKind::Call { name, args } => {
+                Expr::call(name, args.iter().map(|arg| self.simplify(arg)).collect())
+            }
+            ExprKind::Unit { operand, unit } => Expr::unit(self.simplify(operand), unit),
+            ExprKind::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let condition = self.simplify(condition);
+
+                match self.decide(&condition) {
+                    Some(true) => return self.simplify(then),
+                    Some(false) => return self.simplify(otherwise),
+                    None => {
+                        Expr::conditional(condition, self.simplify(then), self.simplify(otherwise))
+                    }
+                }
+            }
+        }
+        .with_span(expr.span);
+
+        match self.fold(&simplified) {
+            Some(folded) => folded,
+            None => self.apply_identities(simplified),
+        }
+    }
+
+    /// Evaluates the node which operands are all literals,
+    /// negation is left for the identities as it is a part of the literal
+    fn fold(&self, expr: &Expr) -> Option<Expr> {
+        let constant = match &expr.kind {
+            ExprKind::Binary { left, right, .. } => is_literal(left) && is_literal(right),
+            ExprKind::Call { args, .. } => args.iter().all(is_literal),
+            _ => false,
+        };
+
+        if !constant {
+            return None;
+        }
+
+        let exact = self.exact.eval(expr).ok()?;
+        let float = self.float.eval(expr).ok()?;
+
+        if !exact.is_integer() || exact.numer().bits() > EXACT_BITS || exact.to_f64() != float {
+            return None;
+        }
+
+        let literal = Expr::number(Number::Int(exact.numer().abs())).with_span(expr.span);
+
+        Some(if exact.is_negative() {
+            Expr::unary(Operator::Neg, literal)
+        } else {
+            literal
+        })
+    }
+
+    /// Value of the condition without variables that both evaluations agree on
+    fn decide(&self, condition: &Expr) -> Option<bool> {
+        match (
+            self.exact.eval_value(condition).ok()?,
+            self.float.eval_value(condition).ok()?,
+        ) {
+            (Value::Bool(exact), Value::Bool(float)) if exact == float => Some(exact),
+            _ => None,
+        }
+    }
+
+    fn apply_identities(&self, expr: Expr) -> Expr {
+        let span = expr.span;
+
+        match expr.kind {
+            ExprKind::Unary {
+                operator: Operator::Neg,
+                operand,
+            } => match operand.kind {
+                ExprKind::Unary {
+                    operator: Operator::Neg,
+                    operand,
+                } if self.is_numeric(&operand) => *operand,
+                kind => Expr::unary(Operator::Neg, Expr::new(kind, operand.span)).with_span(span),
+            },
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => match operator {
+                Operator::Add if is_number(&left, 0.0) && self.is_dimensionless(&right) => *right,
+                Operator::Add | Operator::Sub
+                    if is_number(&right, 0.0) && self.is_dimensionless(&left) =>
+                {
+                    *left
+                }
+                Operator::Mul if is_number(&left, 1.0) && self.is_numeric(&right) => *right,
+                Operator::Mul | Operator::Div | Operator::Pow
+                    if is_number(&right, 1.0) && self.is_numeric(&left) =>
+                {
+                    *left
+                }
+                Operator::Mul
+                    if self.assume_finite
+                        && ((is_number(&left, 0.0) && self.is_total(&right))
+                            || (is_number(&right, 0.0) && self.is_total(&left))) =>
+                {
+                    Expr::number(Number::Int(0.into())).with_span(span)
+                }
+                operator => Expr::binary(operator, *left, *right).with_span(span),
+            },
+            kind => Expr::new(kind, span),
+        }
+    }
+
+    /// Operand the identity may keep in place of the operation, the operation
+    /// fails on the boolean, so the identity would hide the error
+    fn is_numeric(&self, expr: &Expr) -> bool {
+        matches!(self.checker.check(expr), Ok(Type::Number))
+    }
+
+    /// Numeric operand without the unit annotations, so the dimensionless number can be added to it
+    fn is_dimensionless(&self, expr: &Expr) -> bool {
+        !has_unit(expr) && self.is_numeric(expr)
+    }
+
+    /// Numeric expression that can't fail and stays finite for the finite variables
+    fn is_total(&self, expr: &Expr) -> bool {
+        is_total(expr) && self.is_numeric(expr)
+    }
+}
+
+/// Literal number possibly negated, i.e. the result of the folding
+fn is_literal(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Number(_) => true,
+        ExprKind::Unary {
+            operator: Operator::Neg,
+            operand,
+        } => matches!(operand.kind, ExprKind::Number(_)),
+        _ => false,
+    }
+}
+
+fn is_number(expr: &Expr, value: f64) -> bool {
+    matches!(&expr.kind, ExprKind::Number(number) if number.to_f64() == value)
+}
+
+/// Expression with the unit annotation in any of its operands
+fn has_unit(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Number(_) | ExprKind::Variable(_) => false,
+        ExprKind::Unit { .. } => true,
+        ExprKind::Group(inner) => has_unit(inner),
+        ExprKind::Unary { operand, .. } => has_unit(operand),
+        ExprKind::Binary { left, right, .. } => has_unit(left) || has_unit(right),
+        ExprKind::Call { args, .. } => args.iter().any(has_unit),
+        ExprKind::Conditional {
+            condition,
+            then,
+            otherwise,
+        } => has_unit(condition) || has_unit(then) || has_unit(otherwise),
+    }
+}
+
+/// Expression that can't fail and stays finite for the finite variables
+fn is_total(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Number(_) | ExprKind::Variable(_) => true,
+        ExprKind::Group(inner) => is_total(inner),
+        ExprKind::Unary { operand, .. } => is_total(operand),
+        ExprKind::Binary {
+            operator: Operator::Add | Operator::Sub | Operator::Mul,
+            left,
+            right,
+        } => is_total(left) && is_total(right),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Environment;
+
+    fn int(num: i32) -> Expr {
+        Expr::number(Number::Int(num.into()))
+    }
+
+    fn simplify(expr: Expr) -> Expr {
+        Simplifier::new().simplify(&expr)
+    }
+
+    fn x() -> Expr {
+        Expr::variable("x")
+    }
+
+    #[test]
+    fn folding() {
+        // (2 + 3) * x
+        assert_eq!(
+            simplify(Expr::binary(
+                Operator::Mul,
+                Expr::group(Expr::binary(Operator::Add, int(2), int(3))),
+                x()
+            )),
+            Expr::binary(Operator::Mul, int(5), x())
+        );
+        assert_eq!(
+            simplify(Expr::binary(Operator::Sub, int(2), int(7))),
+            Expr::unary(Operator::Neg, int(5))
+        );
+        assert_eq!(simplify(Expr::call("sqrt", vec![int(16)])), int(4));
+        assert_eq!(
+            simplify(Expr::call(
+                "max",
+                vec![int(1), Expr::unary(Operator::Neg, int(3))]
+            )),
+            int(1)
+        );
+
+        // results that differ between the modes or fail are kept
+        let third = Expr::binary(Operator::Div, int(1), int(3));
+        let zero_division = Expr::binary(Operator::Div, int(1), int(0));
+        let sum = Expr::binary(
+            Operator::Add,
+            Expr::number(Number::Float(0.1)),
+            Expr::number(Number::Float(0.2)),
+        );
+        let huge = Expr::binary(Operator::Pow, int(2), int(60));
+
+        assert_eq!(simplify(third.clone()), third);
+        assert_eq!(simplify(zero_division.clone()), zero_division);
+        assert_eq!(simplify(sum.clone()), sum);
+        assert_eq!(simplify(huge.clone()), huge);
+    }
+
+    #[test]
+    fn identities() {
+        let neg = |expr| Expr::unary(Operator::Neg, expr);
+
+        assert_eq!(simplify(neg(neg(x()))), x());
+        assert_eq!(simplify(neg(Expr::group(neg(x())))), x());
+        assert_eq!(simplify(neg(neg(neg(x())))), neg(x()));
+        assert_eq!(simplify(Expr::binary(Operator::Add, int(0), x())), x());
+        assert_eq!(simplify(Expr::binary(Operator::Sub, x(), int(0))), x());
+        assert_eq!(
+            simplify(Expr::binary(Operator::Sub, int(0), x())),
+            Expr::binary(Operator::Sub, int(0), x())
+        );
+        assert_eq!(
+            simplify(Expr::binary(
+                Operator::Mul,
+                Expr::binary(Operator::Sub, int(3), int(2)),
+                x()
+            )),
+            x()
+        );
+        assert_eq!(simplify(Expr::binary(Operator::Pow, x(), int(1))), x());
+
+        let zero = Expr::binary(
+            Operator::Mul,
+            Expr::binary(Operator::Add, x(), int(1)),
+            int(0),
+        );
+        let finite = Simplifier::new().with_assume_finite(true);
+
+        assert_eq!(simplify(zero.clone()), zero);
+        assert_eq!(finite.simplify(&zero), int(0));
+
+        let failing = Expr::binary(Operator::Mul, Expr::call("ln", vec![x()]), int(0));
+
+        assert_eq!(finite.simplify(&failing), failing);
+
+        let condition = Expr::binary(Operator::Lt, x(), int(2));
+        let boolean = [
+            Expr::binary(Operator::Add, condition.clone(), int(0)),
+            Expr::binary(Operator::Mul, int(1), condition.clone()),
+            Expr::binary(Operator::Pow, condition.clone(), int(1)),
+            neg(neg(condition.clone())),
+        ];
+
+        for expr in boolean {
+            assert_eq!(simplify(expr.clone()), expr);
+        }
+
+        let boolean = Expr::binary(Operator::Mul, condition, int(0));
+
+        assert_eq!(finite.simplify(&boolean), boolean);
+
+        let grams = Expr::unit(int(3), "g");
+        let zero = Expr::binary(Operator::Sub, grams.clone(), int(0));
+
+        assert_eq!(simplify(zero.clone()), zero);
+        assert_eq!(
+            simplify(Expr::binary(Operator::Mul, grams.clone(), int(1))),
+            grams
+        );
+    }
+
+    #[test]
+    fn same_result() {
+        // -(-(x * 1) + (4 - 2 * 2)) / (2 ^ 3)
+        let expr = Expr::binary(
+            Operator::Div,
+            Expr::unary(
+                Operator::Neg,
+                Expr::group(Expr::binary(
+                    Operator::Add,
+                    Expr::unary(
+                        Operator::Neg,
+                        Expr::group(Expr::binary(Operator::Mul, x(), int(1))),
+                    ),
+                    Expr::group(Expr::binary(
+                        Operator::Sub,
+                        int(4),
+                        Expr::binary(Operator::Mul, int(2), int(2)),
+                    )),
+                )),
+            ),
+            Expr::group(Expr::binary(Operator::Pow, int(2), int(3))),
+        );
+        let simplified = simplify(expr.clone());
+        let env = Environment::new().with_variable("x", 12.0);
+
+        assert_eq!(simplified, Expr::binary(Operator::Div, x(), int(8)));
+        assert_eq!(
+            Evaluator::new().eval_with(&simplified, &env),
+            Evaluator::new().eval_with(&expr, &env)
+        );
+    }
+
+    #[test]
+    fn conditionals() {
+        let float = |num| Expr::number(Number::Float(num));
+        let conditional = |condition| {
+            Expr::conditional(
+                condition,
+                Expr::binary(Operator::Mul, x(), int(1)),
+                Expr::binary(Operator::Add, int(2), int(3)),
+            )
+        };
+
+        assert_eq!(
+            simplify(conditional(Expr::binary(Operator::Lt, int(1), int(2)))),
+            x()
+        );
+
+        // exact and floating point evaluations disagree on the condition
+        let disputed = Expr::binary(
+            Operator::Eq,
+            Expr::binary(Operator::Add, float(0.1), float(0.2)),
+            float(0.3),
+        );
+        let open = Expr::binary(Operator::Gt, x(), int(0));
+
+        for condition in [disputed, open] {
+            assert_eq!(
+                simplify(conditional(condition.clone())),
+                Expr::conditional(condition, x(), int(5))
+            );
+        }
+    }
+}
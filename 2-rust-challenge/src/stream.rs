@@ -0,0 +1,547 @@
+//! Module to tokenize the input that arrives in chunks,
+//! i.e. large files or network streams, without buffering all of it
+
+use std::{
+    io::{ErrorKind, Read},
+    str,
+};
+
+use crate::{
+    dialect::Dialect,
+    span::{Span, Spanned},
+    tokens::{whitespace_len, Result, Token, TokenIterator, TokenizerError},
+};
+
+/// Number of bytes the [TokenReader] reads at once
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Tokenizer that is fed with the input piece by piece,
+/// so more tokens could be taken after every [StreamTokenizer::push].
+///
+/// Token at the end of the input is held back until the following input shows it is complete,
+/// as the chunk could split it, i.e. `12` and `3`, or until [StreamTokenizer::finish].
+/// Only the input of the tokens that are not taken yet is kept,
+/// the whitespace and the comments are dropped as soon as they are read.
+///
+/// Bytes are decoded as UTF-8 joining the chars split between the chunks,
+/// invalid sequences are replaced by `U+FFFD` and reported as the unknown token.
+/// Spans count bytes of the decoded input from its start
+#[derive(Debug, Clone)]
+pub struct StreamTokenizer<'dialect> {
+    dialect: &'dialect Dialect,
+    /// Decoded input starting from the first token that is not taken
+    buffer: String,
+    /// Bytes at the start of the buffer that are already tokenized
+    consumed: usize,
+    /// Position of the buffer start inside the whole input
+    offset: usize,
+    /// Bytes of the char split by the end of the last chunk
+    partial: Vec<u8>,
+    finished: bool,
+    expect_for_neg: bool,
+    /// Comment the input ends inside, its opening marker is consumed
+    comment: Option<Comment>,
+    /// Bytes after the start of the token that should be known to be sure it is complete,
+    /// so the longer symbol, the identifier prefix, the comment marker or the unit bracket is not cut
+    lookahead: usize,
+}
+
+impl Default for StreamTokenizer<'static> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamTokenizer<'static> {
+    /// Creates tokenizer of the input encoded with the default [Dialect::letters]
+    pub fn new() -> Self {
+        Self::with_dialect(Dialect::default_ref())
+    }
+}
+
+impl<'dialect> StreamTokenizer<'dialect> {
+    pub fn with_dialect(dialect: &'dialect Dialect) -> Self {
+        let prefix = dialect.identifier_prefix().map_or(0, str::len);
+        let marker = [
+            dialect.line_comment(),
+            dialect.block_comment().map(|(open, _)| open),
+            dialect.unit_brackets().map(|(open, _)| open),
+        ]
+        .into_iter()
+        .flatten()
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+
+        Self {
+            dialect,
+            buffer: String::new(),
+            consumed: 0,
+            offset: 0,
+            partial: vec![],
+            finished: false,
+            expect_for_neg: true,
+            comment: None,
+            lookahead: dialect.longest_symbol().max(prefix + 1).max(marker),
+        }
+    }
+
+    /// Appends the bytes to the input, that should not be finished
+    pub fn push(&mut self, bytes: &[u8]) {
+        assert!(!self.finished, "input is finished");
+
+        let joined;
+        let mut bytes = bytes;
+
+        if !self.partial.is_empty() {
+            self.partial.extend_from_slice(bytes);
+            joined = std::mem::take(&mut self.partial);
+            bytes = &joined;
+        }
+
+        self.compact();
+
+        loop {
+            match str::from_utf8(bytes) {
+                Ok(text) => return self.buffer.push_str(text),
+                Err(error) => {
+                    let (valid, rest) = bytes.split_at(error.valid_up_to());
+
+                    self.buffer
+                        .push_str(str::from_utf8(valid).expect("bytes are valid up to the error"));
+
+                    match error.error_len() {
+                        Some(len) => {
+                            self.buffer.push(char::REPLACEMENT_CHARACTER);
+                            bytes = &rest[len..];
+                        }
+                        // the rest of the char comes with the next chunk
+                        None => return self.partial.extend_from_slice(rest),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Appends the text to the input
+    pub fn push_str(&mut self, text: &str) {
+        self.push(text.as_bytes())
+    }
+
+    /// Marks the end of the input, so the tokens held back could be taken
+    pub fn finish(&mut self) {
+        if !self.partial.is_empty() {
+            self.partial.clear();
+            self.buffer.push(char::REPLACEMENT_CHARACTER);
+        }
+
+        self.finished = true;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Number of bytes of the decoded input so far
+    pub fn position(&self) -> usize {
+        self.offset + self.buffer.len()
+    }
+
+    /// Consumes the whitespace and the comments before the token, returns whether the token
+    /// could follow or more input is needed to find the end of the comment.
+    /// Block comment without the closing marker is reported once the input is finished
+    fn skip_trivia(&mut self) -> Result<bool> {
+        loop {
+            let rest = &self.buffer[self.consumed..];
+
+            match self.comment {
+                Some(Comment::Line) => match rest.find('\n') {
+                    Some(len) => {
+                        self.consumed += len;
+                        self.comment = None;
+                    }
+                    None => {
+                        self.consumed = self.buffer.len();
+
+                        return Ok(self.finished);
+                    }
+                },
+                Some(Comment::Block { start }) => {
+                    let (open, close) = self
+                        .dialect
+                        .block_comment()
+                        .expect("comment is opened by the dialect marker");
+
+                    if let Some(len) = rest.find(close) {
+                        self.consumed += len + close.len();
+                        self.comment = None;
+                    } else if self.finished {
+                        self.consumed = self.buffer.len();
+                        self.comment = None;
+
+                        return Err(TokenizerError::UnclosedComment {
+                            span: Span::new(start, start + open.len()),
+                        });
+                    } else {
+                        // closing marker could be split by the end of the input
+                        let mut len = rest.len().saturating_sub(close.len() - 1);
+
+                        while !rest.is_char_boundary(len) {
+                            len -= 1;
+                        }
+
+                        self.consumed += len;
+
+                        return Ok(false);
+                    }
+                }
+                None => {
+                    let whitespace = whitespace_len(rest);
+                    let rest = &rest[whitespace..];
+                    let start = self.offset + self.consumed + whitespace;
+
+                    self.consumed += whitespace;
+
+                    if let Some(marker) = self
+                        .dialect
+                        .line_comment()
+                        .filter(|marker| rest.starts_with(marker))
+                    {
+                        self.consumed += marker.len();
+                        self.comment = Some(Comment::Line);
+                    } else if let Some((open, _)) = self
+                        .dialect
+                        .block_comment()
+                        .filter(|(open, _)| rest.starts_with(open))
+                    {
+                        self.consumed += open.len();
+                        self.comment = Some(Comment::Block { start });
+                    } else {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drops the input of the tokens that are taken
+    fn compact(&mut self) {
+        self.buffer.drain(..self.consumed);
+        self.offset += self.consumed;
+        self.consumed = 0;
+    }
+}
+
+/// Yields the tokens that are complete, `None` means more input is needed
+/// unless the input is finished
+impl Iterator for StreamTokenizer<'_> {
+    type Item = Result<Spanned<Token>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.skip_trivia() {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(error) => return Some(Err(error)),
+        }
+
+        let rest = &self.buffer[self.consumed..];
+        let mut tokens = TokenIterator::with_dialect(rest, self.dialect)
+            .with_expected_operand(self.expect_for_neg);
+
+        // the rest could be the start of the comment marker that is completed by the following input
+        let result = tokens.next()?;
+
+        let span = match &result {
+            Ok(token) => token.span,
+            Err(error) => error.span(),
+        };
+
+        // error of the number spans only its wrong char, so the end is taken from the iterator
+        let end = tokens.position();
+
+        if !self.finished && (end >= rest.len() || span.start + self.lookahead > rest.len()) {
+            return None;
+        }
+
+        let offset = self.offset + self.consumed;
+
+        self.consumed += end;
+        self.expect_for_neg = tokens.expects_operand();
+
+        Some(match result {
+            Ok(token) => Ok(Spanned::new(token.node, shift(token.span, offset))),
+            Err(error) => Err(match error {
+                TokenizerError::UnknownToken { token, span } => TokenizerError::UnknownToken {
+                    token,
+                    span: shift(span, offset),
+                },
+                TokenizerError::NumberParseError { kind, span } => {
+                    TokenizerError::NumberParseError {
+                        kind,
+                        span: shift(span, offset),
+                    }
+                }
+                TokenizerError::ReadError { kind, span } => TokenizerError::ReadError {
+                    kind,
+                    span: shift(span, offset),
+                },
+                TokenizerError::UnclosedComment { span } => TokenizerError::UnclosedComment {
+                    span: shift(span, offset),
+                },
+                TokenizerError::UnclosedUnit { span } => TokenizerError::UnclosedUnit {
+                    span: shift(span, offset),
+                },
+            }),
+        })
+    }
+}
+
+/// Comment that continues with the following input
+#[derive(Debug, Clone, Copy)]
+enum Comment {
+    Line,
+    /// Block comment opened at the position
+    Block {
+        start: usize,
+    },
+}
+
+fn shift(span: Span, offset: usize) -> Span {
+    Span::new(span.start + offset, span.end + offset)
+}
+
+/// Tokenizer that reads the input from the source in chunks as the tokens are taken,
+/// i.e. from the file or the socket. The source doesn't need to be buffered,
+/// as the reader has its own buffer. Failure of the source ends the tokens with the error
+pub struct TokenReader<'dialect, R> {
+    reader: R,
+    tokenizer: StreamTokenizer<'dialect>,
+    chunk: Vec<u8>,
+    failed: bool,
+}
+
+impl<R: Read> TokenReader<'static, R> {
+    /// Creates reader of the input encoded with the default [Dialect::letters]
+    pub fn new(reader: R) -> Self {
+        Self::with_dialect(reader, Dialect::default_ref())
+    }
+}
+
+impl<'dialect, R: Read> TokenReader<'dialect, R> {
+    pub fn with_dialect(reader: R, dialect: &'dialect Dialect) -> Self {
+        Self {
+            reader,
+            tokenizer: StreamTokenizer::with_dialect(dialect),
+            chunk: vec![0; CHUNK_SIZE],
+            failed: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for TokenReader<'_, R> {
+    type Item = Result<Spanned<Token>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            if let Some(token) = self.tokenizer.next() {
+                return Some(token);
+            }
+
+            if self.tokenizer.is_finished() {
+                return None;
+            }
+
+            match self.reader.read(&mut self.chunk) {
+                Ok(0) => self.tokenizer.finish(),
+                Ok(len) => self.tokenizer.push(&self.chunk[..len]),
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.failed = true;
+
+                    return Some(Err(TokenizerError::ReadError {
+                        kind: error.kind(),
+                        span: Span::point(self.tokenizer.position()),
+                    }));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::tokens::{Number, Operator};
+
+    fn collect(
+        tokens: impl Iterator<Item = Result<Spanned<Token>>>,
+    ) -> Vec<Result<Spanned<Token>>> {
+        tokens.collect()
+    }
+
+    /// Feeds the chunks taking the tokens after each of them
+    fn feed(chunks: &[&[u8]], dialect: &Dialect) -> Vec<Result<Spanned<Token>>> {
+        let mut tokenizer = StreamTokenizer::with_dialect(dialect);
+        let mut tokens = vec![];
+
+        for chunk in chunks {
+            tokenizer.push(chunk);
+            tokens.extend(tokenizer.by_ref());
+        }
+
+        tokenizer.finish();
+        tokens.extend(tokenizer);
+        tokens
+    }
+
+    #[test]
+    fn split_anywhere() {
+        let dialect = Dialect::standard()
+            .with_symbol("×", Operator::Mul)
+            .unwrap()
+            .with_symbol("**", Operator::Pow)
+            .unwrap()
+            .with_symbol("plus", Operator::Add)
+            .unwrap();
+        let source =
+            "12.5 × pl ** -(3 plus plum) ÷ 2.5.1, € 1_0e-2 0x1F 0b12\n// note\u{a0}\n3E /* a\n */ 4 /* b";
+        let expected = collect(TokenIterator::with_dialect(source, &dialect));
+
+        for split in 0..=source.len() {
+            let (head, tail) = source.as_bytes().split_at(split);
+
+            assert_eq!(feed(&[head, tail], &dialect), expected, "split at {split}");
+        }
+
+        let bytes = source.bytes().map(|byte| [byte]).collect::<Vec<_>>();
+        let chunks = bytes.iter().map(|byte| byte.as_slice()).collect::<Vec<_>>();
+
+        assert_eq!(feed(&chunks, &dialect), expected);
+    }
+
+    #[test]
+    fn incremental() {
+        let mut tokenizer = StreamTokenizer::new();
+
+        tokenizer.push_str("1 a 2");
+
+        assert_eq!(
+            collect(tokenizer.by_ref()),
+            vec![
+                Ok(Spanned::new(Token::Number(Number::Int(1.into())), 0..1)),
+                Ok(Spanned::new(Token::Operator(Operator::Add), 2..3))
+            ]
+        );
+
+        tokenizer.push_str("3 c b");
+
+        assert_eq!(
+            collect(tokenizer.by_ref()),
+            vec![
+                Ok(Spanned::new(Token::Number(Number::Int(23.into())), 4..6)),
+                Ok(Spanned::new(Token::Operator(Operator::Mul), 7..8))
+            ]
+        );
+
+        tokenizer.finish();
+
+        assert_eq!(
+            collect(tokenizer.by_ref()),
+            vec![Ok(Spanned::new(Token::Operator(Operator::Neg), 9..10))]
+        );
+        assert!(tokenizer.is_finished());
+        assert_eq!(tokenizer.position(), 10);
+    }
+
+    #[test]
+    fn long_trivia() {
+        let dialect = Dialect::standard();
+        let mut tokenizer = StreamTokenizer::with_dialect(&dialect);
+        let chunks = [" ".repeat(1024), "\t\n".repeat(512), "/* ".to_owned()]
+            .into_iter()
+            .chain(std::iter::repeat("comment ".repeat(128)).take(1024))
+            .chain(["*/ // note".to_owned(), " ".repeat(1024), "\n7".to_owned()]);
+
+        for chunk in chunks {
+            tokenizer.push_str(&chunk);
+
+            assert_eq!(tokenizer.next(), None);
+            // trivia is dropped as it is read instead of waiting for the token
+            assert!(tokenizer.buffer.len() - tokenizer.consumed < 16);
+        }
+
+        tokenizer.finish();
+
+        let end = tokenizer.position();
+
+        assert_eq!(
+            collect(tokenizer),
+            vec![Ok(Spanned::new(
+                Token::Number(Number::Int(7.into())),
+                end - 1..end
+            ))]
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let unknown = |span: std::ops::Range<usize>| {
+            Err(TokenizerError::UnknownToken {
+                token: char::REPLACEMENT_CHARACTER,
+                span: span.into(),
+            })
+        };
+
+        assert_eq!(
+            feed(&[b"1\xff", b"2 \xe2\x82"], Dialect::default_ref()),
+            vec![
+                Ok(Spanned::new(Token::Number(Number::Int(1.into())), 0..1)),
+                unknown(1..4),
+                Ok(Spanned::new(Token::Number(Number::Int(2.into())), 4..5)),
+                unknown(6..9),
+            ]
+        );
+    }
+
+    /// Source that fails after the data
+    struct Failing<'data>(&'data [u8]);
+
+    impl Read for Failing<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::ErrorKind::ConnectionReset.into());
+            }
+
+            let len = self.0.len().min(buf.len());
+
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn reader() {
+        let source = "2 a 3 c e4 b 1f ".repeat(2000);
+
+        assert_eq!(
+            collect(TokenReader::new(source.as_bytes())),
+            collect(TokenIterator::new(&source))
+        );
+        assert_eq!(
+            collect(TokenReader::new(Failing(b"12 a"))),
+            vec![
+                Ok(Spanned::new(Token::Number(Number::Int(12.into())), 0..2)),
+                Err(TokenizerError::ReadError {
+                    kind: io::ErrorKind::ConnectionReset,
+                    span: Span::point(4)
+                })
+            ]
+        );
+    }
+}
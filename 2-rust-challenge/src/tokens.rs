@@ -1,68 +1,107 @@
 //! Module to parse string into stream of tokens
 //! i.e. operands, operators and brackets
 
-use std::{
-    cmp::Ordering,
-    error::Error,
-    fmt::Display,
-    num::{ParseFloatError, ParseIntError},
-    str::Chars,
+use std::{borrow::Cow, error::Error, fmt::Display, io, num::ParseFloatError};
+
+use crate::{
+    bigint::{BigInt, ParseBigIntError},
+    dialect::{Dialect, Symbol},
+    span::{Span, Spanned},
 };
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
 pub enum Operator {
     Neg,
     Add,
     Sub,
     Mul,
     Div,
+    /// Remainder of the division
+    Mod,
+    Pow,
+    /// Comparisons yield the boolean
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Logical operators take and yield the booleans,
+    /// the second operand is skipped when the first one decides the result
+    And,
+    Or,
+    Not,
 }
 
-/// Priority of the operation
-type Priority = u8;
-
 /// Number of arguments used by operation
 pub type Arity = u8;
 
-fn get_operator_priority(operator: &Operator) -> Priority {
-    match operator {
-        Operator::Neg => 0,
-        // There is no difference in priority for operations
-        _ => 1,
-    }
-}
-
 impl Operator {
     pub fn arity(&self) -> Arity {
         match self {
-            Operator::Neg => 1,
+            Operator::Neg | Operator::Not => 1,
             _ => 2,
         }
     }
-}
 
-impl Ord for Operator {
-    fn cmp(&self, other: &Self) -> Ordering {
-        get_operator_priority(self).cmp(&get_operator_priority(other))
+    /// Comparison of the numbers that yields the boolean
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::Eq | Operator::Ne | Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge
+        )
     }
-}
 
-impl PartialOrd for Operator {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    /// Operator that takes and yields the booleans
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Operator::And | Operator::Or | Operator::Not)
+    }
+
+    /// Unary operator that shares the same symbol,
+    /// i.e. negation for subtraction
+    pub fn unary(&self) -> Option<Operator> {
+        match self {
+            Operator::Sub => Some(Operator::Neg),
+            _ => None,
+        }
     }
 }
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Group {
     Open,
     Close,
 }
 
+/// Parts of the conditional expression, i.e. `?` and `:` in `a > b ? a : b`
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Branch {
+    Then,
+    Else,
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Number {
-    Int(i32),
+    /// Integer of any size, so long literals are not truncated
+    Int(BigInt),
     Float(f64),
+    /// Decimal literal with more significant digits than `f64` holds, kept as its text
+    /// without the separators, i.e. `0.1000000000000000000001`, so the exact numbers get all digits
+    Decimal(String),
+    /// Coefficient of the imaginary unit, i.e. `2.5` for `2.5i`
+    Imaginary(f64),
+}
+
+impl Number {
+    /// Real value of the literal, which is NaN for the imaginary one
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Self::Int(num) => num.to_f64(),
+            Self::Float(num) => *num,
+            Self::Decimal(digits) => digits.parse().expect("digits are checked by the tokenizer"),
+            Self::Imaginary(_) => f64::NAN,
+        }
+    }
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -70,35 +109,103 @@ pub enum Token {
     Operator(Operator),
     Group(Group),
     Number(Number),
+    /// Name of the function or variable
+    Identifier(String),
+    /// Separator of the function arguments
+    Separator,
+    Branch(Branch),
+    /// Unit of the preceding operand written between the unit brackets, i.e. `kg` in `5[kg]`
+    Unit(String),
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum NumberParseErrorKind {
-    Int(ParseIntError),
+    Int(ParseBigIntError),
     Float(ParseFloatError),
+    /// Char that is not the digit of the number radix, i.e. `2` in `0b12`
+    InvalidDigit {
+        digit: char,
+        radix: u32,
+    },
+    /// Digit separator `_` that is not between two digits
+    MisplacedSeparator,
+    /// Second decimal point or the point in the exponent or non-decimal number
+    UnexpectedPoint,
+    /// Radix prefix or exponent marker that is not followed by digits, or the lone point
+    MissingDigits,
+}
+
+impl Display for NumberParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Int(err) => write!(f, "{err}"),
+            Self::Float(err) => write!(f, "{err}"),
+            Self::InvalidDigit { digit, radix } => {
+                write!(f, "`{digit}` is not a digit of the base {radix}")
+            }
+            Self::MisplacedSeparator => write!(f, "digit separator should be between digits"),
+            Self::UnexpectedPoint => write!(f, "unexpected decimal point"),
+            Self::MissingDigits => write!(f, "digits are missing"),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum TokenizerError {
-    UnknownToken(char),
-    NumberParseError { kind: NumberParseErrorKind },
+    UnknownToken {
+        token: char,
+        span: Span,
+    },
+    NumberParseError {
+        kind: NumberParseErrorKind,
+        span: Span,
+    },
+    /// Source of the streamed input has failed
+    ReadError {
+        kind: io::ErrorKind,
+        span: Span,
+    },
+    /// Block comment lasts until the end of the input, spans its opening marker
+    UnclosedComment {
+        span: Span,
+    },
+    /// Unit bracket lasts until the end of the input, spans its opening marker
+    UnclosedUnit {
+        span: Span,
+    },
+}
+
+impl TokenizerError {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::UnknownToken { span, .. }
+            | Self::NumberParseError { span, .. }
+            | Self::ReadError { span, .. }
+            | Self::UnclosedComment { span }
+            | Self::UnclosedUnit { span } => *span,
+        }
+    }
 }
 
 impl Display for TokenizerError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::UnknownToken(token) => write!(f, "Unknown token `{token}` in the stream"),
-            Self::NumberParseError { .. } => write!(f, "Unable to parse number"),
+            Self::UnknownToken { token, .. } => write!(f, "Unknown token `{token}` in the stream"),
+            Self::NumberParseError { kind, .. } => write!(f, "Unable to parse number, {kind}"),
+            Self::ReadError { kind, .. } => write!(f, "Unable to read the input: {kind}"),
+            Self::UnclosedComment { .. } => write!(f, "Comment is not closed"),
+            Self::UnclosedUnit { .. } => write!(f, "Unit is not closed"),
         }
     }
 }
 
 impl Error for TokenizerError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        if let TokenizerError::NumberParseError { kind } = self {
+        if let TokenizerError::NumberParseError { kind, .. } = self {
             match kind {
                 NumberParseErrorKind::Int(err) => Some(err),
                 NumberParseErrorKind::Float(err) => Some(err),
+                _ => None,
             }
         } else {
             None
@@ -106,122 +213,599 @@ impl Error for TokenizerError {
     }
 }
 
-impl From<ParseIntError> for TokenizerError {
-    fn from(value: ParseIntError) -> Self {
-        Self::NumberParseError {
-            kind: NumberParseErrorKind::Int(value),
-        }
+impl From<ParseBigIntError> for NumberParseErrorKind {
+    fn from(value: ParseBigIntError) -> Self {
+        Self::Int(value)
     }
 }
 
-impl From<ParseFloatError> for TokenizerError {
+impl From<ParseFloatError> for NumberParseErrorKind {
     fn from(value: ParseFloatError) -> Self {
-        Self::NumberParseError {
-            kind: NumberParseErrorKind::Float(value),
-        }
+        Self::Float(value)
     }
 }
 
 pub type Result<T> = std::result::Result<T, TokenizerError>;
 
-pub struct TokenIterator<'stream> {
-    stream: Chars<'stream>,
-    input: Option<char>,
+/// Token that borrows its text from the source, so it is produced without any allocation
+#[derive(PartialEq, Debug, Clone)]
+pub enum RawToken<'stream> {
+    Operator(Operator),
+    Group(Group),
+    /// Text of the integer literal, i.e. `0x1F` or `1_000`
+    Int(&'stream str),
+    /// Value of the decimal literal along with its text, i.e. `1_000.5E-3`
+    Float {
+        value: f64,
+        literal: &'stream str,
+    },
+    /// Coefficient of the imaginary literal, i.e. `2.5` for `2.5i`
+    Imaginary(f64),
+    /// Name of the function or variable without the identifier prefix
+    Identifier(&'stream str),
+    Separator,
+    Branch(Branch),
+    /// Text of the unit without the brackets and the surrounding whitespace
+    Unit(&'stream str),
+}
+
+impl RawToken<'_> {
+    /// Owned token, that allocates for the names and the integers
+    pub fn to_token(&self) -> Token {
+        match self {
+            Self::Operator(operator) => Token::Operator(operator.clone()),
+            Self::Group(group) => Token::Group(group.clone()),
+            Self::Int(literal) => Token::Number(Number::Int(int_value(literal))),
+            Self::Float { value, literal } => Token::Number(float_value(*value, literal)),
+            Self::Imaginary(num) => Token::Number(Number::Imaginary(*num)),
+            Self::Identifier(name) => Token::Identifier(name.to_string()),
+            Self::Separator => Token::Separator,
+            Self::Branch(branch) => Token::Branch(branch.clone()),
+            Self::Unit(unit) => Token::Unit(unit.to_string()),
+        }
+    }
+}
+
+/// Value of the integer literal that is checked by the tokenizer,
+/// the literals of 64 bits are read without any allocation
+fn int_value(literal: &str) -> BigInt {
+    let (radix, digits) = match literal.as_bytes() {
+        [b'0', b'x' | b'X', ..] => (16, &literal[2..]),
+        [b'0', b'b' | b'B', ..] => (2, &literal[2..]),
+        _ => (10, literal),
+    };
+    let small = digits
+        .chars()
+        .filter(|input| *input != '_')
+        .try_fold(0u64, |value, digit| {
+            value
+                .checked_mul(u64::from(radix))?
+                .checked_add(u64::from(digit.to_digit(radix)?))
+        });
+
+    if let Some(value) = small {
+        return BigInt::from(value);
+    }
+
+    let digits = match digits.contains('_') {
+        true => Cow::Owned(digits.replace('_', "")),
+        false => Cow::Borrowed(digits),
+    };
+
+    BigInt::from_str_radix(&digits, radix).expect("digits are checked by the tokenizer")
+}
+
+/// Length of any Unicode whitespace at the start of the input, the ASCII one is checked by bytes
+pub(crate) fn whitespace_len(input: &str) -> usize {
+    let bytes = input.as_bytes();
+    let mut len = 0;
+
+    while let Some(&byte) = bytes.get(len) {
+        match byte {
+            b' ' | b'\t' | b'\n' | b'\r' | b'\x0b' | b'\x0c' => len += 1,
+            _ if byte.is_ascii() => break,
+            _ => match input[len..].chars().next() {
+                Some(input) if input.is_whitespace() => len += input.len_utf8(),
+                _ => break,
+            },
+        }
+    }
+
+    len
+}
+
+/// Number of the decimal literal that is checked by the tokenizer, the literals
+/// with up to 15 significant digits are kept as `f64`, as it holds all their digits
+fn float_value(value: f64, literal: &str) -> Number {
+    let mantissa = literal
+        .split(|input: char| input.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default();
+    let digits = mantissa
+        .bytes()
+        .filter(u8::is_ascii_digit)
+        .skip_while(|digit| *digit == b'0')
+        .count();
+
+    if digits <= f64::DIGITS as usize && (value == 0.0 || value.is_normal()) {
+        return Number::Float(value);
+    }
+
+    // custom exponent marker is the only letter of the decimal literal
+    Number::Decimal(
+        literal
+            .chars()
+            .filter(|input| *input != '_')
+            .map(|input| {
+                if input.is_ascii_alphabetic() {
+                    'e'
+                } else {
+                    input
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Finds the first char of the number literal that breaks its syntax,
+/// returns the kind of the error along with its range inside the literal
+fn check_literal(
+    literal: &[u8],
+    start: usize,
+    radix: u32,
+    exponent: Option<usize>,
+) -> std::result::Result<(), (NumberParseErrorKind, usize, usize)> {
+    let is_digit = |index: usize| {
+        literal
+            .get(index)
+            .is_some_and(|byte| char::from(*byte).is_digit(radix))
+    };
+    let mut point = false;
+    let mut index = start;
+
+    while index < literal.len() {
+        if Some(index) == exponent {
+            let sign = matches!(literal.get(index + 1), Some(b'+' | b'-'));
+            let end = index + 1 + usize::from(sign);
+
+            if end == literal.len() {
+                return Err((NumberParseErrorKind::MissingDigits, index, end));
+            }
+
+            index = end;
+            continue;
+        }
+
+        match literal[index] {
+            b'_' if index == 0 || !is_digit(index - 1) || !is_digit(index + 1) => {
+                return Err((NumberParseErrorKind::MisplacedSeparator, index, index + 1))
+            }
+            b'.' if radix != 10 || point || exponent.is_some_and(|marker| index > marker) => {
+                return Err((NumberParseErrorKind::UnexpectedPoint, index, index + 1))
+            }
+            b'.' => point = true,
+            b'_' => {}
+            _ if is_digit(index) => {}
+            byte => {
+                return Err((
+                    NumberParseErrorKind::InvalidDigit {
+                        digit: char::from(byte),
+                        radix,
+                    },
+                    index,
+                    index + 1,
+                ))
+            }
+        }
+
+        index += 1;
+    }
+
+    let mantissa = exponent.unwrap_or(literal.len());
+
+    if !(start..mantissa).any(is_digit) {
+        return Err((NumberParseErrorKind::MissingDigits, 0, mantissa));
+    }
+
+    Ok(())
+}
+
+impl From<&Symbol> for RawToken<'_> {
+    fn from(symbol: &Symbol) -> Self {
+        match symbol {
+            Symbol::Operator(operator) => Self::Operator(operator.clone()),
+            Symbol::Group(group) => Self::Group(group.clone()),
+            Symbol::Separator => Self::Separator,
+            Symbol::Branch(branch) => Self::Branch(branch.clone()),
+        }
+    }
+}
+
+/// Tokenizer that scans the bytes of the source and yields [RawToken] slices of it,
+/// i.e. for the batch validation of the sources that doesn't need the owned tokens
+#[derive(Debug, Clone)]
+pub struct RawTokenIterator<'stream> {
+    stream: &'stream str,
+    position: usize,
+    dialect: &'stream Dialect,
     expect_for_neg: bool,
+    detect_unary: bool,
 }
 
-impl<'stream> TokenIterator<'stream> {
-    pub fn new(stream: Chars<'stream>) -> Self {
+impl<'stream> RawTokenIterator<'stream> {
+    /// Creates iterator over the stream encoded with the default [Dialect::letters]
+    pub fn new(stream: &'stream str) -> Self {
+        Self::with_dialect(stream, Dialect::default_ref())
+    }
+
+    pub fn with_dialect(stream: &'stream str, dialect: &'stream Dialect) -> Self {
         Self {
             stream,
-            input: None,
+            position: 0,
+            dialect,
             expect_for_neg: true,
+            detect_unary: true,
         }
     }
 
+    /// Turns the operator into its unary counterpart when the operand is expected, on by default.
+    /// Polish notations write the unary operators with their own symbols,
+    /// as the position of the operator doesn't tell its arity there
+    pub fn with_unary_detection(mut self, detect_unary: bool) -> Self {
+        self.detect_unary = detect_unary;
+        self
+    }
+
+    fn rest(&self) -> &'stream str {
+        &self.stream[self.position..]
+    }
+
     fn exhaust_whitespace(&mut self) {
-        for input in self.stream.by_ref() {
-            if input != ' ' {
-                self.input = Some(input);
+        self.position += whitespace_len(self.rest());
+    }
+
+    /// Skips the whitespace and the comments before the token,
+    /// the block comment without the closing marker takes the rest of the input
+    fn exhaust_trivia(&mut self) -> Result<()> {
+        loop {
+            self.exhaust_whitespace();
+
+            let rest = self.rest().as_bytes();
+            // first byte rejects most of the tokens before the markers are compared
+            let starts = |marker: &str| {
+                rest.first() == marker.as_bytes().first() && rest.starts_with(marker.as_bytes())
+            };
 
-                return;
+            if let Some(marker) = self.dialect.line_comment().filter(|marker| starts(marker)) {
+                // line break is skipped as the whitespace
+                self.position += rest[marker.len()..]
+                    .iter()
+                    .position(|byte| *byte == b'\n')
+                    .map_or(rest.len(), |len| marker.len() + len);
+            } else if let Some((open, close)) = self
+                .dialect
+                .block_comment()
+                .filter(|(open, _)| starts(open))
+            {
+                let start = self.position;
+
+                match self.rest()[open.len()..].find(close) {
+                    Some(len) => self.position += open.len() + len + close.len(),
+                    None => {
+                        self.position = self.stream.len();
+
+                        return Err(TokenizerError::UnclosedComment {
+                            span: Span::new(start, start + open.len()),
+                        });
+                    }
+                }
+            } else {
+                return Ok(());
             }
         }
     }
 
-    fn exhaust_number(&mut self, first_digit: char) -> Result<Number> {
-        let mut had_dot = first_digit == '.';
-        let mut digits = vec![first_digit];
+    /// Reads the unit between the unit brackets of the dialect at the start of the input,
+    /// returns its length in bytes along with the text or `None` if there are no brackets
+    fn match_unit(&self, input: &'stream str) -> Option<Result<(usize, &'stream str)>> {
+        let (open, close) = self.dialect.unit_brackets()?;
 
-        for input in self.stream.by_ref() {
-            match input {
-                '0'..='9' => digits.push(input),
-                '.' => {
-                    had_dot = true;
-                    digits.push(input);
-                }
-                _ => {
-                    self.input = Some(input);
-                    break;
+        // first byte rejects most of the tokens before the markers are compared
+        if input.as_bytes().first() != open.as_bytes().first()
+            || !input.as_bytes().starts_with(open.as_bytes())
+        {
+            return None;
+        }
+
+        Some(match input[open.len()..].find(close) {
+            Some(len) => Ok((
+                open.len() + len + close.len(),
+                input[open.len()..open.len() + len].trim(),
+            )),
+            None => Err(TokenizerError::UnclosedUnit {
+                span: Span::new(self.position, self.position + open.len()),
+            }),
+        })
+    }
+
+    /// Finds the name at the start of the input, that begins with
+    /// the identifier prefix of the dialect if there is any.
+    /// Returns the length in bytes along with the name itself
+    fn match_identifier(&self, input: &'stream str) -> Option<(usize, &'stream str)> {
+        let prefix = self.dialect.identifier_prefix().unwrap_or_default();
+
+        // bytes are compared directly, as the `str` pattern builds the searcher for every call
+        if !input.as_bytes().starts_with(prefix.as_bytes()) {
+            return None;
+        }
+
+        let name = &input[prefix.len()..];
+        let mut chars = name.char_indices();
+
+        if !chars
+            .next()
+            .is_some_and(|(_, first)| first.is_alphabetic() || first == '_')
+        {
+            return None;
+        }
+
+        let len = chars
+            .find(|(_, input)| !(input.is_alphanumeric() || *input == '_'))
+            .map_or(name.len(), |(position, _)| position);
+
+        Some((prefix.len() + len, &name[..len]))
+    }
+
+    /// Radix of the integer literal that starts with `0x` or `0b`,
+    /// unless the letter is the symbol of the dialect, i.e. `0b1` is `0 - 1` in the letters
+    fn radix_prefix(&self, input: &str) -> Option<u32> {
+        let radix = match input.as_bytes() {
+            [b'0', b'x' | b'X', ..] => 16,
+            [b'0', b'b' | b'B', ..] => 2,
+            _ => return None,
+        };
+
+        self.dialect
+            .match_symbol(&input[1..])
+            .is_none()
+            .then_some(radix)
+    }
+
+    /// Whether the literal ends right before the input, which is the symbol
+    /// or the char that could not be inside the literal
+    fn ends_literal(&self, input: &str) -> bool {
+        match input.as_bytes().first() {
+            Some(&byte) if byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.') => {
+                self.dialect.match_symbol(input).is_some()
+            }
+            _ => true,
+        }
+    }
+
+    /// Parses the number right from the slice of the source, integers are kept as text.
+    /// Literal lasts over the alphanumeric chars, so the wrong digits are reported inside it,
+    /// until the symbol of the dialect, i.e. `0x1Fa2` is `0x1F + 2` in the letters.
+    /// Decimal literal followed by the imaginary suffix is the imaginary number, i.e. `2i`
+    fn exhaust_number(&mut self) -> Result<Spanned<RawToken<'stream>>> {
+        let rest = self.rest();
+        let bytes = rest.as_bytes();
+        let radix = self.radix_prefix(rest);
+        let start = if radix.is_some() { 2 } else { 0 };
+        let mut len = start;
+        let mut exponent = None;
+        let mut imaginary = false;
+        // decimal digits alone need no checks
+        let mut plain = radix.is_none();
+
+        while let Some(&byte) = bytes.get(len) {
+            if byte.is_ascii_digit() {
+                len += 1;
+                continue;
+            }
+
+            if !(byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.')) {
+                break;
+            }
+
+            if radix.is_none() && exponent.is_none() && self.dialect.is_exponent_marker(byte) {
+                exponent = Some(len);
+                len += 1;
+
+                // sign of the exponent is not the operator
+                if matches!(bytes.get(len), Some(b'+' | b'-')) {
+                    len += 1;
                 }
+
+                continue;
             }
+
+            if radix.is_none()
+                && self.dialect.is_imaginary_suffix(byte)
+                && self.ends_literal(&rest[len + 1..])
+            {
+                imaginary = true;
+                break;
+            }
+
+            if self.dialect.match_symbol(&rest[len..]).is_some() {
+                break;
+            }
+
+            plain = false;
+            len += 1;
         }
 
-        let digits = String::from_iter(digits);
+        let literal = &rest[..len];
+        let bytes = literal.as_bytes();
+        let position = self.position;
+        let span = Span::new(position, position + len + usize::from(imaginary));
 
-        if had_dot {
-            Ok(Number::Float(digits.parse::<f64>()?))
-        } else {
-            Ok(Number::Int(digits.parse::<i32>()?))
+        self.position = span.end;
+
+        if plain && exponent.is_none() && !imaginary {
+            return Ok(Spanned::new(RawToken::Int(literal), span));
+        }
+
+        if let Err((kind, start, end)) = check_literal(bytes, start, radix.unwrap_or(10), exponent)
+        {
+            return Err(TokenizerError::NumberParseError {
+                kind,
+                span: Span::new(position + start, position + end),
+            });
+        }
+
+        if !imaginary && (radix.is_some() || (exponent.is_none() && !bytes.contains(&b'.'))) {
+            return Ok(Spanned::new(RawToken::Int(literal), span));
+        }
+
+        // only the separators and the custom markers need the copy of the literal
+        let normalized = match exponent.map(|marker| bytes[marker]) {
+            None | Some(b'e' | b'E') if !bytes.contains(&b'_') => Cow::Borrowed(literal),
+            _ => Cow::Owned(
+                literal
+                    .char_indices()
+                    .filter(|(_, input)| *input != '_')
+                    .map(|(index, input)| if Some(index) == exponent { 'e' } else { input })
+                    .collect(),
+            ),
+        };
+
+        match normalized.parse::<f64>() {
+            Ok(number) if imaginary => Ok(Spanned::new(RawToken::Imaginary(number), span)),
+            Ok(value) => Ok(Spanned::new(RawToken::Float { value, literal }, span)),
+            Err(error) => Err(TokenizerError::NumberParseError {
+                kind: error.into(),
+                span,
+            }),
         }
     }
 }
 
-impl<'stream> Iterator for TokenIterator<'stream> {
-    type Item = Result<Token>;
+impl<'stream> Iterator for RawTokenIterator<'stream> {
+    type Item = Result<Spanned<RawToken<'stream>>>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.input.is_none() || self.input == Some(' ') {
-            self.exhaust_whitespace();
+        if let Err(error) = self.exhaust_trivia() {
+            return Some(Err(error));
         }
 
-        let Some(input) = self.input.take() else {
-            return None;
-        };
+        let position = self.position;
+        let rest = self.rest();
+        let first = *rest.as_bytes().first()?;
 
-        let result = match input {
-            'a' => Token::Operator(Operator::Add),
-            'b' => {
-                if self.expect_for_neg {
-                    Token::Operator(Operator::Neg)
-                } else {
-                    Token::Operator(Operator::Sub)
+        let symbol = self.dialect.match_symbol(rest);
+        let identifier = self.match_identifier(rest);
+
+        let result = if let Some(unit) = self.match_unit(rest) {
+            match unit {
+                Ok((len, unit)) => {
+                    self.position += len;
+
+                    RawToken::Unit(unit)
+                }
+                Err(error) => {
+                    // the rest of the input is taken, as there is no end of the unit
+                    self.position = self.stream.len();
+
+                    return Some(Err(error));
                 }
             }
-            'c' => Token::Operator(Operator::Mul),
-            'd' => Token::Operator(Operator::Div),
-            'e' => Token::Group(Group::Open),
-            'f' => Token::Group(Group::Close),
-            '0'..='9' | '.' => match self.exhaust_number(input) {
-                Ok(num) => Token::Number(num),
+        } else if let Some((len, name)) =
+            identifier.filter(|(len, _)| symbol.map_or(true, |(symbol_len, _)| *len > symbol_len))
+        {
+            self.position += len;
+
+            RawToken::Identifier(name)
+        } else if let Some((len, symbol)) = symbol {
+            self.position += len;
+
+            match RawToken::from(symbol) {
+                RawToken::Operator(operator) if self.detect_unary && self.expect_for_neg => {
+                    RawToken::Operator(operator.unary().unwrap_or(operator))
+                }
+                token => token,
+            }
+        } else if matches!(first, b'0'..=b'9' | b'.') {
+            match self.exhaust_number() {
+                Ok(num) => num.node,
                 Err(err) => return Some(Err(err)),
-            },
-            _ => return Some(Err(TokenizerError::UnknownToken(input))),
+            }
+        } else {
+            let input = rest.chars().next().expect("rest is not empty");
+
+            self.position += input.len_utf8();
+
+            return Some(Err(TokenizerError::UnknownToken {
+                token: input,
+                span: Span::new(position, self.position),
+            }));
         };
 
         self.expect_for_neg = match &result {
-            Token::Operator(operator) => *operator != Operator::Neg,
-            Token::Group(Group::Open) => true,
+            // negation could follow the other unary operators, i.e. `!-x`
+            RawToken::Operator(operator) => *operator != Operator::Neg,
+            RawToken::Group(Group::Open) | RawToken::Separator | RawToken::Branch(_) => true,
             _ => false,
         };
 
-        Some(Ok(result))
+        Some(Ok(Spanned::new(result, Span::new(position, self.position))))
+    }
+}
+
+/// Tokenizer that yields the owned tokens converting [RawToken] ones
+#[derive(Debug, Clone)]
+pub struct TokenIterator<'stream> {
+    raw: RawTokenIterator<'stream>,
+}
+
+impl<'stream> TokenIterator<'stream> {
+    /// Creates iterator over the stream encoded with the default [Dialect::letters]
+    pub fn new(stream: &'stream str) -> Self {
+        Self::with_dialect(stream, Dialect::default_ref())
+    }
+
+    pub fn with_dialect(stream: &'stream str, dialect: &'stream Dialect) -> Self {
+        Self {
+            raw: RawTokenIterator::with_dialect(stream, dialect),
+        }
+    }
+
+    /// See [RawTokenIterator::with_unary_detection]
+    pub fn with_unary_detection(mut self, detect_unary: bool) -> Self {
+        self.raw = self.raw.with_unary_detection(detect_unary);
+        self
+    }
+
+    /// Bytes of the stream that are tokenized, the failed number counts as a whole
+    pub(crate) fn position(&self) -> usize {
+        self.raw.position
+    }
+
+    /// Whether the next operator is turned into the unary one
+    pub(crate) fn expects_operand(&self) -> bool {
+        self.raw.expect_for_neg
+    }
+
+    /// Continues tokenizing after the token that has left the iterator in the state
+    pub(crate) fn with_expected_operand(mut self, expect_for_neg: bool) -> Self {
+        self.raw.expect_for_neg = expect_for_neg;
+        self
+    }
+}
+
+impl Iterator for TokenIterator<'_> {
+    type Item = Result<Spanned<Token>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.raw
+            .next()
+            .map(|token| token.map(|token| token.map(|token| token.to_token())))
     }
 }
 
 impl<'stream> From<&'stream str> for TokenIterator<'stream> {
     fn from(s: &'stream str) -> Self {
-        TokenIterator::new(s.chars())
+        TokenIterator::new(s)
     }
 }
 
@@ -235,7 +819,12 @@ mod tests {
         };
 
 		($str:literal, $( $token:expr ),* $(,)?) => {
-			assert_eq!(TokenIterator::from($str).collect::<Vec<_>>(), vec![$($token),*])
+			assert_eq!(
+				TokenIterator::from($str)
+					.map(|token| token.map(|token| token.node))
+					.collect::<Vec<_>>(),
+				vec![$($token),*]
+			)
 		};
 	}
 
@@ -249,7 +838,7 @@ mod tests {
         assert_tokens!("a", Ok(Token::Operator(Operator::Add)));
         assert_tokens!("d", Ok(Token::Operator(Operator::Div)));
         assert_tokens!("e", Ok(Token::Group(Group::Open)));
-        assert_tokens!("1", Ok(Token::Number(Number::Int(1))));
+        assert_tokens!("1", Ok(Token::Number(Number::Int(1.into()))));
         assert_tokens!("1.25", Ok(Token::Number(Number::Float(1.25))));
         assert_tokens!(
             "b3.8",
@@ -257,7 +846,13 @@ mod tests {
             Ok(Token::Number(Number::Float(3.8)))
         );
         assert_tokens!(".5", Ok(Token::Number(Number::Float(0.5))));
-        assert_tokens!("5.", Ok(Token::Number(Number::Float(5.0))))
+        assert_tokens!("5.", Ok(Token::Number(Number::Float(5.0))));
+        assert_tokens!(
+            "123456789012345678901234567890",
+            Ok(Token::Number(Number::Int(
+                "123456789012345678901234567890".parse().unwrap()
+            )))
+        )
     }
 
     #[test]
@@ -268,7 +863,7 @@ mod tests {
             Ok(Token::Operator(Operator::Neg)),
             Ok(Token::Group(Group::Open)),
             Ok(Token::Operator(Operator::Neg)),
-            Ok(Token::Number(Number::Int(1))),
+            Ok(Token::Number(Number::Int(1.into()))),
             Ok(Token::Group(Group::Close)),
             Ok(Token::Group(Group::Close)),
         );
@@ -277,23 +872,68 @@ mod tests {
             "b e 2f b e b 3f", // - ( 2) - ( - 3)
             Ok(Token::Operator(Operator::Neg)),
             Ok(Token::Group(Group::Open)),
-            Ok(Token::Number(Number::Int(2))),
+            Ok(Token::Number(Number::Int(2.into()))),
             Ok(Token::Group(Group::Close)),
             Ok(Token::Operator(Operator::Sub)),
             Ok(Token::Group(Group::Open)),
             Ok(Token::Operator(Operator::Neg)),
-            Ok(Token::Number(Number::Int(3))),
+            Ok(Token::Number(Number::Int(3.into()))),
             Ok(Token::Group(Group::Close))
         );
     }
 
+    #[test]
+    fn without_unary_detection() {
+        assert_eq!(
+            TokenIterator::new("b1 a b")
+                .with_unary_detection(false)
+                .map(|token| token.map(|token| token.node))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(Token::Operator(Operator::Sub)),
+                Ok(Token::Number(Number::Int(1.into()))),
+                Ok(Token::Operator(Operator::Add)),
+                Ok(Token::Operator(Operator::Sub)),
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_tokens() {
+        let source = "$max e12, b1.5f";
+
+        assert_eq!(
+            RawTokenIterator::new(source)
+                .map(|token| token.map(|token| token.node))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(RawToken::Identifier("max")),
+                Ok(RawToken::Group(Group::Open)),
+                Ok(RawToken::Int("12")),
+                Ok(RawToken::Separator),
+                Ok(RawToken::Operator(Operator::Neg)),
+                Ok(RawToken::Float {
+                    value: 1.5,
+                    literal: "1.5"
+                }),
+                Ok(RawToken::Group(Group::Close)),
+            ]
+        );
+        assert_eq!(
+            RawTokenIterator::new(source)
+                .map(|token| token.map(|token| token.map(|token| token.to_token())))
+                .collect::<Vec<_>>(),
+            TokenIterator::new(source).collect::<Vec<_>>()
+        );
+    }
+
     #[test]
     fn list_of_tokens() {
         assert_tokens!(
             " 2 a    3", // 2 +   3
-            Ok(Token::Number(Number::Int(2))),
+            Ok(Token::Number(Number::Int(2.into()))),
             Ok(Token::Operator(Operator::Add)),
-            Ok(Token::Number(Number::Int(3)))
+            Ok(Token::Number(Number::Int(3.into())))
         );
 
         assert_tokens!(
@@ -313,41 +953,411 @@ mod tests {
             Ok(Token::Number(Number::Float(3253252.12))),
             Ok(Token::Group(Group::Close)),
             Ok(Token::Operator(Operator::Add)),
-            Ok(Token::Number(Number::Int(3))),
+            Ok(Token::Number(Number::Int(3.into()))),
             Ok(Token::Group(Group::Close)),
             Ok(Token::Operator(Operator::Add)),
-            Ok(Token::Number(Number::Int(212)))
+            Ok(Token::Number(Number::Int(212.into())))
         )
     }
 
     #[test]
     fn wrong_single_token() {
-        let parse_float_error = "..".parse::<f32>().unwrap_err();
-
-        assert_tokens!(":", Err(TokenizerError::UnknownToken(':')));
-        assert_tokens!("+", Err(TokenizerError::UnknownToken('+')));
-        assert_tokens!("g", Err(TokenizerError::UnknownToken('g')));
+        assert_tokens!(
+            ";",
+            Err(TokenizerError::UnknownToken {
+                token: ';',
+                span: Span::new(0, 1)
+            })
+        );
+        assert_tokens!(
+            "+",
+            Err(TokenizerError::UnknownToken {
+                token: '+',
+                span: Span::new(0, 1)
+            })
+        );
+        assert_tokens!(
+            "x",
+            Err(TokenizerError::UnknownToken {
+                token: 'x',
+                span: Span::new(0, 1)
+            })
+        );
         assert_tokens!(
             "2213.2132.233",
             Err(TokenizerError::NumberParseError {
-                kind: NumberParseErrorKind::Float(parse_float_error)
+                kind: NumberParseErrorKind::UnexpectedPoint,
+                span: Span::new(9, 10)
             })
         );
         assert_tokens!(
             "2 + a",
-            Ok(Token::Number(Number::Int(2))),
-            Err(TokenizerError::UnknownToken('+')),
+            Ok(Token::Number(Number::Int(2.into()))),
+            Err(TokenizerError::UnknownToken {
+                token: '+',
+                span: Span::new(2, 3)
+            }),
             Ok(Token::Operator(Operator::Add))
         );
         assert_tokens!(
-            ": a e3 a 2f",
-            Err(TokenizerError::UnknownToken(':')),
+            "; a e3 a 2f",
+            Err(TokenizerError::UnknownToken {
+                token: ';',
+                span: Span::new(0, 1)
+            }),
             Ok(Token::Operator(Operator::Add)),
             Ok(Token::Group(Group::Open)),
-            Ok(Token::Number(Number::Int(3))),
+            Ok(Token::Number(Number::Int(3.into()))),
             Ok(Token::Operator(Operator::Add)),
-            Ok(Token::Number(Number::Int(2))),
+            Ok(Token::Number(Number::Int(2.into()))),
             Ok(Token::Group(Group::Close))
         )
     }
+
+    #[test]
+    fn number_literals() {
+        let int = |value: i64| Ok(Token::Number(Number::Int(value.into())));
+        let float = |value: f64| Ok(Token::Number(Number::Float(value)));
+
+        assert_tokens!("1.5E-3", float(1.5e-3));
+        assert_tokens!(
+            "2E+5 a 1.E2",
+            float(2e5),
+            Ok(Token::Operator(Operator::Add)),
+            float(100.0)
+        );
+        assert_tokens!(
+            "0x1F c 0B101",
+            int(31),
+            Ok(Token::Operator(Operator::Mul)),
+            int(5)
+        );
+        assert_tokens!(
+            "0x1Fa2",
+            int(31),
+            Ok(Token::Operator(Operator::Add)),
+            int(2)
+        );
+        assert_tokens!("0b1", int(0), Ok(Token::Operator(Operator::Sub)), int(1));
+        assert_tokens!(
+            "1_000_000 d 0B1111_0000",
+            int(1_000_000),
+            Ok(Token::Operator(Operator::Div)),
+            int(240)
+        );
+        assert_tokens!("1_000.000_5E1_0", float(1_000.000_5e10));
+        assert_tokens!(
+            "123_456_789_012_345_678.9E-2",
+            Ok(Token::Number(Number::Decimal(
+                "123456789012345678.9e-2".to_string()
+            )))
+        );
+        assert_tokens!(
+            "1E400",
+            Ok(Token::Number(Number::Decimal("1e400".to_string())))
+        );
+
+        let dialect = Dialect::standard();
+
+        assert_eq!(
+            TokenIterator::with_dialect("-1e3+0xff-2E-2", &dialect)
+                .map(|token| token.map(|token| token.node))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(Token::Operator(Operator::Neg)),
+                float(1e3),
+                Ok(Token::Operator(Operator::Add)),
+                int(255),
+                Ok(Token::Operator(Operator::Sub)),
+                float(2e-2),
+            ]
+        );
+
+        let dialect = Dialect::standard().with_exponent_marker('D').unwrap();
+
+        assert_eq!(
+            TokenIterator::with_dialect("1_5D2", &dialect)
+                .map(|token| token.map(|token| token.node))
+                .collect::<Vec<_>>(),
+            vec![float(1500.0)]
+        );
+    }
+
+    #[test]
+    fn wrong_number_literals() {
+        let error = |kind, span: std::ops::Range<usize>| {
+            Err(TokenizerError::NumberParseError {
+                kind,
+                span: span.into(),
+            })
+        };
+
+        assert_tokens!(
+            "0B102 a 1",
+            error(
+                NumberParseErrorKind::InvalidDigit {
+                    digit: '2',
+                    radix: 2
+                },
+                4..5
+            ),
+            Ok(Token::Operator(Operator::Add)),
+            Ok(Token::Number(Number::Int(1.into())))
+        );
+        assert_tokens!(
+            "12x",
+            error(
+                NumberParseErrorKind::InvalidDigit {
+                    digit: 'x',
+                    radix: 10
+                },
+                2..3
+            )
+        );
+        assert_tokens!(
+            "1__0",
+            error(NumberParseErrorKind::MisplacedSeparator, 1..2)
+        );
+        assert_tokens!("1_", error(NumberParseErrorKind::MisplacedSeparator, 1..2));
+        assert_tokens!(
+            "0x_1",
+            error(NumberParseErrorKind::MisplacedSeparator, 2..3)
+        );
+        assert_tokens!("0x1.5", error(NumberParseErrorKind::UnexpectedPoint, 3..4));
+        assert_tokens!("1E2.5", error(NumberParseErrorKind::UnexpectedPoint, 3..4));
+        assert_tokens!("0X", error(NumberParseErrorKind::MissingDigits, 0..2));
+        assert_tokens!("1.5E+", error(NumberParseErrorKind::MissingDigits, 3..5));
+        assert_tokens!(
+            "1Ea",
+            error(NumberParseErrorKind::MissingDigits, 1..2),
+            Ok(Token::Operator(Operator::Add))
+        );
+        assert_tokens!(".", error(NumberParseErrorKind::MissingDigits, 0..1));
+
+        assert_eq!(
+            TokenIterator::new("0B12")
+                .next()
+                .unwrap()
+                .unwrap_err()
+                .to_string(),
+            "Unable to parse number, `2` is not a digit of the base 2"
+        );
+    }
+
+    #[test]
+    fn imaginary_literals() {
+        let imaginary = |value: f64| Ok(Token::Number(Number::Imaginary(value)));
+        let invalid = |digit, radix, span: std::ops::Range<usize>| {
+            Err(TokenizerError::NumberParseError {
+                kind: NumberParseErrorKind::InvalidDigit { digit, radix },
+                span: span.into(),
+            })
+        };
+
+        assert_tokens!("2i", imaginary(2.0));
+        assert_tokens!(
+            "1.5E2ia1_0i",
+            imaginary(150.0),
+            Ok(Token::Operator(Operator::Add)),
+            imaginary(10.0)
+        );
+        // suffix is the last char of the literal
+        assert_tokens!("2in", invalid('i', 10, 1..2));
+        assert_tokens!("0x1i", invalid('i', 16, 3..4));
+        assert_eq!(
+            TokenIterator::from("b.5i").collect::<Vec<_>>(),
+            vec![
+                Ok(Spanned::new(Token::Operator(Operator::Neg), 0..1)),
+                Ok(Spanned::new(Token::Number(Number::Imaginary(0.5)), 1..4)),
+            ]
+        );
+    }
+
+    #[test]
+    fn whitespace_and_comments() {
+        assert_tokens!(
+            "\t1\n a\u{a0}\u{2003}2\r\n",
+            Ok(Token::Number(Number::Int(1.into()))),
+            Ok(Token::Operator(Operator::Add)),
+            Ok(Token::Number(Number::Int(2.into())))
+        );
+        assert_eq!(
+            TokenIterator::new("// price\n$price /* with tax */ c\n// rate\n1.2 // total")
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(Spanned::new(Token::Identifier("price".to_string()), 9..15)),
+                Ok(Spanned::new(Token::Operator(Operator::Mul), 31..32)),
+                Ok(Spanned::new(Token::Number(Number::Float(1.2)), 41..44)),
+            ]
+        );
+        assert_tokens!(
+            "1 /* b 2 */b /**/ 3 /* b",
+            Ok(Token::Number(Number::Int(1.into()))),
+            Ok(Token::Operator(Operator::Sub)),
+            Ok(Token::Number(Number::Int(3.into()))),
+            Err(TokenizerError::UnclosedComment {
+                span: Span::new(20, 22)
+            })
+        );
+
+        let dialect = Dialect::standard()
+            .with_line_comment("#")
+            .unwrap()
+            .with_symbol("//", Operator::Div)
+            .unwrap();
+
+        assert_eq!(
+            TokenIterator::with_dialect("6 // 2 # half", &dialect)
+                .map(|token| token.map(|token| token.node))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(Token::Number(Number::Int(6.into()))),
+                Ok(Token::Operator(Operator::Div)),
+                Ok(Token::Number(Number::Int(2.into())))
+            ]
+        );
+    }
+
+    #[test]
+    fn conditions() {
+        let dialect = Dialect::standard();
+        let int = |num: i32| Ok(Token::Number(Number::Int(num.into())));
+
+        assert_eq!(
+            TokenIterator::with_dialect("!-x <= 1 && (y != 2) ? -1 : 0", &dialect)
+                .map(|token| token.map(|token| token.node))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(Token::Operator(Operator::Not)),
+                Ok(Token::Operator(Operator::Neg)),
+                Ok(Token::Identifier("x".to_string())),
+                Ok(Token::Operator(Operator::Le)),
+                int(1),
+                Ok(Token::Operator(Operator::And)),
+                Ok(Token::Group(Group::Open)),
+                Ok(Token::Identifier("y".to_string())),
+                Ok(Token::Operator(Operator::Ne)),
+                int(2),
+                Ok(Token::Group(Group::Close)),
+                Ok(Token::Branch(Branch::Then)),
+                Ok(Token::Operator(Operator::Neg)),
+                int(1),
+                Ok(Token::Branch(Branch::Else)),
+                int(0),
+            ]
+        );
+        assert_tokens!(
+            "1<2>=b3",
+            int(1),
+            Ok(Token::Operator(Operator::Lt)),
+            int(2),
+            Ok(Token::Operator(Operator::Ge)),
+            Ok(Token::Operator(Operator::Neg)),
+            int(3)
+        );
+    }
+
+    #[test]
+    fn token_spans() {
+        assert_eq!(
+            TokenIterator::from(" 12.5 a  eb3f").collect::<Vec<_>>(),
+            vec![
+                Ok(Spanned::new(Token::Number(Number::Float(12.5)), 1..5)),
+                Ok(Spanned::new(Token::Operator(Operator::Add), 6..7)),
+                Ok(Spanned::new(Token::Group(Group::Open), 9..10)),
+                Ok(Spanned::new(Token::Operator(Operator::Neg), 10..11)),
+                Ok(Spanned::new(Token::Number(Number::Int(3.into())), 11..12)),
+                Ok(Spanned::new(Token::Group(Group::Close), 12..13)),
+            ]
+        );
+
+        assert_eq!(
+            TokenIterator::from("ä a 1").next(),
+            Some(Err(TokenizerError::UnknownToken {
+                token: 'ä',
+                span: Span::new(0, 2)
+            }))
+        );
+    }
+
+    #[test]
+    fn custom_dialect() {
+        let dialect = Dialect::standard()
+            .with_symbol("plus", Operator::Add)
+            .unwrap()
+            .with_symbol("~", Operator::Neg)
+            .unwrap();
+
+        assert_eq!(
+            TokenIterator::with_dialect("-(1 plus-2)*~3", &dialect)
+                .map(|token| token.map(|token| token.node))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(Token::Operator(Operator::Neg)),
+                Ok(Token::Group(Group::Open)),
+                Ok(Token::Number(Number::Int(1.into()))),
+                Ok(Token::Operator(Operator::Add)),
+                Ok(Token::Operator(Operator::Neg)),
+                Ok(Token::Number(Number::Int(2.into()))),
+                Ok(Token::Group(Group::Close)),
+                Ok(Token::Operator(Operator::Mul)),
+                Ok(Token::Operator(Operator::Neg)),
+                Ok(Token::Number(Number::Int(3.into()))),
+            ]
+        );
+
+        assert_eq!(
+            TokenIterator::with_dialect("1 # 2", &dialect).nth(1),
+            Some(Err(TokenizerError::UnknownToken {
+                token: '#',
+                span: Span::new(2, 3)
+            }))
+        );
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_tokens!(
+            "$sqrt e2f c $max_2e1,b2f",
+            Ok(Token::Identifier("sqrt".to_string())),
+            Ok(Token::Group(Group::Open)),
+            Ok(Token::Number(Number::Int(2.into()))),
+            Ok(Token::Group(Group::Close)),
+            Ok(Token::Operator(Operator::Mul)),
+            Ok(Token::Identifier("max_2e1".to_string())),
+            Ok(Token::Separator),
+            Ok(Token::Operator(Operator::Neg)),
+            Ok(Token::Number(Number::Int(2.into()))),
+            Ok(Token::Group(Group::Close)),
+        );
+        assert_tokens!(
+            "$ 1",
+            Err(TokenizerError::UnknownToken {
+                token: '$',
+                span: Span::new(0, 1)
+            }),
+            Ok(Token::Number(Number::Int(1.into()))),
+        );
+
+        let dialect = Dialect::standard()
+            .with_symbol("plus", Operator::Add)
+            .unwrap();
+
+        assert_eq!(
+            TokenIterator::with_dialect("max(1,-2) plus plus2", &dialect)
+                .map(|token| token.map(|token| token.node))
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(Token::Identifier("max".to_string())),
+                Ok(Token::Group(Group::Open)),
+                Ok(Token::Number(Number::Int(1.into()))),
+                Ok(Token::Separator),
+                Ok(Token::Operator(Operator::Neg)),
+                Ok(Token::Number(Number::Int(2.into()))),
+                Ok(Token::Group(Group::Close)),
+                Ok(Token::Operator(Operator::Add)),
+                Ok(Token::Identifier("plus2".to_string())),
+            ]
+        );
+    }
 }
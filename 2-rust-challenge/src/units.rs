@@ -0,0 +1,385 @@
+//! Module with the physical units of the quantities
+//! and the conversions between them
+
+use std::{collections::BTreeMap, collections::HashMap, fmt::Display};
+
+use crate::eval::CalculationError;
+
+/// Powers of the named units, i.e. `USD/oz` is `USD^1 * oz^-1`
+type Powers = BTreeMap<String, i32>;
+
+/// Unit of the quantity as the product of the powers of the units from the [Units],
+/// that knows its size and dimension, so the quantities are combined without the registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unit {
+    /// Powers of the units as they are written, i.e. `lb` and `oz` stay apart
+    powers: Powers,
+    /// Size of the unit in the base units, i.e. `1000` for `kg` in grams
+    factor: f64,
+    /// Powers of the base units, which should be the same for the conversion
+    dimension: Powers,
+}
+
+impl Unit {
+    /// Unit of the plain numbers
+    pub fn none() -> Self {
+        Self {
+            powers: Powers::new(),
+            factor: 1.0,
+            dimension: Powers::new(),
+        }
+    }
+
+    /// New base unit that is not convertible to any other base unit
+    fn base(name: &str) -> Self {
+        let powers = Powers::from([(name.to_string(), 1)]);
+
+        Self {
+            powers: powers.clone(),
+            factor: 1.0,
+            dimension: powers,
+        }
+    }
+
+    /// Plain numbers are written without any unit
+    pub fn is_none(&self) -> bool {
+        self.powers.is_empty()
+    }
+
+    /// Size of the unit in the base units
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    /// Whether the quantities of the units could be converted to each other
+    pub fn is_compatible(&self, other: &Unit) -> bool {
+        self.dimension == other.dimension
+    }
+
+    pub fn mul(&self, other: &Unit) -> Unit {
+        Self {
+            powers: combine(&self.powers, &other.powers, 1),
+            factor: self.factor * other.factor,
+            dimension: combine(&self.dimension, &other.dimension, 1),
+        }
+    }
+
+    pub fn div(&self, other: &Unit) -> Unit {
+        Self {
+            powers: combine(&self.powers, &other.powers, -1),
+            factor: self.factor / other.factor,
+            dimension: combine(&self.dimension, &other.dimension, -1),
+        }
+    }
+
+    pub fn powi(&self, exponent: i32) -> Unit {
+        let scale = |powers: &Powers| {
+            powers
+                .iter()
+                .map(|(name, power)| (name.clone(), power * exponent))
+                .filter(|(_, power)| *power != 0)
+                .collect()
+        };
+
+        Self {
+            powers: scale(&self.powers),
+            factor: self.factor.powi(exponent),
+            dimension: scale(&self.dimension),
+        }
+    }
+}
+
+/// Adds the powers of the right units multiplied by the sign, the cancelled units are dropped
+fn combine(left: &Powers, right: &Powers, sign: i32) -> Powers {
+    let mut powers = left.clone();
+
+    for (name, power) in right {
+        *powers.entry(name.clone()).or_default() += sign * power;
+    }
+
+    powers.retain(|_, power| *power != 0);
+    powers
+}
+
+/// Units with the positive powers followed by the ones with the negative powers,
+/// i.e. `kg*m/s^2`, plain numbers are written as `1`
+impl Display for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let write_power = |f: &mut std::fmt::Formatter<'_>, name: &str, power: i32| match power {
+            1 => write!(f, "{name}"),
+            power => write!(f, "{name}^{power}"),
+        };
+        let mut numerator = self.powers.iter().filter(|(_, power)| **power > 0);
+
+        match numerator.next() {
+            Some((name, power)) => write_power(f, name, *power)?,
+            None => write!(f, "1")?,
+        }
+
+        for (name, power) in numerator {
+            write!(f, "*")?;
+            write_power(f, name, *power)?;
+        }
+
+        for (name, power) in self.powers.iter().filter(|(_, power)| **power < 0) {
+            write!(f, "/")?;
+            write_power(f, name, -power)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Number with the unit, i.e. `2.5 kg`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    value: f64,
+    unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Self { value, unit }
+    }
+
+    /// Plain number without the unit
+    pub fn number(value: f64) -> Self {
+        Self::new(value, Unit::none())
+    }
+
+    /// Value in the unit of the quantity
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn unit(&self) -> &Unit {
+        &self.unit
+    }
+
+    /// Same quantity in the unit of the same dimension, i.e. `1.5 kg` for `1500 g`
+    pub fn convert(&self, unit: &Unit) -> Result<Quantity, CalculationError> {
+        if !self.unit.is_compatible(unit) {
+            return Err(CalculationError::IncompatibleUnits(
+                self.unit.to_string(),
+                unit.to_string(),
+            ));
+        }
+
+        // the same units are kept as they are, so the value is not rounded
+        if self.unit.powers == unit.powers {
+            return Ok(self.clone());
+        }
+
+        Ok(Self::new(
+            self.value * self.unit.factor / unit.factor,
+            unit.clone(),
+        ))
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.unit.is_none() {
+            true => write!(f, "{}", self.value),
+            false => write!(f, "{} {}", self.value, self.unit),
+        }
+    }
+}
+
+/// Named units the quantities could have, each of them is either the base one
+/// or is defined through the others, i.e. `kg` is `1000 g`.
+/// Quantities are convertible when their units reduce to the same base units.
+///
+/// Units are written as the product of the names with the optional integer powers,
+/// i.e. `kg*m/s^2` or `USD/oz`
+#[derive(Debug, Clone)]
+pub struct Units {
+    units: HashMap<String, Unit>,
+}
+
+/// Registry with [Units::builtin], so the evaluator knows the common units
+impl Default for Units {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Units {
+    /// Creates registry without any units
+    pub fn new() -> Self {
+        Self {
+            units: HashMap::new(),
+        }
+    }
+
+    /// Registry with the mass based on `g`, i.e. `mg`, `kg`, `t`, `oz`, `ozt` and `lb`,
+    /// the length based on `m`, i.e. `mm`, `cm`, `km`, `in`, `ft`, `yd` and `mi`,
+    /// the time based on `s`, i.e. `ms`, `min`, `h` and `d`,
+    /// the volume `l` and `ml` and the currency `USD`
+    pub fn builtin() -> Self {
+        let bases = Self::new()
+            .with_base("g")
+            .with_base("m")
+            .with_base("s")
+            .with_base("USD");
+
+        [
+            ("mg", 0.001, "g"),
+            ("kg", 1000.0, "g"),
+            ("t", 1e6, "g"),
+            ("oz", 28.349523125, "g"),
+            ("ozt", 31.1034768, "g"),
+            ("lb", 453.59237, "g"),
+            ("mm", 0.001, "m"),
+            ("cm", 0.01, "m"),
+            ("km", 1000.0, "m"),
+            ("in", 0.0254, "m"),
+            ("ft", 0.3048, "m"),
+            ("yd", 0.9144, "m"),
+            ("mi", 1609.344, "m"),
+            ("ms", 0.001, "s"),
+            ("min", 60.0, "s"),
+            ("h", 3600.0, "s"),
+            ("d", 86400.0, "s"),
+            ("l", 0.001, "m^3"),
+            ("ml", 0.001, "l"),
+        ]
+        .into_iter()
+        .try_fold(bases, |units, (name, factor, definition)| {
+            units.with_unit(name, factor, definition)
+        })
+        .expect("definitions consist of the units defined before them")
+    }
+
+    /// Adds the base unit of the new dimension, i.e. the currency
+    pub fn with_base(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+
+        self.units.insert(name.clone(), Unit::base(&name));
+        self
+    }
+
+    /// Adds the unit that is the `factor` of the unit `definition`, i.e. `EUR` as `1.08 USD`
+    /// or `N` as `1000 g*m/s^2`, the definition with the unknown unit is the error
+    pub fn with_unit(
+        mut self,
+        name: impl Into<String>,
+        factor: f64,
+        definition: &str,
+    ) -> Result<Self, CalculationError> {
+        let name = name.into();
+        let definition = self.parse(definition)?;
+
+        self.units.insert(
+            name.clone(),
+            Unit {
+                powers: Powers::from([(name, 1)]),
+                factor: factor * definition.factor,
+                dimension: definition.dimension,
+            },
+        );
+        Ok(self)
+    }
+
+    /// Reads the unit written as the names with the integer powers
+    /// separated by `*` and `/`, `1` stands for no unit, i.e. `1/s`
+    pub fn parse(&self, text: &str) -> Result<Unit, CalculationError> {
+        let unknown = || CalculationError::UnknownUnit(text.trim().to_string());
+        let mut unit = Unit::none();
+        let mut divide = false;
+        let mut rest = text;
+
+        loop {
+            let end = rest.find(['*', '/']).unwrap_or(rest.len());
+            let (name, power) = match rest[..end].split_once('^') {
+                Some((name, power)) => (name.trim(), power.trim().parse().map_err(|_| unknown())?),
+                None => (rest[..end].trim(), 1),
+            };
+            let factor = match self.units.get(name) {
+                Some(known) => known.powi(power),
+                None if name == "1" => Unit::none(),
+                None => return Err(unknown()),
+            };
+
+            unit = match divide {
+                true => unit.div(&factor),
+                false => unit.mul(&factor),
+            };
+
+            let Some(separator) = rest[end..].chars().next() else {
+                return Ok(unit);
+            };
+
+            divide = separator == '/';
+            rest = &rest[end + 1..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_print() {
+        let units = Units::builtin();
+        let unit = |text| units.parse(text).unwrap();
+
+        assert_eq!(unit(" kg * m / s^2 ").to_string(), "kg*m/s^2");
+        assert_eq!(unit("USD/oz").to_string(), "USD/oz");
+        assert_eq!(unit("1/s").to_string(), "1/s");
+        assert_eq!(unit("oz/oz"), Unit::none());
+        assert_eq!(unit("l").factor(), 0.001);
+        assert!(unit("l").is_compatible(&unit("cm^3")));
+        assert!(!unit("g").is_compatible(&unit("USD")));
+        assert_eq!(
+            units.parse("kg/parsec"),
+            Err(CalculationError::UnknownUnit("kg/parsec".to_string()))
+        );
+        assert_eq!(
+            units.parse("m^x"),
+            Err(CalculationError::UnknownUnit("m^x".to_string()))
+        );
+        assert_eq!(
+            units.parse(""),
+            Err(CalculationError::UnknownUnit(String::new()))
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let units = Units::builtin().with_unit("EUR", 1.08, "USD").unwrap();
+        let quantity = |value, text| Quantity::new(value, units.parse(text).unwrap());
+
+        assert_eq!(
+            quantity(1500.0, "g").convert(units.parse("kg").as_ref().unwrap()),
+            Ok(quantity(1.5, "kg"))
+        );
+        assert_eq!(
+            quantity(10.0, "EUR")
+                .convert(&units.parse("USD").unwrap())
+                .unwrap()
+                .to_string(),
+            "10.8 USD"
+        );
+        assert_eq!(
+            quantity(2.0, "kg").convert(&units.parse("USD").unwrap()),
+            Err(CalculationError::IncompatibleUnits(
+                "kg".to_string(),
+                "USD".to_string()
+            ))
+        );
+        assert_eq!(
+            quantity(2.0, "ozt")
+                .convert(&units.parse("g").unwrap())
+                .unwrap()
+                .to_string(),
+            "62.2069536 g"
+        );
+        assert_eq!(Quantity::number(2.5).to_string(), "2.5");
+        assert_eq!(
+            Units::new().with_unit("EUR", 1.08, "USD").unwrap_err(),
+            CalculationError::UnknownUnit("USD".to_string())
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Module with the typed result of the evaluation
+//! that is either the number or the boolean
+
+use std::fmt::Display;
+
+/// Type of the value, so the mismatches are reported without the values themselves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Number,
+    Bool,
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number => write!(f, "number"),
+            Self::Bool => write!(f, "boolean"),
+        }
+    }
+}
+
+/// Result of the expression, booleans are produced by the comparisons and logic
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<N = f64> {
+    Number(N),
+    Bool(bool),
+}
+
+impl<N> Value<N> {
+    pub fn value_type(&self) -> Type {
+        match self {
+            Self::Number(_) => Type::Number,
+            Self::Bool(_) => Type::Bool,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&N> {
+        match self {
+            Self::Number(number) => Some(number),
+            Self::Bool(_) => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Number(_) => None,
+            Self::Bool(value) => Some(*value),
+        }
+    }
+}
+
+impl<N: Display> Display for Value<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{number}"),
+            Self::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
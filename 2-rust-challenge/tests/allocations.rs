@@ -0,0 +1,73 @@
+//! Checks that the tokenizers don't allocate more than the owned tokens need,
+//! kept apart as the counting allocator serves the whole test binary
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use eval::{
+    tokens::{RawTokenIterator, TokenIterator},
+    Dialect,
+};
+
+struct Counting;
+
+thread_local! {
+    /// Allocations of the test thread, so the ones of the test harness are not counted
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Number of allocations made by the closure along with its result
+fn count<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    let after = ALLOCATIONS.with(Cell::get);
+
+    (result, after - before)
+}
+
+#[test]
+fn raw_tokens_do_not_allocate() {
+    let dialect = Dialect::standard();
+    let source = "price * (1 + 0.2) - min(discount, 123456789012345678901234567890) ".repeat(100);
+
+    let (count, allocations) = count(|| {
+        RawTokenIterator::with_dialect(&source, &dialect)
+            .filter(Result::is_ok)
+            .count()
+    });
+
+    assert_eq!(count, 1400);
+    assert_eq!(allocations, 0);
+}
+
+#[test]
+fn tokens_allocate_only_names() {
+    let dialect = Dialect::standard();
+    let source = "price * (1_000 + 0.2) - min(discount, 0xFFFF_FFFF_FFFF_FFFF) ^ 2 ".repeat(100);
+
+    let (count, allocations) = count(|| {
+        TokenIterator::with_dialect(&source, &dialect)
+            .filter(Result::is_ok)
+            .count()
+    });
+
+    // integers of 64 bits are kept inline, the names are owned by the tokens
+    assert_eq!(count, 1600);
+    assert_eq!(allocations, 300);
+}
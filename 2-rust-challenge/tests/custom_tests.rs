@@ -1,5 +1,12 @@
 mod helpers;
 
+use eval::{
+    eval::CalculationError, tokens::Operator, Associativity, Complex, ComplexForm, DecimalContext,
+    Dialect, DivisionPolicy, Environment, EvalError, Evaluator, ExprError, FloatPolicy,
+    FunctionArity, Notation, Numeral, Numeric, Parentheses, ParserConfig, Printer, Rounding,
+    RpnParser, Type, Units, Value,
+};
+
 #[test]
 fn single_values() {
     assert_expr_eq!("0", 0.0);
@@ -49,3 +56,855 @@ fn multiple_operators() {
     assert_expr_eq!("e1 b 2f a bebebeb4fff", 3.0);
     assert_expr_eq!("ee2.33 d e2.9a3.5fc4f b b6f", 7.45625);
 }
+
+#[test]
+fn errors_point_to_source() {
+    let source = "2 d e1 b 1f";
+    let err = eval::eval(source).unwrap_err();
+
+    assert_eq!(err.span(), eval::Span::new(4, 11));
+    assert_eq!(
+        err.diagnostic(source).to_string(),
+        [
+            "error: division by zero",
+            " --> 1:5",
+            "  |",
+            "1 | 2 d e1 b 1f",
+            "  |     ^^^^^^^",
+        ]
+        .join("\n")
+    );
+
+    let source = "2 a e3 c";
+    let err = eval::eval(source).unwrap_err();
+
+    assert_eq!(err.span(), eval::Span::point(8));
+    assert_eq!(err.diagnostic(source).location(), (1, 9));
+}
+
+#[test]
+fn standard_precedence() {
+    let config = ParserConfig::standard();
+
+    assert_expr_eq!("2a3c4", 14.0, config);
+    assert_expr_eq!("2a3c4", 20.0, ParserConfig::flat());
+    assert_expr_eq!("3ae4c66fb32", 235.0, config);
+    assert_expr_eq!("3c4d2aee2a4c41fc4f", 670.0, config);
+    assert_expr_eq!("2 b 3 b 4 d 2 d 2", -2.0, config);
+    assert_expr_eq!("b2 c 3 a 4", -2.0, config);
+}
+
+#[test]
+fn custom_associativity() {
+    let config = ParserConfig::standard().with_operator(Operator::Sub, 1, Associativity::Right);
+
+    assert_expr_eq!("10 b 4 b 3", 9.0, config);
+    assert_expr_eq!("10 b 4 b 3", 3.0, ParserConfig::standard());
+}
+
+#[test]
+fn dialects() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+
+    assert_expr_eq!("3 + 2 * 4", 11.0, standard);
+    assert_expr_eq!("3*4/2+((2+4*41)*4)", 670.0, standard);
+    assert_expr_eq!("1 - -(2 - 3)", 0.0, standard);
+
+    let custom = ParserConfig::flat().with_dialect(
+        Dialect::new()
+            .with_symbol("plus", Operator::Add)
+            .unwrap()
+            .with_symbol("times", Operator::Mul)
+            .unwrap()
+            .with_symbol("minus", Operator::Sub)
+            .unwrap()
+            .with_symbol("[", eval::tokens::Group::Open)
+            .unwrap()
+            .with_symbol("]", eval::tokens::Group::Close)
+            .unwrap(),
+    );
+
+    assert_expr_eq!("3 plus 2 times [minus 4]", -20.0, custom);
+}
+
+#[test]
+fn power_and_remainder() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+
+    assert_expr_eq!("2 ^ 3 ^ 2", 512.0, standard);
+    assert_expr_eq!("2 g 3 g 2", 512.0);
+    assert_expr_eq!("ee2 g 3f g 2f", 64.0);
+    assert_expr_eq!("-2 ^ 2", -4.0, standard);
+    assert_expr_eq!("(-2) ^ 2", 4.0, standard);
+    assert_expr_eq!("b2 g 2", -4.0);
+    assert_expr_eq!("eb2f g 2", 4.0);
+    assert_expr_eq!("1 a 2 g 3 c 2", 18.0);
+    assert_expr_eq!("2 ^ -1", 0.5, standard);
+    assert_expr_eq!("2 * 3 ^ 2 % 5", 3.0, standard);
+    assert_expr_eq!("17 h 5 a 1", 3.0);
+
+    let error = |expr| match eval::eval_with_config(expr, &standard) {
+        Err(ExprError::EvalError(EvalError::CalculationError { error, .. })) => error,
+        result => panic!("expected calculation error, got {result:?}"),
+    };
+
+    assert_eq!(error("5 % (2 - 2)"), CalculationError::ZeroModulus);
+    assert_eq!(
+        error("(-8) ^ (1 / 3)"),
+        CalculationError::FractionalPowerOfNegative
+    );
+    assert_eq!(error("0 ^ -2"), CalculationError::ZeroDivision);
+}
+
+#[test]
+fn function_calls() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+
+    assert_expr_eq!("$sqrt e16f a $max e1, 2, 3f", 7.0);
+    assert_expr_eq!("sqrt(3 * 3 + 4 ^ 2) - abs(-2)", 3.0, standard);
+    assert_expr_eq!("round(2.345, 2) * 100", 235.0, standard);
+    assert_expr_eq!(
+        "min(4, max(1, 2), 3) + floor(-1.5) + ceil(0.2)",
+        1.0,
+        standard
+    );
+    assert_expr_eq!("log(100) + ln(exp(2)) + log(8, 2)", 7.0, standard);
+
+    let error = eval::eval_with_config("1 + sqrt(1, 2)", &standard).unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Function `sqrt` expects 1 argument(s), but 2 were provided"
+    );
+    assert_eq!(error.span(), eval::Span::new(4, 14));
+
+    let error = eval::eval_with_config("foo(1)", &standard).unwrap_err();
+
+    assert_eq!(error.to_string(), "Unknown function `foo`");
+}
+
+#[test]
+fn custom_functions() {
+    let config = ParserConfig::standard().with_dialect(Dialect::standard());
+    let evaluator = eval::Evaluator::with_functions(
+        eval::Functions::builtin()
+            .with_closure("hypot", FunctionArity::exact(2), |args| {
+                Ok(args[0].hypot(args[1]))
+            })
+            .with_closure("avg", FunctionArity::at_least(1), |args| {
+                Ok(args.iter().sum::<f64>() / args.len() as f64)
+            }),
+    );
+    let eval = |expr| evaluator.eval(&eval::parse_with_config(expr, &config).unwrap());
+
+    assert_eq!(eval("hypot(3, 4) * 2"), Ok(10.0));
+    assert_eq!(eval("avg(1, 2, sqrt(81))"), Ok(4.0));
+}
+
+#[test]
+fn variables() {
+    let mut env = Environment::new()
+        .with_variable("price", 10.0)
+        .with_variable("tax", 0.2);
+
+    assert_eq!(eval::eval_with("$price c e1 a $tax f", &env).unwrap(), 12.0);
+
+    env.set("price", 20.0);
+    assert_eq!(eval::eval_with("$price c e1 a $tax f", &env).unwrap(), 24.0);
+
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let result = eval::eval_with_env_and_config("sqrt(price * 5) - discount", &env, &standard);
+    let error = result.unwrap_err();
+
+    assert_eq!(error.to_string(), "Unknown variable `discount`");
+    assert_eq!(error.span(), eval::Span::new(18, 26));
+}
+
+#[test]
+fn compiled_expressions() {
+    let compiled = eval::compile("$price c e1 a $tax f").unwrap();
+
+    assert_eq!(compiled.variables(), ["price", "tax"]);
+    assert_eq!(compiled.eval(&[10.0, 0.2]).unwrap(), 12.0);
+    assert_eq!(compiled.eval(&[20.0, 0.2]).unwrap(), 24.0);
+
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let error = eval::compile_with_config("1 + foo(x)", &standard).unwrap_err();
+
+    assert_eq!(error.to_string(), "Unknown function `foo`");
+    assert_eq!(error.span(), eval::Span::new(4, 10));
+}
+
+#[test]
+fn exact_arithmetic() {
+    let exact = |expr| eval::eval_exact(expr).unwrap();
+
+    assert_eq!(exact("1d3c3").to_string(), "1");
+    assert_eq!(exact("2 d e2 a 3f c 4.33 b b6").to_string(), "1933/250");
+    assert_eq!(exact("2 d e2 a 3f c 4.33 b b6").to_decimal(3), "7.732");
+    assert_eq!(exact("ee2.33 d e2.9a3.5fc4f b b6f").to_string(), "1193/160");
+    assert_eq!(exact("1 d 3").to_decimal(5), "0.33333");
+    assert_eq!(exact("e2 d 3f g 2").to_string(), "4/9");
+    assert_eq!(
+        exact("0.1000000000000000000001").to_string(),
+        "1000000000000000000001/10000000000000000000000"
+    );
+    assert_eq!(exact("1.5E-30 c 2E30").to_string(), "3");
+    assert_eq!(exact("1 g 1000000000").to_string(), "1");
+    assert_eq!(exact("b1 g 3000000001").to_string(), "-1");
+    assert_eq!(exact("0 g 5000000000").to_string(), "0");
+    assert_eq!(exact("$min e1 d 3, 1f").to_string(), "1/3");
+    assert_eq!(exact("$round e2 d 3, 3f").to_string(), "667/1000");
+    assert_eq!(exact("$sqrt e9 d 4f").to_string(), "3/2");
+
+    let error = eval::eval_exact("2 g e1 d 2f").unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "fractional power can't be calculated exactly"
+    );
+    assert_eq!(
+        eval::eval_exact("$sqrt e2f").unwrap_err().to_string(),
+        "number can't be represented exactly"
+    );
+}
+
+#[test]
+fn big_integers() {
+    let numeral = |expr, policy| eval::eval_numeral(expr, policy).unwrap().to_string();
+
+    assert_expr_eq!("99999999999 a 1", 100000000000.0);
+    assert_eq!(
+        numeral("99999999999 c 99999999999", DivisionPolicy::Rational),
+        "9999999999800000000001"
+    );
+    assert_eq!(
+        numeral("123456789012345678901234567890 b 1", DivisionPolicy::Float),
+        "123456789012345678901234567889"
+    );
+    assert_eq!(numeral("10 d 4 c 2", DivisionPolicy::Rational), "5");
+    assert_eq!(numeral("10 d 4", DivisionPolicy::Rational), "5/2");
+    assert_eq!(numeral("10 d 4", DivisionPolicy::Float), "2.5");
+    assert_eq!(
+        numeral("12345678901234567.5 c 2", DivisionPolicy::Rational),
+        "24691357802469135"
+    );
+    assert_eq!(
+        eval::eval_numeral("2 g 100 d e2 g 98f", DivisionPolicy::Float).unwrap(),
+        Numeral::Int(4.into())
+    );
+    assert_eq!(
+        numeral("$abs eb99999999999999999999f", DivisionPolicy::Rational),
+        "99999999999999999999"
+    );
+    assert_eq!(
+        numeral("$max e99999999999999999999, 1f", DivisionPolicy::Float),
+        "99999999999999999999"
+    );
+    assert_eq!(numeral("$round e10 d 4f", DivisionPolicy::Rational), "3");
+    assert_eq!(
+        eval::eval_numeral("$sqrt e2f", DivisionPolicy::Rational).unwrap(),
+        Numeral::Float(2f64.sqrt())
+    );
+}
+
+#[test]
+fn simplification() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let simplify = |expr| eval::simplify_with_config(expr, &standard).unwrap();
+    let parse = |expr| eval::parse_with_config(expr, &standard).unwrap();
+
+    assert_eq!(simplify("(2 + 3) * price * 1 + 0"), parse("5 * price"));
+    assert_eq!(simplify("-(-x) - 10 / 5"), parse("x - 2"));
+    assert_eq!(simplify("(1 / 3) * x"), parse("1 / 3 * x"));
+    assert_eq!(simplify("max(1, sqrt(81), -4) ^ 2 + x"), parse("81 + x"));
+
+    // identities keep the type errors of the boolean operands
+    let boolean = simplify("(1 < 2) + 0");
+
+    assert_ne!(boolean, parse("1 < 2"));
+    assert!(Evaluator::new().eval(&boolean).is_err());
+
+    let expr = "(0.5 + 0.25) * (x ^ 1 - -(-y)) / (4 - 2 * 2 + 1)";
+    let env = Environment::new()
+        .with_variable("x", 7.0)
+        .with_variable("y", 3.0);
+    let simplified = simplify(expr);
+
+    assert_eq!(simplified, simplify("(0.5 + 0.25) * (x - y)"));
+    assert_eq!(
+        Evaluator::new().eval_with(&simplified, &env),
+        Evaluator::new().eval_with(&parse(expr), &env)
+    );
+    assert_eq!(
+        eval::simplify("1 d 0 a $x c 0").unwrap(),
+        eval::parse("1 d 0 a $x c 0").unwrap()
+    );
+}
+
+#[test]
+fn derivatives() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let derive = |expr, variable| eval::derive_with_config(expr, variable, &standard);
+    let simplify = |expr| eval::simplify_with_config(expr, &standard).unwrap();
+
+    assert_eq!(
+        derive("price * (1 + rate) ^ years", "price").unwrap(),
+        simplify("(1 + rate) ^ years")
+    );
+    assert_eq!(
+        derive("3 * x ^ 2 + 2 * x - 7", "x").unwrap(),
+        simplify("3 * (2 * x) + 2")
+    );
+    assert_eq!(derive("sin(x)", "x").unwrap(), simplify("cos(x)"));
+    assert_eq!(derive("ln(y)", "x").unwrap(), simplify("0"));
+
+    let sensitivity = derive("100 * exp(-rate * years)", "rate").unwrap();
+    let env = Environment::new()
+        .with_variable("rate", 0.05)
+        .with_variable("years", 2.0);
+    let result = Evaluator::new().eval_with(&sensitivity, &env).unwrap();
+
+    assert!((result - -200.0 * (-0.1f64).exp()).abs() < 1e-9);
+
+    let error = derive("2 * pv(x)", "x").unwrap_err();
+
+    assert_eq!(error.to_string(), "Function `pv` has no known derivative");
+    assert_eq!(error.span(), eval::Span::new(4, 9));
+}
+
+#[test]
+fn printing() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let letters = eval::parse("2 a 3 c $price g 2 b $tax").unwrap();
+    let human = Printer::new(standard.clone()).unwrap().print(&letters);
+
+    assert_eq!(human, "(2 + 3) * price ^ 2 - tax");
+    assert_eq!(letters.to_string(), "2 a 3 c $price g 2 b $tax");
+
+    let formula = eval::parse_with_config("-x ^ 2 / (1 - y) * max(a, -3)", &standard).unwrap();
+    let encoded = Printer::default().print(&formula);
+
+    assert_eq!(encoded, "b$x g 2 d e1 b $y f c $max e$a, b3f");
+    assert_eq!(
+        eval::parse(&encoded).unwrap().ungrouped(),
+        formula.ungrouped()
+    );
+    assert_eq!(
+        Printer::new(standard)
+            .unwrap()
+            .with_parentheses(Parentheses::Full)
+            .print(&formula),
+        "((-(x ^ 2)) / (1 - y)) * max(a, -3)"
+    );
+}
+
+#[test]
+fn reverse_polish_notation() {
+    let evaluator = Evaluator::new();
+    let eval_rpn = |expr| eval::parse_rpn(expr).and_then(|expr| Ok(evaluator.eval(&expr)?));
+
+    assert_eq!(eval_rpn("2 3 a 4 c").unwrap(), 20.0);
+    assert_eq!(eval_rpn("10 2 8 c a 3 b").unwrap(), 23.0);
+    assert_eq!(eval_rpn("2 ~ 3 g").unwrap(), -8.0);
+    assert_eq!(eval_rpn("16 $sqrt e1f 1 5 $max e3f").unwrap(), 5.0);
+
+    let error = eval_rpn("1 2 a c").unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Expected 2 operands on the stack, but there are 1"
+    );
+    assert_eq!(error.span(), eval::Span::new(6, 7));
+
+    let error = eval_rpn("1 2 3 a").unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Expected single result, but 2 values are left on the stack"
+    );
+    assert_eq!(error.span(), eval::Span::new(0, 1));
+
+    let calculator = RpnParser::default().with_max_depth(4);
+
+    assert!(calculator.parse_str("1 2 3 4 a a a").is_ok());
+    assert_eq!(
+        calculator
+            .parse_str("1 2 3 4 5 a a a a")
+            .unwrap_err()
+            .to_string(),
+        "Stack has exceeded the limit of 4 values"
+    );
+
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let expr = eval::parse_with_config("-(price - 2) * max(tax, 3)", &standard).unwrap();
+    let postfix = Printer::new(standard.clone())
+        .unwrap()
+        .with_notation(Notation::Postfix)
+        .print(&expr);
+
+    assert_eq!(postfix, "price 2 - ~ tax 3 max(2) *");
+    assert_eq!(
+        eval::parse_rpn_with_config(&postfix, &standard).unwrap(),
+        expr.ungrouped()
+    );
+}
+
+#[test]
+fn streamed_input() {
+    let source = "1 a 2 c 3 a ".repeat(1_000) + "0";
+    let expr = eval::parse_reader(std::io::BufReader::new(source.as_bytes())).unwrap();
+
+    assert_eq!(expr, eval::parse(&source).unwrap());
+
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let expr = eval::parse_reader_with_config("2 * (3 + 4)".as_bytes(), &standard).unwrap();
+
+    assert_eq!(Evaluator::new().eval(&expr), Ok(14.0));
+
+    let error = eval::parse_reader("1 a 2 c € 3".as_bytes()).unwrap_err();
+
+    assert_eq!(error.to_string(), "Unknown token `€` in the stream");
+    assert_eq!(error.span(), eval::Span::new(8, 11));
+}
+
+#[test]
+fn number_literals() {
+    assert_expr_eq!("1.5E-3 c 2E+3", 3.0);
+    assert_expr_eq!("0x1F a 0B101", 36.0);
+    assert_expr_eq!("1_000_000 d 0x3E8", 1000.0);
+    assert_expr_eq!("0b1", -1.0);
+
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+
+    assert_expr_eq!("2.5e2 - 0xff + 1_0.0E-1", -4.0, standard);
+
+    let error = eval::parse("1 a 0x1G").unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Unable to parse number, `G` is not a digit of the base 16"
+    );
+    assert_eq!(error.span(), eval::Span::new(7, 8));
+
+    let error = eval::parse("1_000_ a 1").unwrap_err();
+
+    assert_eq!(
+        error.to_string(),
+        "Unable to parse number, digit separator should be between digits"
+    );
+    assert_eq!(error.span(), eval::Span::new(5, 6));
+}
+
+#[test]
+fn comments() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let source = "\
+// price of the order
+(100 + 20)\t/* shipping */
+\u{a0}* 1.5 // tax
+";
+
+    assert_expr_eq!(source, 180.0, standard);
+    assert_expr_eq!("2 /* plus */ a\n3 // five", 5.0);
+
+    let error = eval::parse("1 a 2 /* unfinished").unwrap_err();
+
+    assert_eq!(error.to_string(), "Comment is not closed");
+    assert_eq!(error.span(), eval::Span::new(6, 8));
+}
+
+#[test]
+fn conditions() {
+    assert_expr_eq!("2 a 3 > 4 ? 1 : 0", 1.0);
+    assert_expr_eq!("1 == 2 || 3 != 3 ? 1 : 2 < 3 ? 2 : 3", 2.0);
+    assert_eq!(
+        eval::eval_value("1 < 2 && !e3 >= 4f").unwrap(),
+        Value::Bool(true)
+    );
+
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let pricing = "total > 1000 || member == 1 ? total * 0.9 : total";
+    let price = |total, member| {
+        let env = Environment::new()
+            .with_variable("total", total)
+            .with_variable("member", member);
+
+        eval::eval_with_env_and_config(pricing, &env, &standard).unwrap()
+    };
+
+    assert_eq!(price(1200.0, 0.0), 1080.0);
+    assert_eq!(price(500.0, 1.0), 450.0);
+    assert_eq!(price(500.0, 0.0), 500.0);
+
+    let compiled = eval::compile_with_config(pricing, &standard).unwrap();
+
+    assert_eq!(compiled.eval(&[1200.0, 0.0]).unwrap(), 1080.0);
+
+    // division is skipped once the left operand decides the result
+    let env = Environment::new().with_variable("x", 0.0);
+    let result = eval::eval_value_with_env_and_config("x != 0 && 10 / x > 1", &env, &standard);
+
+    assert_eq!(result.unwrap(), Value::Bool(false));
+
+    let error = eval::eval("1 a e2 > 1f").unwrap_err();
+
+    assert_eq!(error.to_string(), "Expected number, but found boolean");
+    assert_eq!(error.span(), eval::Span::new(4, 11));
+
+    let error = eval::parse("$x ? 1").unwrap_err();
+
+    assert_eq!(error.to_string(), "Expected else branch of the conditional");
+    assert_eq!(error.span(), eval::Span::point(6));
+}
+
+#[test]
+fn checks() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let check = |expr| eval::check_with_config(expr, &["total", "member"], &standard);
+
+    assert_eq!(
+        check("total > 1000 || member == 1 ? total * 0.9 : total").unwrap(),
+        Type::Number
+    );
+    assert_eq!(check("min(total, 10) < 5").unwrap(), Type::Bool);
+
+    // every problem is reported, even the ones in the branch that is never taken
+    let source = "1 > 2 ? totl + sqrt(1, 2) : (member > 1) * avg(total)";
+    let error = check(source).unwrap_err();
+    let reports = error
+        .diagnostics(source)
+        .iter()
+        .map(|report| (report.message().to_owned(), report.location()))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        reports,
+        [
+            ("Unknown variable `totl`".to_owned(), (1, 9)),
+            (
+                "Function `sqrt` expects 1 argument(s), but 2 were provided".to_owned(),
+                (1, 16)
+            ),
+            ("Expected number, but found boolean".to_owned(), (1, 29)),
+            ("Unknown function `avg`".to_owned(), (1, 44)),
+        ]
+    );
+    assert_eq!(
+        error.to_string(),
+        "Unknown variable `totl`, and 3 more problem(s)"
+    );
+
+    // parser errors are reported alone
+    assert_eq!(check("1 +").unwrap_err().diagnostics("1 +").len(), 1);
+    assert!(eval::check("x + 1", &[]).is_err());
+    assert!(eval::check("1 a 2", &[]).is_ok());
+}
+
+#[test]
+fn units() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let quantity = |expr, unit| {
+        eval::eval_quantity_with_config(expr, unit, Units::builtin(), &standard)
+            .map(|result| result.to_string())
+            .map_err(|err| err.to_string())
+    };
+
+    assert_eq!(quantity("3[USD/oz] * 2[oz]", None), Ok("6 USD".to_owned()));
+    assert_eq!(quantity("1[kg] + 500[g]", None), Ok("1.5 kg".to_owned()));
+    assert_eq!(
+        quantity("3[lb] - 8[oz]", Some("oz")),
+        Ok("40 oz".to_owned())
+    );
+    assert_eq!(
+        quantity("2[USD/kg] * 1500[g]", Some("USD")),
+        Ok("3 USD".to_owned())
+    );
+    assert_eq!(
+        quantity("(2[m])[cm] ^ 2", None),
+        Ok("40000 cm^2".to_owned())
+    );
+    assert_eq!(
+        quantity("500[g] + 2[USD]", None).unwrap_err(),
+        "incompatible units `g` and `USD`"
+    );
+    assert_eq!(
+        quantity("2[kg]", Some("USD")).unwrap_err(),
+        "incompatible units `kg` and `USD`"
+    );
+    assert_eq!(
+        quantity("2[parsec]", None).unwrap_err(),
+        "unknown unit `parsec`"
+    );
+
+    // simplified expression keeps the error of adding the dimensionless zero
+    let simplified = eval::simplify_with_config("3[g] + 0", &standard).unwrap();
+
+    assert_eq!(
+        simplified,
+        eval::parse_with_config("3[g] + 0", &standard).unwrap()
+    );
+    assert_eq!(
+        quantity("3[g] + 0", None).unwrap_err(),
+        "incompatible units `g` and `1`"
+    );
+
+    let units = Units::builtin().with_unit("EUR", 1.08, "USD").unwrap();
+    let price = eval::eval_quantity_with_config("10[EUR]", Some("USD"), units, &standard);
+
+    assert_eq!(price.unwrap().to_string(), "10.8 USD");
+
+    // plain numbers have no units
+    let error = eval::eval_with_config("5[kg]", &standard).unwrap_err();
+
+    assert!(matches!(
+        error,
+        ExprError::EvalError(EvalError::CalculationError {
+            error: CalculationError::UnitsUnsupported,
+            ..
+        })
+    ));
+    assert_eq!(
+        eval::eval_quantity("2[kg] a 300[g]", Some("g"))
+            .unwrap()
+            .to_string(),
+        "2300 g"
+    );
+}
+
+#[test]
+fn complex_numbers() {
+    let standard = ParserConfig::standard().with_dialect(Dialect::standard());
+    let complex = |expr, form| {
+        eval::eval_complex_with_config(expr, &standard)
+            .map(|result| result.format(form))
+            .map_err(|err| err.to_string())
+    };
+    let rectangular = |expr| complex(expr, ComplexForm::Rectangular);
+
+    assert_eq!(rectangular("sqrt(-1)"), Ok("1i".to_owned()));
+    assert_eq!(rectangular("(1 + 2i) * (3 - 4i)"), Ok("11 + 2i".to_owned()));
+    assert_eq!(
+        rectangular("(1 + 2i) / (3 - 4i)"),
+        Ok("-0.2 + 0.4i".to_owned())
+    );
+    assert_eq!(rectangular("(2i) ^ 2 - 2i"), Ok("-4 - 2i".to_owned()));
+    assert_eq!(
+        rectangular("-abs(3 + 4i) + re(2 - 1i)"),
+        Ok("-3".to_owned())
+    );
+    assert_eq!(
+        rectangular("im(conj(3 + 4i)) + ln(-1) / 3.141592653589793i"),
+        Ok("-3".to_owned())
+    );
+    assert_eq!(rectangular("1i == 1i ? 2 : 3"), Ok("2".to_owned()));
+    assert_eq!(
+        complex("-4", ComplexForm::Polar),
+        Ok("4∠3.141592653589793".to_owned())
+    );
+    assert_eq!(
+        complex("sqrt(-4)", ComplexForm::Polar),
+        Ok("2∠1.5707963267948966".to_owned())
+    );
+    assert_eq!(
+        rectangular("5 % 2i").unwrap_err(),
+        "operator Mod got non-real operand"
+    );
+    assert_eq!(rectangular("1i / 0").unwrap_err(), "division by zero");
+    assert_eq!(
+        eval::eval_complex("e1 a 2if c $sqrt eb4f").unwrap(),
+        Complex::new(-4.0, 2.0)
+    );
+
+    // real evaluation rejects the imaginary literals
+    let error = eval::eval_with_config("1 + 1i", &standard).unwrap_err();
+
+    assert!(matches!(
+        error,
+        ExprError::EvalError(EvalError::CalculationError {
+            error: CalculationError::ComplexUnsupported,
+            ..
+        })
+    ));
+    assert!(eval::compile("1 a 2i").is_err());
+}
+
+#[test]
+fn strict_floats() {
+    let strict = |expr, env: &Environment| {
+        let expr = eval::parse_with_config(
+            expr,
+            &ParserConfig::standard().with_dialect(Dialect::standard()),
+        )
+        .unwrap();
+
+        Evaluator::new()
+            .with_context(FloatPolicy::Strict)
+            .eval_with(&expr, env)
+            .map_err(|err| err.to_string())
+    };
+    let env = Environment::new()
+        .with_variable("big", 1e300)
+        .with_variable("inf", f64::INFINITY);
+
+    assert_eq!(strict("big * 2", &env), Ok(2e300));
+    assert_eq!(
+        strict("1 + big * big", &env).unwrap_err(),
+        "operator Mul overflowed to infinity"
+    );
+    assert_eq!(
+        strict("1 / big / big", &env).unwrap_err(),
+        "operator Div underflowed"
+    );
+    assert_eq!(
+        strict("inf", &env).unwrap_err(),
+        "variable overflowed to infinity"
+    );
+    assert_eq!(
+        strict("1e400 * 0", &env).unwrap_err(),
+        "literal overflowed to infinity"
+    );
+    assert_eq!(
+        strict("exp(big)", &env).unwrap_err(),
+        "function `exp` overflowed to infinity"
+    );
+    assert_eq!(eval::eval_with("$big c $big", &env).unwrap(), f64::INFINITY);
+}
+
+#[test]
+fn decimals() {
+    let decimal = |expr, context| eval::eval_decimal(expr, context).unwrap().to_string();
+    let cents = DecimalContext::default();
+
+    assert_eq!(decimal("1 d 3", cents), "0.33");
+    assert_eq!(
+        decimal("2 d 3", cents.with_rounding(Rounding::Down)),
+        "0.66"
+    );
+    assert_eq!(decimal("1 d 8", cents), "0.12");
+    assert_eq!(
+        decimal("1 d 8", cents.with_rounding(Rounding::HalfUp)),
+        "0.13"
+    );
+    assert_eq!(
+        decimal("b1 d 3", cents.with_rounding(Rounding::Up)),
+        "-0.34"
+    );
+    assert_eq!(decimal("0.1 a 0.2", cents.with_scale(4)), "0.3000");
+    assert_eq!(decimal("19.99 c 3", cents), "59.97");
+    assert_eq!(
+        decimal(
+            "$round e1.005, 2f",
+            cents.with_scale(4).with_rounding(Rounding::HalfUp)
+        ),
+        "1.0100"
+    );
+    assert_eq!(decimal("$round e1.005, 2f", cents.with_scale(4)), "1.0000");
+    assert_eq!(
+        decimal("$max e0.1, 0.3 b 0.2f", cents.with_scale(4)),
+        "0.1000"
+    );
+    assert_eq!(decimal("$floor eb2.5f", cents), "-3.00");
+    assert_eq!(decimal("1.00 g 20000000", cents), "1.00");
+    assert_eq!(
+        decimal("12345678901234567.89", cents),
+        "12345678901234567.89"
+    );
+    assert_eq!(
+        eval::eval_decimal("10 g 40", cents)
+            .unwrap_err()
+            .to_string(),
+        "result is too large"
+    );
+}
+
+/// Integers modulo the number set by the context
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Modular(u64);
+
+#[derive(Clone)]
+struct Modulus(u64);
+
+impl Default for Modulus {
+    fn default() -> Self {
+        Self(12)
+    }
+}
+
+impl Numeric for Modular {
+    type Context = Modulus;
+
+    fn from_number(
+        number: &eval::tokens::Number,
+        modulus: &Modulus,
+    ) -> Result<Self, CalculationError> {
+        Self::from_f64(number.to_f64(), modulus)
+    }
+
+    fn from_f64(value: f64, modulus: &Modulus) -> Result<Self, CalculationError> {
+        if value.fract() != 0.0 {
+            return Err(CalculationError::Inexact);
+        }
+
+        Ok(Self((value as i64).rem_euclid(modulus.0 as i64) as u64))
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.0 as f64
+    }
+
+    fn neg(self, modulus: &Modulus) -> Result<Self, CalculationError> {
+        Ok(Self((modulus.0 - self.0) % modulus.0))
+    }
+
+    fn add(self, other: Self, modulus: &Modulus) -> Result<Self, CalculationError> {
+        Ok(Self((self.0 + other.0) % modulus.0))
+    }
+
+    fn sub(self, other: Self, modulus: &Modulus) -> Result<Self, CalculationError> {
+        self.add(other.neg(modulus)?, modulus)
+    }
+
+    fn mul(self, other: Self, modulus: &Modulus) -> Result<Self, CalculationError> {
+        Ok(Self(self.0 * other.0 % modulus.0))
+    }
+
+    fn div(self, other: Self, modulus: &Modulus) -> Result<Self, CalculationError> {
+        (0..modulus.0)
+            .find(|candidate| candidate * other.0 % modulus.0 == self.0)
+            .map(Self)
+            .ok_or(CalculationError::ZeroDivision)
+    }
+}
+
+#[test]
+fn numeric_backends() {
+    let parse = |expr| eval::parse(expr).unwrap();
+
+    assert_eq!(Evaluator::<i64>::default().eval(&parse("7 d 2 c 2")), Ok(6));
+    assert_eq!(
+        Evaluator::<i64>::default().eval(&parse("9223372036854775807 a 1")),
+        Err(EvalError::CalculationError {
+            error: CalculationError::Overflow,
+            span: eval::Span::new(0, 23)
+        })
+    );
+    assert_eq!(
+        Evaluator::<i64>::default()
+            .eval(&parse("2.5 c 2"))
+            .unwrap_err()
+            .to_string(),
+        "number can't be represented exactly"
+    );
+
+    let clock = Evaluator::<Modular>::default();
+
+    assert_eq!(clock.eval(&parse("9 a 5")), Ok(Modular(2)));
+    assert_eq!(clock.eval(&parse("b1")), Ok(Modular(11)));
+    assert_eq!(clock.eval(&parse("2 g 4")), Ok(Modular(4)));
+
+    let modulo_7 = Evaluator::<Modular>::default().with_context(Modulus(7));
+
+    assert_eq!(modulo_7.eval(&parse("3 d 5")), Ok(Modular(2)));
+    assert_eq!(modulo_7.eval(&parse("$max e1, 9f")), Ok(Modular(2)));
+}
@@ -10,4 +10,14 @@ macro_rules! assert_expr_eq {
             $expr, $expect, result,
         );
     };
+
+    ($expr: expr, $expect: expr, $config: expr) => {
+        let result = eval::eval_with_config($expr, &$config).unwrap();
+
+        assert_eq!(
+            result, $expect,
+            "\nexpected expression \"{}\" to equal \"{:?}\", but got \"{:?}\"",
+            $expr, $expect, result,
+        );
+    };
 }